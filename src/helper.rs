@@ -1,2 +1,11 @@
+pub mod base64;
 pub mod bytes;
 pub mod enums;
+pub mod escape;
+pub mod file;
+pub mod percent;
+pub mod punycode;
+pub mod random;
+pub mod regex;
+pub mod syntax;
+pub mod xml;
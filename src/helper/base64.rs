@@ -0,0 +1,87 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` as standard, padded base64.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                output.push(ALPHABET[((triple >> (18 - 6 * index)) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// Decodes standard, padded base64.
+///
+/// Returns [`None`] if `slice` contains characters outside the base64 alphabet, isn't padded to a
+/// multiple of 4 characters, or isn't canonical because the bits after the last byte aren't zero,
+/// so each value has only one encoding.
+pub fn decode(slice: &str) -> Option<Vec<u8>> {
+    if !slice.len().is_multiple_of(4) {
+        return None
+    }
+    let slice = slice.strip_suffix("==").or_else(|| slice.strip_suffix('=')).unwrap_or(slice);
+    let mut output = Vec::with_capacity(slice.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in slice.bytes() {
+        let value = ALPHABET.iter().position(|character| *character == byte)? as u32;
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    match buffer {
+        0 => Some(output),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The test vectors in RFC 4648 section 10.
+    const VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn encodes() {
+        for (decoded, encoded) in VECTORS {
+            assert_eq!(encode(decoded.as_bytes()), encoded);
+        }
+    }
+
+    #[test]
+    fn decodes() {
+        for (decoded, encoded) in VECTORS {
+            assert_eq!(decode(encoded).as_deref(), Some(decoded.as_bytes()), "{encoded}");
+        }
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(&encode(&bytes)), Some(bytes));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for invalid in ["Zg", "Zm8", "Zh==", "Zm9=", "Z===", "====", "Zg==Zg==", "=Zg=", "Zm9v!A==", "Zm 9", "Zm9v-_=="] {
+            assert_eq!(decode(invalid), None, "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,38 @@
+/// Escapes `slice` for use in HTML or XML text and quoted attribute values.
+pub fn html(slice: &str) -> String {
+    let mut output = String::with_capacity(slice.len());
+    for character in slice.chars() {
+        match character {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            character => output.push(character),
+        }
+    }
+    output
+}
+
+/// Escapes `slice` as a JSON string, including the surrounding quotes.
+///
+/// `<`, `>` and `&` are also escaped, so the string is safe to embed in HTML.
+pub fn json_string(slice: &str) -> String {
+    let mut output = String::with_capacity(slice.len() + 2);
+    output.push('"');
+    for character in slice.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            character if character.is_control() || matches!(character, '<' | '>' | '&') => {
+                output.push_str(&format!("\\u{:04x}", character as u32));
+            },
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+    output
+}
@@ -0,0 +1,80 @@
+use std::{fs, io::{self, Write}, path::{Path, PathBuf}};
+
+use super::random;
+
+/// Gets a path for a temporary file next to `path`, so it can be renamed to `path` atomically.
+///
+/// The filename starts with `.`, so it is hidden from directory listings.
+pub fn temporary_path_for(path: &Path) -> PathBuf {
+    let filename = path.file_name().map_or("file".into(), |filename| filename.to_string_lossy());
+    path.with_file_name(format!(".{filename}.{:016x}.tmp", random::random_u64()))
+}
+
+/// Writes `bytes` to a temporary file and renames it to `path`, so readers never see a partly written file.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temporary_path = temporary_path_for(path);
+    let result = fs::File::create_new(&temporary_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary_path, path));
+    if result.is_err() {
+        _ = fs::remove_file(&temporary_path);
+    }
+    result
+}
+
+/// Copies the file at `from` to a temporary file and renames it to `to`.
+pub fn copy_atomically(from: &Path, to: &Path) -> io::Result<()> {
+    let temporary_path = temporary_path_for(to);
+    let result = fs::copy(from, &temporary_path).and_then(|_| fs::rename(&temporary_path, to));
+    if result.is_err() {
+        _ = fs::remove_file(&temporary_path);
+    }
+    result
+}
+
+/// Returns `true` if `path`, or the part of it that exists, is inside `root` once symbolic links are followed.
+pub fn is_confined(root: &Path, path: &Path) -> bool {
+    let Ok(root) = root.canonicalize() else {
+        return false
+    };
+    let mut ancestor = path;
+    loop {
+        match ancestor.canonicalize() {
+            Ok(canonical_path) => return canonical_path.starts_with(&root),
+            Err(_) => match ancestor.parent() {
+                None => return false,
+                Some(parent) => ancestor = parent,
+            },
+        }
+    }
+}
+
+/// An empty directory to test in, which is removed when it is dropped.
+#[cfg(test)]
+pub(crate) struct TestDirectory(pub PathBuf);
+
+#[cfg(test)]
+impl TestDirectory {
+    pub(crate) fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("webserver-test-{}", random::uuid()));
+        fs::create_dir_all(&path).expect("test directory should be created");
+        TestDirectory(path)
+    }
+
+    /// Writes a file at the relative `path`, creating its parent directories.
+    pub(crate) fn write(&self, path: &str, contents: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().expect("path should have a parent")).expect("directory should be created");
+        fs::write(path, contents).expect("file should be written");
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,109 @@
+/// Decodes `%XX` escapes in `slice`. If `plus_as_space` is `true`, `+` is also decoded as a space,
+/// as it is in query strings and form bodies.
+///
+/// Returns [`None`] if an escape isn't followed by two hex digits, or if the decoded bytes aren't UTF8.
+pub fn decode(slice: &str, plus_as_space: bool) -> Option<String> {
+    String::from_utf8(decode_bytes(slice.as_bytes(), plus_as_space)?).ok()
+}
+
+/// Decodes `%XX` escapes in `bytes`, which may decode to non-UTF8 bytes.
+pub fn decode_bytes(bytes: &[u8], plus_as_space: bool) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                let high = hex_value(*bytes.get(index + 1)?)?;
+                let low = hex_value(*bytes.get(index + 2)?)?;
+                output.push(high << 4 | low);
+                index += 3;
+                continue;
+            },
+            b'+' if plus_as_space => output.push(b' '),
+            byte => output.push(byte),
+        }
+        index += 1;
+    }
+    Some(output)
+}
+
+/// Encodes every byte of `slice` that `keep` returns `false` for as a `%XX` escape.
+/// If `space_as_plus` is `true`, spaces are encoded as `+` instead.
+pub fn encode(slice: &str, space_as_plus: bool, keep: fn(u8) -> bool) -> String {
+    let hex_digits = b"0123456789ABCDEF";
+    let mut output = String::with_capacity(slice.len());
+    for byte in slice.bytes() {
+        if keep(byte) {
+            output.push(byte as char);
+        } else if byte == b' ' && space_as_plus {
+            output.push('+');
+        } else {
+            output.push('%');
+            output.push(hex_digits[(byte >> 4) as usize] as char);
+            output.push(hex_digits[(byte & 0xf) as usize] as char);
+        }
+    }
+    output
+}
+
+/// Normalizes the `%XX` escapes in `slice` by decoding the ones for unreserved characters and
+/// uppercasing the hex digits of the rest, as described by RFC 3986.
+///
+/// Returns [`None`] if an escape isn't followed by two hex digits.
+pub fn normalize(slice: &str) -> Option<String> {
+    let hex_digits = b"0123456789ABCDEF";
+    let bytes = slice.as_bytes();
+    let mut output = String::with_capacity(slice.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'%' {
+            let character = slice[index..].chars().next().expect("`index` should be on a character boundary");
+            output.push(character);
+            index += character.len_utf8();
+            continue;
+        }
+        let high = hex_value(*bytes.get(index + 1)?)?;
+        let low = hex_value(*bytes.get(index + 2)?)?;
+        let byte = high << 4 | low;
+        if is_unreserved(byte) {
+            output.push(byte as char);
+        } else {
+            output.push('%');
+            output.push(hex_digits[high as usize] as char);
+            output.push(hex_digits[low as usize] as char);
+        }
+        index += 3;
+    }
+    Some(output)
+}
+
+/// Returns `true` for the unreserved characters, which never need to be encoded.
+pub fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// Returns `true` for the characters that can be left unencoded in a path segment.
+pub fn is_path_segment_character(byte: u8) -> bool {
+    is_unreserved(byte) || matches!(byte, b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@')
+}
+
+/// Returns `true` for the characters that can be left unencoded in a query name or value.
+///
+/// This is stricter than RFC 3986 requires, so that the name and value delimiters are always encoded.
+pub fn is_query_component_character(byte: u8) -> bool {
+    is_unreserved(byte) || matches!(byte, b'!' | b'$' | b'\'' | b'(' | b')' | b'*' | b',' | b';' | b':' | b'@' | b'/' | b'?')
+}
+
+/// Returns `true` for the characters that can be left unencoded in a whole query or fragment.
+pub fn is_fragment_character(byte: u8) -> bool {
+    is_path_segment_character(byte) || matches!(byte, b'/' | b'?')
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
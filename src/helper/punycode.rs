@@ -0,0 +1,232 @@
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+const ACE_PREFIX: &str = "xn--";
+
+/// Encodes `label` as Punycode (RFC 3492), without the `xn--` prefix.
+///
+/// Returns [`None`] if the encoding overflows.
+pub fn encode(label: &str) -> Option<String> {
+    let code_points: Vec<u32> = label.chars().map(|character| character as u32).collect();
+    let mut output: String = label.chars().filter(|character| character.is_ascii()).collect();
+    let basic_count = output.len() as u32;
+    let mut handled_count = basic_count;
+    if basic_count > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    while (handled_count as usize) < code_points.len() {
+        let next_code_point = *code_points.iter().filter(|code_point| **code_point >= n).min()?;
+        delta = delta.checked_add((next_code_point - n).checked_mul(handled_count + 1)?)?;
+        n = next_code_point;
+        for code_point in &code_points {
+            if *code_point < n {
+                delta = delta.checked_add(1)?;
+            }
+            if *code_point != n {
+                continue;
+            }
+            let mut q = delta;
+            let mut k = BASE;
+            loop {
+                let t = threshold(k, bias);
+                if q < t {
+                    break;
+                }
+                output.push(digit_to_char(t + (q - t) % (BASE - t)));
+                q = (q - t) / (BASE - t);
+                k += BASE;
+            }
+            output.push(digit_to_char(q));
+            bias = adapt(delta, handled_count + 1, handled_count == basic_count);
+            delta = 0;
+            handled_count += 1;
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+/// Decodes a Punycode `label`, without the `xn--` prefix.
+///
+/// Returns [`None`] if it isn't valid Punycode.
+pub fn decode(label: &str) -> Option<String> {
+    let basic_delimiter = '-';
+    let (basic, extended) = match label.rfind(basic_delimiter) {
+        None => ("", label),
+        Some(index) => (&label[..index], &label[(index + 1)..]),
+    };
+    if !basic.is_ascii() {
+        return None
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = extended.chars().peekable();
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = char_to_digit(digits.next()?)?;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let t = threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let length = output.len() as u32 + 1;
+        bias = adapt(i - old_i, length, old_i == 0);
+        n = n.checked_add(i / length)?;
+        i %= length;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+/// Converts a domain name to its ASCII form, lowercasing it and encoding each label that
+/// isn't ASCII as `xn--` followed by its Punycode.
+///
+/// Labels are only lowercased, not mapped with the full UTS 46 tables.
+pub fn domain_to_ascii(domain: &str) -> Option<String> {
+    let label_delimiter = ".";
+    let labels: Option<Vec<String>> = domain.split(label_delimiter)
+        .map(|label| {
+            let label = label.to_lowercase();
+            match label.is_ascii() {
+                true => Some(label),
+                false => Some(format!("{ACE_PREFIX}{}", encode(&label)?)),
+            }
+        })
+        .collect();
+    Some(labels?.join(label_delimiter))
+}
+
+/// Converts a domain name in ASCII form to Unicode, decoding each `xn--` label.
+pub fn domain_to_unicode(domain: &str) -> Option<String> {
+    let label_delimiter = ".";
+    let labels: Option<Vec<String>> = domain.split(label_delimiter)
+        .map(|label| match label.get(..ACE_PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(ACE_PREFIX) => decode(&label[ACE_PREFIX.len()..]),
+            _ => Some(label.to_owned()),
+        })
+        .collect();
+    Some(labels?.join(label_delimiter))
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        T_MIN
+    } else if k >= bias + T_MAX {
+        T_MAX
+    } else {
+        k - bias
+    }
+}
+
+fn adapt(delta: u32, length: u32, first_time: bool) -> u32 {
+    let mut delta = match first_time {
+        true => delta / DAMP,
+        false => delta / 2,
+    };
+    delta += delta / length;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+}
+
+fn digit_to_char(digit: u32) -> char {
+    match digit {
+        0..=25 => (b'a' + digit as u8) as char,
+        _ => (b'0' + (digit - 26) as u8) as char,
+    }
+}
+
+fn char_to_digit(character: char) -> Option<u32> {
+    match character {
+        'a'..='z' => Some(character as u32 - 'a' as u32),
+        'A'..='Z' => Some(character as u32 - 'A' as u32),
+        '0'..='9' => Some(character as u32 - '0' as u32 + 26),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Some of the sample strings in RFC 3492 section 7.1.
+    const SAMPLES: [(&str, &str); 10] = [
+        ("\u{644}\u{64A}\u{647}\u{645}\u{627}\u{628}\u{62A}\u{643}\u{644}\u{645}\u{648}\u{634}\u{639}\u{631}\u{628}\u{64A}\u{61F}", "egbpdaj6bu4bxfgehfvwxn"),
+        ("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye"),
+        ("他們爲什麽不說中文", "ihqwctvzc91f659drss3x8bo0yb"),
+        ("Pročprostěnemluvíčesky", "Proprostnemluvesky-uyb24dma41a"),
+        ("למההםפשוטלאמדבריםעברית", "4dbcagdahymbxekheh6e0a7fei0b"),
+        ("почемужеонинеговорятпорусски", "b1abfaaepdrnnbgefbadotcwatmq2g4l"),
+        ("3年B組金八先生", "3B-ww4c5e180e575a65lsy2b"),
+        ("安室奈美恵-with-SUPER-MONKEYS", "-with-SUPER-MONKEYS-pc58ag80a8qai00g7n9n"),
+        ("そのスピードで", "d9juau41awczczp"),
+        ("-> $1.00 <-", "-> $1.00 <--"),
+    ];
+
+    #[test]
+    fn encodes_samples() {
+        for (unicode, punycode) in SAMPLES {
+            assert_eq!(encode(unicode).as_deref(), Some(punycode), "{unicode}");
+        }
+    }
+
+    #[test]
+    fn decodes_samples() {
+        for (unicode, punycode) in SAMPLES {
+            assert_eq!(decode(punycode).as_deref(), Some(unicode), "{punycode}");
+        }
+        // Digits are case-insensitive, as in the sample's mixed case form
+        assert_eq!(decode("b1abfaaepdrnnbgefbaDotcwatmq2g4l").as_deref(), Some(SAMPLES[5].0));
+    }
+
+    #[test]
+    fn round_trips() {
+        for label in ["bücher", "ü", "日本語", "a\u{10FFFF}b", "mañana-", ""] {
+            let encoded = encode(label).expect("label should be encoded");
+            assert_eq!(decode(&encoded).as_deref(), Some(label), "{label}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for invalid in ["!", "ü-a", "abc-a!", "a-9", "zzzzzzzzzzzzzzz", "99999999999"] {
+            assert_eq!(decode(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        let label = format!("{}\u{10FFFF}", "a".repeat(5_000));
+        assert_eq!(encode(&label), None);
+    }
+
+    #[test]
+    fn converts_domains() {
+        assert_eq!(domain_to_ascii("Bücher.Example").as_deref(), Some("xn--bcher-kva.example"));
+        assert_eq!(domain_to_ascii("example.com").as_deref(), Some("example.com"));
+        assert_eq!(domain_to_unicode("XN--bcher-kva.example").as_deref(), Some("bücher.example"));
+        assert_eq!(domain_to_unicode("xn--!.example"), None);
+    }
+}
@@ -0,0 +1,22 @@
+use std::{collections::hash_map::RandomState, hash::{BuildHasher, Hasher}, sync::atomic::{AtomicU64, Ordering}, time::SystemTime};
+
+/// Gets an unpredictable number, for tokens and temporary filenames. It isn't suitable for keys.
+///
+/// Each [`RandomState`] is seeded with random keys, which are mixed with the time and a counter
+/// so that numbers aren't repeated.
+pub fn random_u64() -> u64 {
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNT.fetch_add(1, Ordering::Relaxed));
+    if let Ok(duration) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(duration.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Gets a random (version 4) UUID, such as `f81d4fae-7dec-41d0-a765-00a0c91e6bf6`.
+pub fn uuid() -> String {
+    let high = (random_u64() & 0xffff_ffff_ffff_0fff) | 0x0000_0000_0000_4000;
+    let low = (random_u64() & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+    format!("{:08x}-{:04x}-{:04x}-{:04x}-{:012x}", high >> 32, (high >> 16) & 0xffff, high & 0xffff, low >> 48, low & 0xffff_ffff_ffff)
+}
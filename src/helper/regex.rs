@@ -0,0 +1,595 @@
+use std::str::FromStr;
+
+/// A backtracking regular expression, for matching paths and field values in configuration.
+///
+/// It supports literals, `.`, classes like `[a-z]` and `[^/]`, the escapes `\d`, `\w`, `\s` (and their
+/// negations), anchors `^` and `$`, capturing groups `(...)`, non-capturing groups `(?:...)`,
+/// alternation `|` and the quantifiers `*`, `+`, `?` and `{n,m}`, which can be made lazy with a
+/// trailing `?`. A leading `(?i)` makes the whole expression case-insensitive.
+///
+/// A match can start anywhere in the text, unless it is anchored with `^`. Matching gives up and
+/// reports no match if it would take more than [`MAXIMUM_STEPS`], so a pattern can't stall the server.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    pattern: String,
+    program: Vec<Instruction>,
+    group_count: usize,
+    case_insensitive: bool,
+}
+
+/// The most instructions a compiled expression can have, which limits how far a bounded
+/// repetition like `{n,m}` can be expanded.
+const MAXIMUM_INSTRUCTIONS: usize = 4096;
+/// The most instructions that can be run to find a match before giving up.
+const MAXIMUM_STEPS: usize = 1_000_000;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Character(char),
+    AnyCharacter,
+    Class { ranges: Vec<(char, char)>, negated: bool },
+    Start,
+    End,
+    Group { index: Option<usize>, alternatives: Vec<Vec<Node>> },
+    Repeat { node: Box<Node>, minimum: usize, maximum: Option<usize>, lazy: bool },
+}
+
+/// A step of a compiled expression, which continues with the next instruction unless it jumps or fails.
+#[derive(Clone, Debug)]
+enum Instruction {
+    /// Matches one character with a [`Node::Character`], [`Node::AnyCharacter`] or [`Node::Class`].
+    Consume(Node),
+    Start,
+    End,
+    Jump(usize),
+    /// Continues at the first index, then at the second if that doesn't lead to a match.
+    Split(usize, usize),
+    GroupStart(usize),
+    GroupEnd(usize),
+    Match,
+}
+
+/// Work saved on the stack for when the current path doesn't lead to a match.
+enum Backtrack {
+    Try { program_index: usize, position: usize },
+    RestoreStart { index: usize, start: Option<usize> },
+    RestoreCapture { index: usize, capture: Option<(usize, usize)> },
+}
+
+/// The byte range of each group in a match, where group `0` is the whole match.
+type Captures = Vec<Option<(usize, usize)>>;
+
+impl Regex {
+    /// Creates a regular expression that matches the whole of a glob pattern.
+    ///
+    /// `*` matches anything except `/`, `**` matches anything, `?` matches one character except `/`
+    /// and `[...]` is a class. Each wildcard is a capturing group, so it can be substituted.
+    ///
+    /// Returns [`None`] if a class isn't valid.
+    pub fn from_glob(glob: &str) -> Option<Self> {
+        let mut pattern = String::from("^");
+        let mut characters = glob.chars().peekable();
+        while let Some(character) = characters.next() {
+            match character {
+                '*' if characters.peek() == Some(&'*') => {
+                    characters.next();
+                    pattern.push_str("(.*)");
+                },
+                '*' => pattern.push_str("([^/]*)"),
+                '?' => pattern.push_str("([^/])"),
+                '[' => {
+                    pattern.push('[');
+                    for character in characters.by_ref() {
+                        match character {
+                            '!' if pattern.ends_with('[') => pattern.push('^'),
+                            '\\' => pattern.push_str("\\\\"),
+                            _ => pattern.push(character),
+                        }
+                        if character == ']' {
+                            break;
+                        }
+                    }
+                },
+                _ => {
+                    if "\\.+()|{}^$".contains(character) {
+                        pattern.push('\\');
+                    }
+                    pattern.push(character);
+                },
+            }
+        }
+        pattern.push('$');
+        pattern.parse::<Regex>().ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find_captures(text).is_some()
+    }
+
+    /// Gets the text of each group in the first match, where group `0` is the whole match.
+    ///
+    /// Groups that didn't take part in the match are [`None`].
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Vec<Option<&'t str>>> {
+        let captures = self.find_captures(text)?;
+        Some(captures.into_iter().map(|capture| capture.map(|(start, end)| &text[start..end])).collect())
+    }
+
+    /// Runs the program from each start position in turn, backtracking with an explicit stack.
+    ///
+    /// Each instruction is only tried once at each position, as whether the rest of the program
+    /// matches from there doesn't depend on how it was reached, so a match takes at most
+    /// `program.len() * (text.len() + 1)` steps. Returns [`None`] if it would take more than
+    /// [`MAXIMUM_STEPS`], so a pathological pattern fails instead of stalling the server.
+    fn find_captures(&self, text: &str) -> Option<Captures> {
+        let positions = text.len() + 1;
+        let mut visited = vec!(0u64; (self.program.len() * positions).div_ceil(u64::BITS as usize));
+        let mut steps = 0;
+        let mut stack = Vec::new();
+        let start_indexes = text.char_indices().map(|(index, _)| index).chain([text.len()]);
+        for start_index in start_indexes {
+            let mut captures: Captures = vec!(None; self.group_count + 1);
+            let mut group_starts: Vec<Option<usize>> = vec!(None; self.group_count + 1);
+            stack.push(Backtrack::Try { program_index: 0, position: start_index });
+            while let Some(backtrack) = stack.pop() {
+                let (mut program_index, mut position) = match backtrack {
+                    Backtrack::Try { program_index, position } => (program_index, position),
+                    Backtrack::RestoreStart { index, start } => {
+                        group_starts[index] = start;
+                        continue;
+                    },
+                    Backtrack::RestoreCapture { index, capture } => {
+                        captures[index] = capture;
+                        continue;
+                    },
+                };
+                loop {
+                    let visited_index = program_index * positions + position;
+                    let (word, bit) = (visited_index / u64::BITS as usize, 1 << (visited_index % u64::BITS as usize));
+                    if visited[word] & bit != 0 {
+                        break;
+                    }
+                    visited[word] |= bit;
+                    steps += 1;
+                    if steps > MAXIMUM_STEPS {
+                        return None
+                    }
+                    match &self.program[program_index] {
+                        Instruction::Match => {
+                            captures[0] = Some((start_index, position));
+                            return Some(captures)
+                        },
+                        Instruction::Consume(node) => match text[position..].chars().next() {
+                            Some(character) if self.character_matches(node, character) => position += character.len_utf8(),
+                            _ => break,
+                        },
+                        Instruction::Start if position != 0 => break,
+                        Instruction::End if position != text.len() => break,
+                        Instruction::Start | Instruction::End => (),
+                        Instruction::Jump(target) => {
+                            program_index = *target;
+                            continue;
+                        },
+                        Instruction::Split(first, second) => {
+                            stack.push(Backtrack::Try { program_index: *second, position });
+                            program_index = *first;
+                            continue;
+                        },
+                        Instruction::GroupStart(index) => {
+                            stack.push(Backtrack::RestoreStart { index: *index, start: group_starts[*index] });
+                            group_starts[*index] = Some(position);
+                        },
+                        Instruction::GroupEnd(index) => {
+                            stack.push(Backtrack::RestoreCapture { index: *index, capture: captures[*index] });
+                            let start = group_starts[*index].expect("`group_starts[index]` should be `Some`");
+                            captures[*index] = Some((start, position));
+                        },
+                    }
+                    program_index += 1;
+                }
+            }
+        }
+        None
+    }
+
+    fn character_matches(&self, node: &Node, character: char) -> bool {
+        let fold = |character: char| match self.case_insensitive {
+            true => character.to_ascii_lowercase(),
+            false => character,
+        };
+        match node {
+            Node::Character(expected) => fold(*expected) == fold(character),
+            Node::AnyCharacter => character != '\n',
+            Node::Class { ranges, negated } => {
+                let in_range = |character: char| ranges.iter().any(|(start, end)| *start <= character && character <= *end);
+                let in_class = in_range(character)
+                    || (self.case_insensitive && (in_range(character.to_ascii_lowercase()) || in_range(character.to_ascii_uppercase())));
+                in_class != *negated
+            },
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Regex {
+    type Err = ();
+
+    /// Compiles a regular expression.
+    ///
+    /// # Bad Data
+    /// Returns an error for unbalanced brackets, a quantifier with nothing to repeat, an
+    /// unfinished escape, or repetitions that expand to more than [`MAXIMUM_INSTRUCTIONS`].
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let case_insensitive_flag = "(?i)";
+        let (expression, case_insensitive) = match pattern.strip_prefix(case_insensitive_flag) {
+            None => (pattern, false),
+            Some(expression) => (expression, true),
+        };
+        let mut parser = Parser {
+            characters: expression.chars().collect(),
+            position: 0,
+            group_count: 0,
+        };
+        let alternatives = parser.parse_alternatives()?;
+        if parser.position < parser.characters.len() {
+            return Err(())
+        }
+        let mut program = Vec::new();
+        compile(&Node::Group { index: None, alternatives }, &mut program)?;
+        program.push(Instruction::Match);
+        Ok(Regex {
+            pattern: pattern.to_owned(),
+            program,
+            group_count: parser.group_count,
+            case_insensitive,
+        })
+    }
+}
+
+/// Adds the instructions for `node` to the end of `program`.
+///
+/// # Bad Data
+/// Returns an error if the program would have more than [`MAXIMUM_INSTRUCTIONS`].
+fn compile(node: &Node, program: &mut Vec<Instruction>) -> Result<(), ()> {
+    match node {
+        Node::Character(_) | Node::AnyCharacter | Node::Class { .. } => program.push(Instruction::Consume(node.clone())),
+        Node::Start => program.push(Instruction::Start),
+        Node::End => program.push(Instruction::End),
+        Node::Group { index, alternatives } => {
+            if let Some(index) = index {
+                program.push(Instruction::GroupStart(*index));
+            }
+            let mut jumps_to_end = Vec::new();
+            for (alternative_index, alternative) in alternatives.iter().enumerate() {
+                let is_last = alternative_index == alternatives.len() - 1;
+                let split_index = program.len();
+                if !is_last {
+                    program.push(Instruction::Split(split_index + 1, split_index + 1));
+                }
+                for node in alternative {
+                    compile(node, program)?;
+                }
+                if !is_last {
+                    jumps_to_end.push(program.len());
+                    program.push(Instruction::Jump(0));
+                    program[split_index] = Instruction::Split(split_index + 1, program.len());
+                }
+            }
+            let end_index = program.len();
+            for jump_index in jumps_to_end {
+                program[jump_index] = Instruction::Jump(end_index);
+            }
+            if let Some(index) = index {
+                program.push(Instruction::GroupEnd(*index));
+            }
+        },
+        Node::Repeat { node, minimum, maximum, lazy } => {
+            let split = |repeat_index: usize, end_index: usize| match lazy {
+                false => Instruction::Split(repeat_index, end_index),
+                true => Instruction::Split(end_index, repeat_index),
+            };
+            for _ in 0..*minimum {
+                compile(node, program)?;
+                if program.len() > MAXIMUM_INSTRUCTIONS {
+                    return Err(())
+                }
+            }
+            match maximum {
+                None => {
+                    let split_index = program.len();
+                    program.push(Instruction::Split(0, 0));
+                    compile(node, program)?;
+                    program.push(Instruction::Jump(split_index));
+                    program[split_index] = split(split_index + 1, program.len());
+                },
+                Some(maximum) => {
+                    let mut split_indexes = Vec::new();
+                    for _ in *minimum..*maximum {
+                        split_indexes.push(program.len());
+                        program.push(Instruction::Split(0, 0));
+                        compile(node, program)?;
+                        if program.len() > MAXIMUM_INSTRUCTIONS {
+                            return Err(())
+                        }
+                    }
+                    let end_index = program.len();
+                    for split_index in split_indexes {
+                        program[split_index] = split(split_index + 1, end_index);
+                    }
+                },
+            }
+        },
+    }
+    match program.len() > MAXIMUM_INSTRUCTIONS {
+        false => Ok(()),
+        true => Err(()),
+    }
+}
+
+struct Parser {
+    characters: Vec<char>,
+    position: usize,
+    group_count: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.characters.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += 1;
+        Some(character)
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Node>>, ()> {
+        let mut alternatives = vec!(self.parse_sequence()?);
+        while self.peek() == Some('|') {
+            self.position += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Node>, ()> {
+        let mut nodes = Vec::new();
+        while let Some(character) = self.peek() {
+            if character == '|' || character == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, ()> {
+        match self.next().ok_or(())? {
+            '.' => Ok(Node::AnyCharacter),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '[' => self.parse_class(),
+            '\\' => self.parse_escape(),
+            '(' => {
+                let non_capturing_prefix = ['?', ':'];
+                let index = match self.characters[self.position..].starts_with(&non_capturing_prefix) {
+                    true => {
+                        self.position += non_capturing_prefix.len();
+                        None
+                    },
+                    false => {
+                        self.group_count += 1;
+                        Some(self.group_count)
+                    },
+                };
+                let alternatives = self.parse_alternatives()?;
+                match self.next() {
+                    Some(')') => Ok(Node::Group { index, alternatives }),
+                    _ => Err(()),
+                }
+            },
+            '*' | '+' | '?' | ')' => Err(()),
+            character => Ok(Node::Character(character)),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, ()> {
+        let digit = vec!(('0', '9'));
+        let word = vec!(('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_'));
+        let space = vec!((' ', ' '), ('\t', '\r'));
+        let node = match self.next().ok_or(())? {
+            'd' => Node::Class { ranges: digit, negated: false },
+            'D' => Node::Class { ranges: digit, negated: true },
+            'w' => Node::Class { ranges: word, negated: false },
+            'W' => Node::Class { ranges: word, negated: true },
+            's' => Node::Class { ranges: space, negated: false },
+            'S' => Node::Class { ranges: space, negated: true },
+            'n' => Node::Character('\n'),
+            't' => Node::Character('\t'),
+            character => Node::Character(character),
+        };
+        Ok(node)
+    }
+
+    fn parse_class(&mut self) -> Result<Node, ()> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.position += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let character = self.next().ok_or(())?;
+            let start = match character {
+                ']' if !first => break,
+                '\\' => match self.parse_escape()? {
+                    Node::Character(character) => character,
+                    Node::Class { ranges: escape_ranges, negated: false } => {
+                        ranges.extend(escape_ranges);
+                        first = false;
+                        continue;
+                    },
+                    _ => return Err(()),
+                },
+                character => character,
+            };
+            first = false;
+            let is_range = self.peek() == Some('-') && self.characters.get(self.position + 1).is_some_and(|end| *end != ']');
+            match is_range {
+                true => {
+                    self.position += 1;
+                    let end = match self.next().ok_or(())? {
+                        '\\' => self.next().ok_or(())?,
+                        end => end,
+                    };
+                    if end < start {
+                        return Err(())
+                    }
+                    ranges.push((start, end));
+                },
+                false => ranges.push((start, start)),
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, ()> {
+        let (minimum, maximum) = match self.peek() {
+            Some('*') => self.consume((0, None)),
+            Some('+') => self.consume((1, None)),
+            Some('?') => self.consume((0, Some(1))),
+            Some('{') => match self.parse_bounds() {
+                Some(bounds) => bounds,
+                // A `{` that doesn't start a quantifier is a literal
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            return Err(())
+        }
+        let lazy = self.peek() == Some('?');
+        if lazy {
+            self.position += 1;
+        }
+        Ok(Node::Repeat { node: Box::new(atom), minimum, maximum, lazy })
+    }
+
+    /// Moves past a one character quantifier, returning its bounds.
+    fn consume(&mut self, bounds: (usize, Option<usize>)) -> (usize, Option<usize>) {
+        self.position += 1;
+        bounds
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`, moving past it.
+    fn parse_bounds(&mut self) -> Option<(usize, Option<usize>)> {
+        let end_index = self.characters[self.position..].iter().position(|character| *character == '}')? + self.position;
+        let bounds: String = self.characters[(self.position + 1)..end_index].iter().collect();
+        let parse_bound = |bound: &str| bound.parse::<usize>().ok();
+        let (minimum, maximum) = match bounds.split_once(',') {
+            None => (parse_bound(&bounds)?, Some(parse_bound(&bounds)?)),
+            Some((minimum, "")) => (parse_bound(minimum)?, None),
+            Some((minimum, maximum)) => (parse_bound(minimum)?, Some(parse_bound(maximum)?)),
+        };
+        if maximum.is_some_and(|maximum| maximum < minimum) {
+            return None
+        }
+        self.position = end_index + 1;
+        Some((minimum, maximum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(pattern: &str) -> Regex {
+        pattern.parse().expect("`pattern` should be valid")
+    }
+
+    #[test]
+    fn matches_literals_classes_and_anchors() {
+        assert!(regex("b.d").is_match("abcde"));
+        assert!(!regex("^b.d").is_match("abcde"));
+        assert!(regex("^[a-c]+\\d$").is_match("abcab7"));
+        assert!(!regex("^[^/]+$").is_match("a/b"));
+        assert!(regex("^\\w+\\s\\S$").is_match("word_1 x"));
+        assert!(regex("(?i)^HELLO$").is_match("hello"));
+        assert!(regex("(?i)^[A-Z]+$").is_match("hello"));
+    }
+
+    #[test]
+    fn matches_quantifiers() {
+        assert!(regex("^a{2,3}$").is_match("aaa"));
+        assert!(!regex("^a{2,3}$").is_match("aaaa"));
+        assert!(regex("^a{2}$").is_match("aa"));
+        assert!(regex("^a{2,}$").is_match("aaaaa"));
+        assert!(regex("^ab?c$").is_match("ac"));
+        assert!(regex("^a{x}$").is_match("a{x}"));
+    }
+
+    #[test]
+    fn gets_captures() {
+        let captures = regex("^/(\\w+)/(?:(\\d+)|(new))$").captures("/posts/new").expect("should match");
+        assert_eq!(captures, vec!(Some("/posts/new"), Some("posts"), None, Some("new")));
+        assert_eq!(regex("(a+)(a*)").captures("xaaay").expect("should match"), vec!(Some("aaa"), Some("aaa"), Some("")));
+        assert_eq!(regex("(a+?)(a*)").captures("xaaay").expect("should match"), vec!(Some("aaa"), Some("a"), Some("aa")));
+        assert_eq!(regex("(?:(a)|b)+").captures("ab").expect("should match"), vec!(Some("ab"), Some("a")));
+    }
+
+    #[test]
+    fn prefers_earlier_alternatives() {
+        assert_eq!(regex("a|ab").captures("ab").expect("should match"), vec!(Some("a")));
+        assert_eq!(regex("(ab|a)(b?)").captures("ab").expect("should match"), vec!(Some("ab"), Some("ab"), Some("")));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for pattern in ["(a", "a)", "*a", "[a", "[z-a]", "a\\", "^*", "a{1,5000}", "(?:a{100}){100}"] {
+            assert!(pattern.parse::<Regex>().is_err(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn converts_globs() {
+        let glob = Regex::from_glob("/images/*.png").expect("glob should be valid");
+        assert_eq!(glob.as_str(), "^/images/([^/]*)\\.png$");
+        assert_eq!(glob.captures("/images/cat.png").expect("should match"), vec!(Some("/images/cat.png"), Some("cat")));
+        assert!(!glob.is_match("/images/cats/cat.png"));
+        assert!(!glob.is_match("/images/catxpng"));
+        assert!(Regex::from_glob("/static/**").expect("glob should be valid").is_match("/static/a/b/c.css"));
+        assert!(Regex::from_glob("/file?.txt").expect("glob should be valid").is_match("/file1.txt"));
+        assert!(!Regex::from_glob("/file?.txt").expect("glob should be valid").is_match("/file/.txt"));
+        assert!(Regex::from_glob("/[!a]b").expect("glob should be valid").is_match("/cb"));
+        assert!(!Regex::from_glob("/[!a]b").expect("glob should be valid").is_match("/ab"));
+        assert!(Regex::from_glob("/a+(b)").expect("glob should be valid").is_match("/a+(b)"));
+        assert!(Regex::from_glob("/[a").is_none());
+    }
+
+    #[test]
+    fn matches_long_input_without_recursion() {
+        let text = "ab".repeat(50_000);
+        assert!(regex("^(?:ab|cd)*$").is_match(&text));
+        assert!(regex("^(a|b)+$").is_match(&text));
+    }
+
+    #[test]
+    fn matches_pathological_patterns_quickly() {
+        let text = "a".repeat(26);
+        assert!(!regex("(a|a)*b").is_match(&text));
+        assert!(!regex("^(a*)*b$").is_match(&text));
+        assert!(!regex("^(a+)+b$").is_match(&"a".repeat(5_000)));
+        assert!(regex("^(a*)*$").is_match(&text));
+        assert!(regex("^(?:a?){26}a{26}$").is_match(&text));
+    }
+
+    #[test]
+    fn gives_up_after_maximum_steps() {
+        let text = "ab".repeat(1_000_000);
+        assert!(!regex("^(?:ab|cd)*$").is_match(&text));
+    }
+}
@@ -0,0 +1,102 @@
+/// Returns `true` if `byte` is a `tchar`, which are the characters allowed in a token.
+pub fn is_tchar(byte: u8) -> bool {
+    matches!(byte,
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~'
+    ) || byte.is_ascii_alphanumeric()
+}
+
+/// Returns `true` if `slice` is a non-empty token, such as a method or field name.
+pub fn is_token(slice: &str) -> bool {
+    !slice.is_empty() && slice.bytes().all(is_tchar)
+}
+
+/// Splits `slice` on every `delimiter` that isn't inside a quoted string, trimming whitespace
+/// from each item and skipping empty items.
+///
+/// This is used for comma-separated field values and semicolon-separated parameters.
+pub fn split_outside_quotes(slice: &str, delimiter: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut start_index = 0;
+    for (index, character) in slice.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match character {
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ if character == delimiter && !in_quotes => {
+                items.push(slice[start_index..index].trim());
+                start_index = index + character.len_utf8();
+            },
+            _ => (),
+        }
+    }
+    items.push(slice[start_index..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Removes the surrounding quotes and escapes from a quoted string.
+///
+/// If `slice` isn't quoted, it is returned unchanged.
+pub fn unquote(slice: &str) -> String {
+    let slice = slice.trim();
+    if slice.len() < 2 || !slice.starts_with('"') || !slice.ends_with('"') {
+        return slice.to_owned()
+    }
+    let mut output = String::with_capacity(slice.len() - 2);
+    let mut escaped = false;
+    for character in slice[1..(slice.len() - 1)].chars() {
+        if !escaped && character == '\\' {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        output.push(character);
+    }
+    output
+}
+
+/// Returns `slice` as-is if it is a token, otherwise as a quoted string.
+pub fn quote_if_needed(slice: &str) -> String {
+    if is_token(slice) {
+        return slice.to_owned()
+    }
+    let mut output = String::with_capacity(slice.len() + 2);
+    output.push('"');
+    for character in slice.chars() {
+        if character == '"' || character == '\\' {
+            output.push('\\');
+        }
+        output.push(character);
+    }
+    output.push('"');
+    output
+}
+
+/// Parses `name=value` parameters separated by `;`, such as the ones following a media type.
+///
+/// Parameter names are lowercased, as they are case-insensitive. Parameters without a `=`
+/// are given an empty value.
+pub fn parse_parameters(slice: &str) -> Vec<(String, String)> {
+    let parameter_delimiter = ';';
+    let value_delimiter = '=';
+    split_outside_quotes(slice, parameter_delimiter).into_iter()
+        .map(|parameter| match parameter.split_once(value_delimiter) {
+            None => (parameter.to_ascii_lowercase(), String::new()),
+            Some((name, value)) => (name.trim().to_ascii_lowercase(), unquote(value)),
+        })
+        .collect()
+}
+
+/// Formats parameters as `; name=value` pairs, quoting values where needed.
+pub fn format_parameters(parameters: &[(String, String)]) -> String {
+    let mut output = String::new();
+    for (name, value) in parameters {
+        output.push_str(&format!("; {name}={}", quote_if_needed(value)));
+    }
+    output
+}
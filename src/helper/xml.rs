@@ -0,0 +1,364 @@
+use std::{collections::HashMap, str::FromStr};
+
+use super::escape;
+
+/// The deepest an element can be nested, so a malicious document can't overflow the stack.
+const MAXIMUM_DEPTH: usize = 256;
+
+/// The namespace of the `xml` prefix, which is bound without being declared.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// A node in the content of an [`XmlElement`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+/// An XML element, with its name resolved to a namespace.
+///
+/// This is a small parser for the documents used by protocols such as WebDAV. Document type
+/// declarations aren't allowed, so entities can't be declared, and only the predefined entities
+/// and character references are decoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct XmlElement {
+    /// The namespace URI, which is empty if the element isn't in a namespace.
+    pub namespace: String,
+    /// The local name, without any prefix.
+    pub name: String,
+    /// The attributes other than namespace declarations, as their namespace, qualified name and
+    /// value. The namespace is empty for an attribute without a prefix.
+    pub attributes: Vec<(String, String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        XmlElement {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if the element has the `name` in the `namespace`.
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+
+    /// Gets the child elements, skipping text.
+    pub fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    /// Gets the first child element with the `name` in the `namespace`.
+    pub fn child(&self, namespace: &str, name: &str) -> Option<&XmlElement> {
+        self.elements().find(|element| element.is(namespace, name))
+    }
+
+    /// Gets the text of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => text.push_str(&element.text()),
+                XmlNode::Text(child_text) => text.push_str(child_text),
+            }
+        }
+        text
+    }
+
+    /// Writes the element as XML, declaring its namespace as the default namespace where it changes,
+    /// and the prefix of each of its prefixed attributes.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        self.write_xml(&mut xml, None);
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String, parent_namespace: Option<&str>) {
+        xml.push('<');
+        xml.push_str(&self.name);
+        if parent_namespace != Some(self.namespace.as_str()) {
+            xml.push_str(&format!(" xmlns=\"{}\"", escape::html(&self.namespace)));
+        }
+        let mut declared_prefixes = Vec::new();
+        for (namespace, name, _) in &self.attributes {
+            let Some((prefix, _)) = name.split_once(':') else {
+                continue
+            };
+            if prefix != "xml" && !declared_prefixes.contains(&prefix) {
+                xml.push_str(&format!(" xmlns:{prefix}=\"{}\"", escape::html(namespace)));
+                declared_prefixes.push(prefix);
+            }
+        }
+        for (_, name, value) in &self.attributes {
+            xml.push_str(&format!(" {name}=\"{}\"", escape::html(value)));
+        }
+        if self.children.is_empty() {
+            xml.push_str("/>");
+            return
+        }
+        xml.push('>');
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => element.write_xml(xml, Some(&self.namespace)),
+                XmlNode::Text(text) => xml.push_str(&escape::html(text)),
+            }
+        }
+        xml.push_str(&format!("</{}>", self.name));
+    }
+}
+
+impl FromStr for XmlElement {
+    type Err = ();
+
+    /// Parses a document, returning its root element.
+    fn from_str(document: &str) -> Result<Self, Self::Err> {
+        let mut parser = XmlParser { document, position: 0 };
+        parser.skip_miscellaneous()?;
+        let root = parser.parse_element(&[HashMap::new()], 0)?;
+        parser.skip_miscellaneous()?;
+        match parser.position == document.len() {
+            false => Err(()),
+            true => Ok(root),
+        }
+    }
+}
+
+struct XmlParser<'a> {
+    document: &'a str,
+    position: usize,
+}
+
+impl XmlParser<'_> {
+    fn remaining(&self) -> &str {
+        &self.document[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let remaining = self.remaining();
+        self.position += remaining.len() - remaining.trim_start_matches([' ', '\t', '\r', '\n']).len();
+    }
+
+    /// Skips past `delimiter`, returning what came before it.
+    fn take_until(&mut self, delimiter: &str) -> Result<&str, ()> {
+        let start = self.position;
+        let length = self.remaining().find(delimiter).ok_or(())?;
+        self.position += length + delimiter.len();
+        Ok(&self.document[start..start + length])
+    }
+
+    /// Skips whitespace, comments and processing instructions, such as the XML declaration.
+    fn skip_miscellaneous(&mut self) -> Result<(), ()> {
+        loop {
+            self.skip_whitespace();
+            if self.remaining().starts_with("<?") {
+                self.take_until("?>")?;
+            } else if self.remaining().starts_with("<!--") {
+                self.take_until("-->")?;
+            } else if self.remaining().starts_with("<!") {
+                // Document type declarations could declare entities that expand without limit
+                return Err(())
+            } else {
+                return Ok(())
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<&str, ()> {
+        let remaining = self.remaining();
+        let length = remaining.find(|character: char| character.is_whitespace() || matches!(character, '/' | '>' | '=')).unwrap_or(remaining.len());
+        if length == 0 {
+            return Err(())
+        }
+        let start = self.position;
+        self.position += length;
+        Ok(&self.document[start..start + length])
+    }
+
+    fn parse_element(&mut self, scopes: &[HashMap<String, String>], depth: usize) -> Result<XmlElement, ()> {
+        if depth > MAXIMUM_DEPTH || !self.remaining().starts_with('<') {
+            return Err(())
+        }
+        self.position += 1;
+        let qualified_name = self.parse_name()?.to_owned();
+
+        let mut raw_attributes = Vec::new();
+        let is_empty = loop {
+            self.skip_whitespace();
+            if self.remaining().starts_with("/>") {
+                self.position += 2;
+                break true
+            }
+            if self.remaining().starts_with('>') {
+                self.position += 1;
+                break false
+            }
+            let name = self.parse_name()?.to_owned();
+            self.skip_whitespace();
+            if !self.remaining().starts_with('=') {
+                return Err(())
+            }
+            self.position += 1;
+            self.skip_whitespace();
+            let quote = self.remaining().chars().next().filter(|quote| matches!(quote, '"' | '\'')).ok_or(())?;
+            self.position += 1;
+            let value = unescape(self.take_until(&quote.to_string())?)?;
+            raw_attributes.push((name, value));
+        };
+
+        let mut scope = HashMap::new();
+        let mut qualified_attributes = Vec::new();
+        for (name, value) in raw_attributes {
+            match name.strip_prefix("xmlns") {
+                Some("") => _ = scope.insert(String::new(), value),
+                Some(prefix) if prefix.starts_with(':') => _ = scope.insert(prefix[1..].to_owned(), value),
+                _ => qualified_attributes.push((name, value)),
+            }
+        }
+        let mut scopes = scopes.to_vec();
+        scopes.push(scope);
+        let mut attributes = Vec::new();
+        for (name, value) in qualified_attributes {
+            // Unlike an element, an attribute without a prefix isn't in the default namespace
+            let namespace = match name.split_once(':') {
+                None => String::new(),
+                Some(("xml", _)) => XML_NAMESPACE.to_owned(),
+                Some((prefix, _)) => scopes.iter().rev().find_map(|scope| scope.get(prefix)).ok_or(())?.clone(),
+            };
+            attributes.push((namespace, name, value));
+        }
+        let (prefix, name) = match qualified_name.split_once(':') {
+            None => ("", qualified_name.as_str()),
+            Some((prefix, name)) => (prefix, name),
+        };
+        let namespace = match scopes.iter().rev().find_map(|scope| scope.get(prefix)) {
+            None if prefix.is_empty() => String::new(),
+            None => return Err(()),
+            Some(namespace) => namespace.clone(),
+        };
+        let mut element = XmlElement {
+            namespace,
+            name: name.to_owned(),
+            attributes,
+            children: Vec::new(),
+        };
+        if is_empty {
+            return Ok(element)
+        }
+
+        loop {
+            let remaining = self.remaining();
+            if remaining.starts_with("</") {
+                self.position += 2;
+                let closing_name = self.take_until(">")?.trim_end();
+                return match closing_name == qualified_name {
+                    false => Err(()),
+                    true => Ok(element),
+                }
+            } else if remaining.starts_with("<!--") {
+                self.take_until("-->")?;
+            } else if remaining.starts_with("<![CDATA[") {
+                self.position += "<![CDATA[".len();
+                let text = self.take_until("]]>")?.to_owned();
+                element.children.push(XmlNode::Text(text));
+            } else if remaining.starts_with("<?") {
+                self.take_until("?>")?;
+            } else if remaining.starts_with('<') {
+                let child = self.parse_element(&scopes, depth + 1)?;
+                element.children.push(XmlNode::Element(child));
+            } else if remaining.is_empty() {
+                return Err(())
+            } else {
+                let length = remaining.find('<').unwrap_or(remaining.len());
+                let text = unescape(&remaining[..length])?;
+                self.position += length;
+                element.children.push(XmlNode::Text(text));
+            }
+        }
+    }
+}
+
+/// Decodes the predefined entities and character references in `slice`.
+fn unescape(slice: &str) -> Result<String, ()> {
+    let mut output = String::with_capacity(slice.len());
+    let mut remaining = slice;
+    while let Some(start) = remaining.find('&') {
+        output.push_str(&remaining[..start]);
+        let (reference, after) = remaining[start + 1..].split_once(';').ok_or(())?;
+        let character = match reference {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).map_err(|_| ())?,
+                    None => reference.strip_prefix('#').ok_or(())?.parse::<u32>().map_err(|_| ())?,
+                };
+                char::from_u32(code).ok_or(())?
+            },
+        };
+        output.push(character);
+        remaining = after;
+    }
+    output.push_str(remaining);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(document: &str) -> XmlElement {
+        document.parse().expect("`document` should be valid")
+    }
+
+    #[test]
+    fn parses_namespaces() {
+        let element = parse("<?xml version=\"1.0\"?>\n<D:propfind xmlns:D=\"DAV:\"><D:prop><x xmlns=\"urn:x\"/><D:getetag/></D:prop></D:propfind>");
+        assert!(element.is("DAV:", "propfind"));
+        let prop = element.child("DAV:", "prop").expect("`prop` should exist");
+        assert!(prop.child("urn:x", "x").is_some());
+        assert!(prop.child("DAV:", "getetag").is_some());
+    }
+
+    #[test]
+    fn parses_text_and_references() {
+        let element = parse("<a>1 &lt; 2 &amp;&#x41;&#66;<!-- comment --><![CDATA[<raw>]]><b>!</b></a>");
+        assert_eq!(element.text(), "1 < 2 &AB<raw>!");
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        let nested = format!("{}{}", "<a>".repeat(MAXIMUM_DEPTH + 2), "</a>".repeat(MAXIMUM_DEPTH + 2));
+        for document in ["<a>", "<a></b>", "<x:a/>", "<a x:b=\"1\"/>", "<a b=1/>", "<!DOCTYPE a><a/>", "<a/><b/>", "<a>&unknown;</a>", nested.as_str()] {
+            assert!(document.parse::<XmlElement>().is_err(), "{document}");
+        }
+    }
+
+    #[test]
+    fn writes_default_namespace_where_it_changes() {
+        let element = parse("<D:prop xmlns:D=\"DAV:\"><D:a>x &amp; y</D:a><b xmlns=\"urn:b\"/></D:prop>");
+        assert_eq!(element.to_xml(), "<prop xmlns=\"DAV:\"><a>x &amp; y</a><b xmlns=\"urn:b\"/></prop>");
+    }
+
+    #[test]
+    fn declares_prefixes_of_attributes() {
+        let element = parse("<Z:a xmlns:Z=\"urn:z\" xmlns:y=\"urn:y\" y:b=\"1\" y:c=\"2\" d=\"3\" xml:lang=\"en\"/>");
+        assert_eq!(element.attributes, vec!(
+            ("urn:y".to_owned(), "y:b".to_owned(), "1".to_owned()),
+            ("urn:y".to_owned(), "y:c".to_owned(), "2".to_owned()),
+            (String::new(), "d".to_owned(), "3".to_owned()),
+            (XML_NAMESPACE.to_owned(), "xml:lang".to_owned(), "en".to_owned()),
+        ));
+        let xml = element.to_xml();
+        assert_eq!(xml, "<a xmlns=\"urn:z\" xmlns:y=\"urn:y\" y:b=\"1\" y:c=\"2\" d=\"3\" xml:lang=\"en\"/>");
+        assert_eq!(parse(&xml), element);
+    }
+}
@@ -3,12 +3,23 @@ mod http_method;
 mod http_version;
 mod http_fieldname;
 mod http_header;
+mod http_host;
+mod http_content_type;
+mod http_date;
+mod http_cache_control;
+mod http_accept;
+mod http_authorization;
 mod http_status_code;
 mod http_target_parameters;
 mod http_target;
+mod http_uri;
+mod http_uri_builder;
+mod http_parse_error;
+mod http_parser_config;
 mod http_partial_request;
 mod http_request;
 mod http_response;
+mod http_form;
 mod filepath;
 
 pub use crate::http_parser::{
@@ -17,10 +28,21 @@ pub use crate::http_parser::{
     http_version::HttpVersion,
     http_fieldname::HttpFieldName,
     http_header::HttpHeader,
-    http_status_code::HttpStatusCode,
+    http_host::HttpHost,
+    http_content_type::HttpContentType,
+    http_date::HttpDate,
+    http_cache_control::{HttpCacheControl, HttpCacheDirective},
+    http_accept::{HttpAccept, HttpQualityItem},
+    http_authorization::{HttpAuthorization, HttpCredentials},
+    http_status_code::{ExtensionStatusCode, HttpStatusCode},
     http_target_parameters::HttpTargetParameters,
-    http_target::HttpTarget,
+    http_target::{HttpTarget, HttpTargetForm},
+    http_uri::HttpUri,
+    http_uri_builder::HttpUriBuilder,
+    http_parse_error::HttpParseError,
+    http_parser_config::{HttpParserConfig, HttpParserMode},
     http_partial_request::PartialHttpRequest,
     http_request::HttpRequest,
     http_response::HttpResponse,
+    http_form::{HttpForm, HttpFormField, HttpFormFile, HttpFormLimits, HttpFormParser},
     filepath::Filepath};
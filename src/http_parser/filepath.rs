@@ -1,4 +1,5 @@
 use core::fmt;
+use std::str::FromStr;
 
 #[derive(Clone)]
 pub struct Filepath {
@@ -13,31 +14,21 @@ impl Filepath {
             filename: None,
         }
     }
+}
+
+impl FromStr for Filepath {
+    type Err = ();
 
-    pub fn from_str(slice: &str) -> Result<Self, ()> {
+    /// Splits a path into its directory, including the trailing `/`, and its filename.
+    ///
+    /// The last segment is the filename unless it is empty, as a path to a directory ends with `/`.
+    fn from_str(slice: &str) -> Result<Self, Self::Err> {
         let directory_delimiter = '/';
-        let file_extension_delimiter = '.';
-        match slice.rfind(directory_delimiter) {
-            None => Err(()),
-            Some(directory_index) => {
-                if directory_index == slice.len() - 1 {
-                    return Ok(Filepath {
-                        directory: Some(slice.to_owned()),
-                        filename: None,
-                    })
-                }
-                match slice[(directory_index + 1)..].find(file_extension_delimiter) {
-                    None => Ok(Filepath {
-                        directory: Some(slice.to_owned()),
-                        filename: None,
-                    }),
-                    Some(_file_extension_index) => Ok(Filepath {
-                        directory: Some(slice[..=directory_index].to_owned()),
-                        filename: Some(slice[(directory_index + 1)..].to_owned()),
-                    }),
-                }
-            },
-        }
+        let (directory, filename) = slice.rsplit_once(directory_delimiter).ok_or(())?;
+        Ok(Filepath {
+            directory: Some(format!("{directory}{directory_delimiter}")),
+            filename: Some(filename.to_owned()).filter(|filename| !filename.is_empty()),
+        })
     }
 }
 
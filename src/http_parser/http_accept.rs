@@ -0,0 +1,153 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::helper::syntax;
+
+/// The highest quality, equal to `q=1`.
+const MAXIMUM_QUALITY: u16 = 1000;
+
+/// A value with an optional quality, as used by `Accept`, `Accept-Charset`,
+/// `Accept-Encoding` and `Accept-Language`.
+///
+/// The quality is stored in thousandths, so `q=0.5` is `500`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpQualityItem {
+    pub value: String,
+    pub parameters: Vec<(String, String)>,
+    pub quality: u16,
+}
+
+impl HttpQualityItem {
+    /// Gets how specifically this item matches `candidate`, or [`None`] if it doesn't match.
+    ///
+    /// An exact match is more specific than a `type/*` match, which is more specific than `*/*` or `*`.
+    fn specificity(&self, candidate: &str) -> Option<u8> {
+        if self.value.eq_ignore_ascii_case(candidate) {
+            return Some(3)
+        }
+        if self.value == "*" || self.value == "*/*" {
+            return Some(1)
+        }
+        let subtype_delimiter = '/';
+        match self.value.strip_suffix("/*") {
+            Some(media_type) => match candidate.split_once(subtype_delimiter) {
+                Some((candidate_type, _)) if candidate_type.eq_ignore_ascii_case(media_type) => Some(2),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+}
+
+impl FromStr for HttpQualityItem {
+    type Err = ();
+
+    fn from_str(item: &str) -> Result<Self, Self::Err> {
+        let parameter_delimiter = ';';
+        let (value, parameters) = match item.split_once(parameter_delimiter) {
+            None => (item.trim(), ""),
+            Some((value, parameters)) => (value.trim(), parameters),
+        };
+        if value.is_empty() {
+            return Err(())
+        }
+        let mut parameters = syntax::parse_parameters(parameters);
+        let quality = match parameters.iter().position(|(name, _)| name == "q") {
+            None => MAXIMUM_QUALITY,
+            Some(index) => parse_quality(&parameters.remove(index).1).ok_or(())?,
+        };
+        Ok(HttpQualityItem {
+            value: value.to_owned(),
+            parameters,
+            quality,
+        })
+    }
+}
+
+impl fmt::Display for HttpQualityItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, syntax::format_parameters(&self.parameters))?;
+        if self.quality < MAXIMUM_QUALITY {
+            let quality = format!("{:03}", self.quality);
+            write!(f, ";q=0.{}", quality.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+/// A comma-separated list of [`HttpQualityItem`]s, in the order they were given.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpAccept(pub Vec<HttpQualityItem>);
+
+impl HttpAccept {
+    /// Gets the quality the client gives to `candidate`, using the most specific matching item.
+    ///
+    /// Returns `0` (not acceptable) if no item matches.
+    pub fn quality(&self, candidate: &str) -> u16 {
+        self.0.iter()
+            .filter_map(|item| item.specificity(candidate).map(|specificity| (specificity, item.quality)))
+            .max_by_key(|(specificity, _)| *specificity)
+            .map_or(0, |(_, quality)| quality)
+    }
+
+    /// Chooses the `available` value the client most prefers.
+    ///
+    /// Ties are broken by the order of `available`. If the list is empty, every value is
+    /// acceptable, so the first is chosen. Returns [`None`] if nothing is acceptable.
+    pub fn preferred<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        if self.0.is_empty() {
+            return available.first().copied()
+        }
+        let mut preferred = None;
+        let mut preferred_quality = 0;
+        for candidate in available {
+            let quality = self.quality(candidate);
+            if quality > preferred_quality {
+                preferred = Some(*candidate);
+                preferred_quality = quality;
+            }
+        }
+        preferred
+    }
+}
+
+impl FromStr for HttpAccept {
+    type Err = ();
+
+    /// Parses a comma-separated list of items. Malformed items are skipped.
+    fn from_str(accept: &str) -> Result<Self, Self::Err> {
+        let item_delimiter = ',';
+        let items = syntax::split_outside_quotes(accept, item_delimiter).into_iter()
+            .filter_map(|item| item.parse().ok())
+            .collect();
+        Ok(HttpAccept(items))
+    }
+}
+
+impl fmt::Display for HttpAccept {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self.0.iter().map(|item| item.to_string()).collect();
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+/// Parses a `qvalue` (`0` to `1` with up to three decimal places) into thousandths.
+fn parse_quality(quality: &str) -> Option<u16> {
+    let decimal_delimiter = '.';
+    let (whole, fraction) = match quality.split_once(decimal_delimiter) {
+        None => (quality, ""),
+        Some((whole, fraction)) => (whole, fraction),
+    };
+    if fraction.len() > 3 || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None
+    }
+    let thousandths = match fraction.len() {
+        0 => 0,
+        length => fraction.parse::<u16>().ok()? * 10u16.pow(3 - length as u32),
+    };
+    match whole {
+        "0" => Some(thousandths),
+        "1" if thousandths == 0 => Some(MAXIMUM_QUALITY),
+        _ => None,
+    }
+}
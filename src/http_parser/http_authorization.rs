@@ -0,0 +1,114 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::helper::{base64, syntax};
+
+/// The credentials following an authentication scheme.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpCredentials {
+    None,
+    /// A single token, as used by the `Basic` and `Bearer` schemes.
+    Token68(String),
+    /// Comma-separated `name=value` parameters, as used by the `Digest` scheme.
+    /// Names are lowercased, as they are case-insensitive.
+    Parameters(Vec<(String, String)>),
+}
+
+/// The value of an `Authorization` or `Proxy-Authorization` field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpAuthorization {
+    pub scheme: String,
+    pub credentials: HttpCredentials,
+}
+
+impl HttpAuthorization {
+    /// Returns `true` if the scheme is `scheme`, ignoring case.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    /// Gets the user ID and password from `Basic` credentials.
+    pub fn basic(&self) -> Option<(String, String)> {
+        if !self.is_scheme("Basic") {
+            return None
+        }
+        let HttpCredentials::Token68(token) = &self.credentials else {
+            return None
+        };
+        let decoded = String::from_utf8(base64::decode(token)?).ok()?;
+        let password_delimiter = ':';
+        decoded.split_once(password_delimiter).map(|(user_id, password)| (user_id.to_owned(), password.to_owned()))
+    }
+
+    /// Gets the token from `Bearer` credentials.
+    pub fn bearer(&self) -> Option<&str> {
+        match &self.credentials {
+            HttpCredentials::Token68(token) if self.is_scheme("Bearer") => Some(token),
+            _ => None,
+        }
+    }
+
+    /// Gets the value of the parameter called `name`, ignoring case.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        match &self.credentials {
+            HttpCredentials::Parameters(parameters) => parameters.iter()
+                .find(|(parameter_name, _)| parameter_name.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for HttpAuthorization {
+    type Err = ();
+
+    fn from_str(authorization: &str) -> Result<Self, Self::Err> {
+        let authorization = authorization.trim();
+        let (scheme, credentials) = match authorization.split_once(' ') {
+            None => (authorization, ""),
+            Some((scheme, credentials)) => (scheme, credentials.trim()),
+        };
+        if !syntax::is_token(scheme) {
+            return Err(())
+        }
+        let credentials = if credentials.is_empty() {
+            HttpCredentials::None
+        } else if is_token68(credentials) {
+            HttpCredentials::Token68(credentials.to_owned())
+        } else {
+            let parameter_delimiter = ',';
+            let value_delimiter = '=';
+            let mut parameters = Vec::new();
+            for parameter in syntax::split_outside_quotes(credentials, parameter_delimiter) {
+                let (name, value) = parameter.split_once(value_delimiter).ok_or(())?;
+                parameters.push((name.trim().to_ascii_lowercase(), syntax::unquote(value)));
+            }
+            HttpCredentials::Parameters(parameters)
+        };
+        Ok(HttpAuthorization {
+            scheme: scheme.to_owned(),
+            credentials,
+        })
+    }
+}
+
+impl fmt::Display for HttpAuthorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.credentials {
+            HttpCredentials::None => write!(f, "{}", self.scheme),
+            HttpCredentials::Token68(token) => write!(f, "{} {token}", self.scheme),
+            HttpCredentials::Parameters(parameters) => {
+                let parameters: Vec<String> = parameters.iter()
+                    .map(|(name, value)| format!("{name}={}", syntax::quote_if_needed(value)))
+                    .collect();
+                write!(f, "{} {}", self.scheme, parameters.join(", "))
+            },
+        }
+    }
+}
+
+/// Returns `true` if `slice` is a `token68`, which is used for opaque credentials.
+fn is_token68(slice: &str) -> bool {
+    let value = slice.trim_end_matches('=');
+    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'+' | b'/'))
+}
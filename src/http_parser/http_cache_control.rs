@@ -0,0 +1,144 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::helper::syntax;
+
+/// A single `Cache-Control` directive.
+///
+/// Directives that take a list of field names (`no-cache` and `private`) keep the list unparsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpCacheDirective {
+    MaxAge(u64),
+    SMaxAge(u64),
+    MaxStale(Option<u64>),
+    MinFresh(u64),
+    StaleWhileRevalidate(u64),
+    StaleIfError(u64),
+    NoCache(Option<String>),
+    Private(Option<String>),
+    NoStore,
+    NoTransform,
+    OnlyIfCached,
+    MustRevalidate,
+    MustUnderstand,
+    ProxyRevalidate,
+    Public,
+    Immutable,
+    Extension(String, Option<String>),
+}
+
+impl FromStr for HttpCacheDirective {
+    type Err = ();
+
+    fn from_str(directive: &str) -> Result<Self, Self::Err> {
+        let value_delimiter = '=';
+        let (name, value) = match directive.split_once(value_delimiter) {
+            None => (directive.trim(), None),
+            Some((name, value)) => (name.trim(), Some(syntax::unquote(value))),
+        };
+        if !syntax::is_token(name) {
+            return Err(())
+        }
+        let seconds = || value.as_ref().and_then(|value| value.parse::<u64>().ok()).ok_or(());
+        let directive = match name.to_ascii_lowercase().as_str() {
+            "max-age" => Self::MaxAge(seconds()?),
+            "s-maxage" => Self::SMaxAge(seconds()?),
+            "max-stale" => Self::MaxStale(seconds().ok()),
+            "min-fresh" => Self::MinFresh(seconds()?),
+            "stale-while-revalidate" => Self::StaleWhileRevalidate(seconds()?),
+            "stale-if-error" => Self::StaleIfError(seconds()?),
+            "no-cache" => Self::NoCache(value),
+            "private" => Self::Private(value),
+            "no-store" => Self::NoStore,
+            "no-transform" => Self::NoTransform,
+            "only-if-cached" => Self::OnlyIfCached,
+            "must-revalidate" => Self::MustRevalidate,
+            "must-understand" => Self::MustUnderstand,
+            "proxy-revalidate" => Self::ProxyRevalidate,
+            "public" => Self::Public,
+            "immutable" => Self::Immutable,
+            name => Self::Extension(name.to_owned(), value),
+        };
+        Ok(directive)
+    }
+}
+
+impl fmt::Display for HttpCacheDirective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxAge(seconds) => write!(f, "max-age={seconds}"),
+            Self::SMaxAge(seconds) => write!(f, "s-maxage={seconds}"),
+            Self::MaxStale(None) => write!(f, "max-stale"),
+            Self::MaxStale(Some(seconds)) => write!(f, "max-stale={seconds}"),
+            Self::MinFresh(seconds) => write!(f, "min-fresh={seconds}"),
+            Self::StaleWhileRevalidate(seconds) => write!(f, "stale-while-revalidate={seconds}"),
+            Self::StaleIfError(seconds) => write!(f, "stale-if-error={seconds}"),
+            Self::NoCache(None) => write!(f, "no-cache"),
+            Self::NoCache(Some(field_names)) => write!(f, "no-cache=\"{field_names}\""),
+            Self::Private(None) => write!(f, "private"),
+            Self::Private(Some(field_names)) => write!(f, "private=\"{field_names}\""),
+            Self::NoStore => write!(f, "no-store"),
+            Self::NoTransform => write!(f, "no-transform"),
+            Self::OnlyIfCached => write!(f, "only-if-cached"),
+            Self::MustRevalidate => write!(f, "must-revalidate"),
+            Self::MustUnderstand => write!(f, "must-understand"),
+            Self::ProxyRevalidate => write!(f, "proxy-revalidate"),
+            Self::Public => write!(f, "public"),
+            Self::Immutable => write!(f, "immutable"),
+            Self::Extension(name, None) => write!(f, "{name}"),
+            Self::Extension(name, Some(value)) => write!(f, "{name}={}", syntax::quote_if_needed(value)),
+        }
+    }
+}
+
+/// The directives of a `Cache-Control` field, in the order they were given.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpCacheControl(pub Vec<HttpCacheDirective>);
+
+impl HttpCacheControl {
+    pub fn new() -> Self {
+        HttpCacheControl(Vec::new())
+    }
+
+    pub fn contains(&self, directive: &HttpCacheDirective) -> bool {
+        self.0.contains(directive)
+    }
+
+    pub fn max_age(&self) -> Option<u64> {
+        self.0.iter().find_map(|directive| match directive {
+            HttpCacheDirective::MaxAge(seconds) => Some(*seconds),
+            _ => None,
+        })
+    }
+
+    pub fn no_cache(&self) -> bool {
+        self.0.iter().any(|directive| matches!(directive, HttpCacheDirective::NoCache(_)))
+    }
+
+    pub fn no_store(&self) -> bool {
+        self.contains(&HttpCacheDirective::NoStore)
+    }
+}
+
+impl FromStr for HttpCacheControl {
+    type Err = ();
+
+    /// Parses a comma-separated list of directives. Malformed directives are skipped.
+    fn from_str(cache_control: &str) -> Result<Self, Self::Err> {
+        let directive_delimiter = ',';
+        let directives: Vec<HttpCacheDirective> = syntax::split_outside_quotes(cache_control, directive_delimiter).into_iter()
+            .filter_map(|directive| directive.parse().ok())
+            .collect();
+        match directives.len() {
+            0 => Err(()),
+            _ => Ok(HttpCacheControl(directives)),
+        }
+    }
+}
+
+impl fmt::Display for HttpCacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let directives: Vec<String> = self.0.iter().map(|directive| directive.to_string()).collect();
+        write!(f, "{}", directives.join(", "))
+    }
+}
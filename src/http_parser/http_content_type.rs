@@ -0,0 +1,84 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::helper::syntax;
+
+/// A media type with parameters, as used by the `Content-Type` field.
+///
+/// The type and subtype are lowercased, as they are case-insensitive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpContentType {
+    pub media_type: String,
+    pub subtype: String,
+    pub parameters: Vec<(String, String)>,
+}
+
+impl HttpContentType {
+    pub fn new(media_type: &str, subtype: &str) -> Self {
+        HttpContentType {
+            media_type: media_type.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Gets the value of the parameter called `name`, ignoring case.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.iter()
+            .find(|(parameter_name, _)| parameter_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the parameter called `name`, replacing any existing value.
+    pub fn set_parameter(&mut self, name: &str, value: &str) {
+        let name = name.to_ascii_lowercase();
+        match self.parameters.iter_mut().find(|(parameter_name, _)| *parameter_name == name) {
+            None => self.parameters.push((name, value.to_owned())),
+            Some((_, old_value)) => *old_value = value.to_owned(),
+        }
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+
+    pub fn boundary(&self) -> Option<&str> {
+        self.parameter("boundary")
+    }
+
+    /// Returns `true` if the type and subtype are `media_type` and `subtype`, ignoring parameters.
+    pub fn is(&self, media_type: &str, subtype: &str) -> bool {
+        self.media_type.eq_ignore_ascii_case(media_type) && self.subtype.eq_ignore_ascii_case(subtype)
+    }
+
+    /// Gets the `type/subtype` without parameters.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.media_type, self.subtype)
+    }
+}
+
+impl FromStr for HttpContentType {
+    type Err = ();
+
+    fn from_str(content_type: &str) -> Result<Self, Self::Err> {
+        let parameter_delimiter = ';';
+        let subtype_delimiter = '/';
+        let (essence, parameters) = match content_type.split_once(parameter_delimiter) {
+            None => (content_type, ""),
+            Some((essence, parameters)) => (essence, parameters),
+        };
+        let (media_type, subtype) = essence.trim().split_once(subtype_delimiter).ok_or(())?;
+        if !syntax::is_token(media_type) || !syntax::is_token(subtype) {
+            return Err(())
+        }
+        let mut content_type = HttpContentType::new(media_type, subtype);
+        content_type.parameters = syntax::parse_parameters(parameters);
+        Ok(content_type)
+    }
+}
+
+impl fmt::Display for HttpContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}{}", self.media_type, self.subtype, syntax::format_parameters(&self.parameters))
+    }
+}
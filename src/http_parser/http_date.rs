@@ -0,0 +1,179 @@
+use core::fmt;
+use std::{str::FromStr, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+const DAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTH_NAMES: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const SECONDS_IN_DAY: u64 = 86_400;
+
+/// A HTTP date, as used by fields such as `Date`, `Last-Modified` and `Expires`.
+///
+/// Dates are always formatted as an IMF-fixdate (e.g. `Sun, 06 Nov 1994 08:49:37 GMT`),
+/// but the obsolete RFC 850 and asctime formats are also accepted when parsing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HttpDate(pub SystemTime);
+
+impl HttpDate {
+    pub fn now() -> Self {
+        HttpDate(SystemTime::now())
+    }
+
+    /// Gets the number of whole seconds since the Unix epoch, or `0` for earlier dates.
+    pub fn seconds_since_epoch(&self) -> u64 {
+        match self.0.duration_since(UNIX_EPOCH) {
+            Err(_) => 0,
+            Ok(duration) => duration.as_secs(),
+        }
+    }
+
+    /// Builds a date from its parts, or [`None`] if they aren't a valid time in the years 1 to 9999.
+    fn from_parts(year: i64, month: u32, day: u32, time: &str) -> Option<Self> {
+        if !(1..=9999).contains(&year) || !(1..=days_in_month(year, month)).contains(&day) {
+            return None
+        }
+        let mut time_parts = time.split(':').map(|part| part.parse::<u64>().ok());
+        let hour = time_parts.next()??;
+        let minute = time_parts.next()??;
+        let second = time_parts.next()??;
+        if time_parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+            return None
+        }
+        let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+        let seconds = days.checked_mul(SECONDS_IN_DAY)?.checked_add(hour * 3600 + minute * 60 + second)?;
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds)).map(HttpDate)
+    }
+}
+
+impl FromStr for HttpDate {
+    type Err = ();
+
+    fn from_str(date: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = date.split_whitespace().collect();
+        let date = match parts.as_slice() {
+            // IMF-fixdate: `Sun, 06 Nov 1994 08:49:37 GMT`
+            [_, day, month, year, time, "GMT"] => {
+                let year = year.parse().map_err(|_| ())?;
+                HttpDate::from_parts(year, month_from_name(month).ok_or(())?, day.parse().map_err(|_| ())?, time)
+            },
+            // RFC 850: `Sunday, 06-Nov-94 08:49:37 GMT`
+            [_, date, time, "GMT"] => {
+                let mut date_parts = date.split('-');
+                let day = date_parts.next().and_then(|day| day.parse().ok()).ok_or(())?;
+                let month = date_parts.next().and_then(month_from_name).ok_or(())?;
+                let year: i64 = date_parts.next().and_then(|year| year.parse().ok()).ok_or(())?;
+                // Two-digit years are interpreted as being within 50 years of the Unix epoch
+                let year = if year < 70 { year + 2000 } else if year < 100 { year + 1900 } else { year };
+                HttpDate::from_parts(year, month, day, time)
+            },
+            // asctime: `Sun Nov  6 08:49:37 1994`
+            [_, month, day, time, year] => {
+                let year = year.parse().map_err(|_| ())?;
+                HttpDate::from_parts(year, month_from_name(month).ok_or(())?, day.parse().map_err(|_| ())?, time)
+            },
+            _ => None,
+        };
+        date.ok_or(())
+    }
+}
+
+impl From<SystemTime> for HttpDate {
+    fn from(time: SystemTime) -> Self {
+        HttpDate(time)
+    }
+}
+
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.seconds_since_epoch();
+        let days = (seconds / SECONDS_IN_DAY) as i64;
+        let seconds_in_day = seconds % SECONDS_IN_DAY;
+        let (year, month, day) = civil_from_days(days);
+        // The Unix epoch was a Thursday
+        let day_name = DAY_NAMES[((days + 4) % 7) as usize];
+        let month_name = MONTH_NAMES[(month - 1) as usize];
+        write!(f, "{day_name}, {day:02} {month_name} {year:04} {:02}:{:02}:{:02} GMT",
+            seconds_in_day / 3600, (seconds_in_day % 3600) / 60, seconds_in_day % 60)
+    }
+}
+
+fn month_from_name(name: &str) -> Option<u32> {
+    MONTH_NAMES.iter().position(|month| *month == name).map(|index| index as u32 + 1)
+}
+
+/// Gets the number of days in a month of a proleptic Gregorian year, or `0` for an invalid month.
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Gets the number of days since the Unix epoch for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Gets the proleptic Gregorian `(year, month, day)` for a number of days since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_imf_fixdate() {
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let http_date: HttpDate = date.parse().unwrap();
+        assert_eq!(http_date.seconds_since_epoch(), 784_111_777);
+        assert_eq!(http_date.to_string(), date);
+    }
+
+    #[test]
+    fn parses_obsolete_formats() {
+        let expected = "Sun, 06 Nov 1994 08:49:37 GMT".parse::<HttpDate>();
+        assert_eq!("Sunday, 06-Nov-94 08:49:37 GMT".parse::<HttpDate>(), expected);
+        assert_eq!("Sun Nov  6 08:49:37 1994".parse::<HttpDate>(), expected);
+    }
+
+    #[test]
+    fn validates_day_of_month() {
+        assert!("Sat, 29 Feb 2000 00:00:00 GMT".parse::<HttpDate>().is_ok());
+        assert!("Thu, 29 Feb 2024 00:00:00 GMT".parse::<HttpDate>().is_ok());
+        assert!("Thu, 31 Dec 2026 00:00:00 GMT".parse::<HttpDate>().is_ok());
+        assert!("Wed, 29 Feb 2023 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Mon, 29 Feb 2100 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Wed, 31 Feb 2024 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Sat, 31 Apr 2026 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Sat, 00 Apr 2026 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Sat, 31-Jun-26 00:00:00 GMT".parse::<HttpDate>().is_err());
+    }
+
+    #[test]
+    fn rejects_years_out_of_range() {
+        assert!("Fri, 31 Dec 9999 23:59:59 GMT".parse::<HttpDate>().is_ok());
+        assert!("Sun, 06 Nov 9999999999999999 08:49:37 GMT".parse::<HttpDate>().is_err());
+        assert!("Sun, 06 Nov 99999999999 08:49:37 GMT".parse::<HttpDate>().is_err());
+        assert!("Sun Nov  6 08:49:37 9999999999999999".parse::<HttpDate>().is_err());
+        assert!("Sun, 06 Nov 0000 08:49:37 GMT".parse::<HttpDate>().is_err());
+    }
+}
@@ -1,28 +1,257 @@
 use core::fmt;
+use std::{hash::{Hash, Hasher}, mem, str::FromStr};
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub enum HttpFieldName {
-    ContentLength,
-    Host
+use crate::helper::syntax;
+
+/// Defines [`HttpFieldName`] from a list of registered field names and their canonical spelling.
+macro_rules! http_field_names {
+    ($($variant:ident => $name:literal,)+) => {
+        /// A HTTP field name.
+        ///
+        /// Every field name in the IANA HTTP Field Name Registry with a permanent status has its
+        /// own variant. Any other field name is kept as an [`HttpFieldName::Other`].
+        #[derive(Clone, Debug)]
+        pub enum HttpFieldName {
+            $($variant,)+
+            Other(String),
+        }
+
+        impl HttpFieldName {
+            /// Every registered field name, excluding [`HttpFieldName::Other`].
+            pub const REGISTERED: &'static [HttpFieldName] = &[$(Self::$variant,)+];
+
+            /// Gets the canonical spelling of the field name.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $name,)+
+                    Self::Other(field_name) => field_name,
+                }
+            }
+
+            fn from_registered(field_name: &str) -> Option<Self> {
+                $(if field_name.eq_ignore_ascii_case($name) {
+                    return Some(Self::$variant)
+                })+
+                None
+            }
+        }
+    };
+}
+
+http_field_names! {
+    AIm => "A-IM",
+    Accept => "Accept",
+    AcceptCh => "Accept-CH",
+    AcceptCharset => "Accept-Charset",
+    AcceptDatetime => "Accept-Datetime",
+    AcceptEncoding => "Accept-Encoding",
+    AcceptLanguage => "Accept-Language",
+    AcceptPatch => "Accept-Patch",
+    AcceptPost => "Accept-Post",
+    AcceptRanges => "Accept-Ranges",
+    AcceptSignature => "Accept-Signature",
+    AccessControlAllowCredentials => "Access-Control-Allow-Credentials",
+    AccessControlAllowHeaders => "Access-Control-Allow-Headers",
+    AccessControlAllowMethods => "Access-Control-Allow-Methods",
+    AccessControlAllowOrigin => "Access-Control-Allow-Origin",
+    AccessControlExposeHeaders => "Access-Control-Expose-Headers",
+    AccessControlMaxAge => "Access-Control-Max-Age",
+    AccessControlRequestHeaders => "Access-Control-Request-Headers",
+    AccessControlRequestMethod => "Access-Control-Request-Method",
+    Age => "Age",
+    Allow => "Allow",
+    Alpn => "ALPN",
+    AltSvc => "Alt-Svc",
+    AltUsed => "Alt-Used",
+    Alternates => "Alternates",
+    AuthenticationControl => "Authentication-Control",
+    AuthenticationInfo => "Authentication-Info",
+    Authorization => "Authorization",
+    CExt => "C-Ext",
+    CMan => "C-Man",
+    COpt => "C-Opt",
+    CPep => "C-PEP",
+    CacheControl => "Cache-Control",
+    CacheStatus => "Cache-Status",
+    CalManagedId => "Cal-Managed-ID",
+    CaldavTimezones => "CalDAV-Timezones",
+    CapsuleProtocol => "Capsule-Protocol",
+    CdnCacheControl => "CDN-Cache-Control",
+    CdnLoop => "CDN-Loop",
+    CertNotAfter => "Cert-Not-After",
+    CertNotBefore => "Cert-Not-Before",
+    ClearSiteData => "Clear-Site-Data",
+    ClientCert => "Client-Cert",
+    ClientCertChain => "Client-Cert-Chain",
+    Close => "Close",
+    Connection => "Connection",
+    ContentDigest => "Content-Digest",
+    ContentDisposition => "Content-Disposition",
+    ContentEncoding => "Content-Encoding",
+    ContentLanguage => "Content-Language",
+    ContentLength => "Content-Length",
+    ContentLocation => "Content-Location",
+    ContentRange => "Content-Range",
+    ContentSecurityPolicy => "Content-Security-Policy",
+    ContentSecurityPolicyReportOnly => "Content-Security-Policy-Report-Only",
+    ContentType => "Content-Type",
+    Cookie => "Cookie",
+    CrossOriginEmbedderPolicy => "Cross-Origin-Embedder-Policy",
+    CrossOriginEmbedderPolicyReportOnly => "Cross-Origin-Embedder-Policy-Report-Only",
+    CrossOriginOpenerPolicy => "Cross-Origin-Opener-Policy",
+    CrossOriginOpenerPolicyReportOnly => "Cross-Origin-Opener-Policy-Report-Only",
+    CrossOriginResourcePolicy => "Cross-Origin-Resource-Policy",
+    Dasl => "DASL",
+    Date => "Date",
+    Dav => "DAV",
+    DeltaBase => "Delta-Base",
+    Depth => "Depth",
+    Destination => "Destination",
+    ETag => "ETag",
+    Expect => "Expect",
+    Expires => "Expires",
+    Forwarded => "Forwarded",
+    From => "From",
+    Hobareg => "Hobareg",
+    Host => "Host",
+    If => "If",
+    IfMatch => "If-Match",
+    IfModifiedSince => "If-Modified-Since",
+    IfNoneMatch => "If-None-Match",
+    IfRange => "If-Range",
+    IfScheduleTagMatch => "If-Schedule-Tag-Match",
+    IfUnmodifiedSince => "If-Unmodified-Since",
+    Im => "IM",
+    IncludeReferredTokenBindingId => "Include-Referred-Token-Binding-ID",
+    KeepAlive => "Keep-Alive",
+    Label => "Label",
+    LastEventId => "Last-Event-ID",
+    LastModified => "Last-Modified",
+    Link => "Link",
+    Location => "Location",
+    LockToken => "Lock-Token",
+    MaxForwards => "Max-Forwards",
+    MementoDatetime => "Memento-Datetime",
+    Meter => "Meter",
+    MimeVersion => "MIME-Version",
+    Negotiate => "Negotiate",
+    Nel => "NEL",
+    OdataEntityId => "OData-EntityId",
+    OdataIsolation => "OData-Isolation",
+    OdataMaxVersion => "OData-MaxVersion",
+    OdataVersion => "OData-Version",
+    OptionalWwwAuthenticate => "Optional-WWW-Authenticate",
+    OrderingType => "Ordering-Type",
+    Origin => "Origin",
+    OriginAgentCluster => "Origin-Agent-Cluster",
+    Oscore => "OSCORE",
+    OslcCoreVersion => "OSLC-Core-Version",
+    Overwrite => "Overwrite",
+    PingFrom => "Ping-From",
+    PingTo => "Ping-To",
+    Position => "Position",
+    Prefer => "Prefer",
+    PreferenceApplied => "Preference-Applied",
+    Priority => "Priority",
+    ProxyAuthenticate => "Proxy-Authenticate",
+    ProxyAuthenticationInfo => "Proxy-Authentication-Info",
+    ProxyAuthorization => "Proxy-Authorization",
+    ProxyStatus => "Proxy-Status",
+    PublicKeyPins => "Public-Key-Pins",
+    PublicKeyPinsReportOnly => "Public-Key-Pins-Report-Only",
+    Range => "Range",
+    RedirectRef => "Redirect-Ref",
+    Referer => "Referer",
+    Refresh => "Refresh",
+    ReplayNonce => "Replay-Nonce",
+    ReprDigest => "Repr-Digest",
+    RetryAfter => "Retry-After",
+    ScheduleReply => "Schedule-Reply",
+    ScheduleTag => "Schedule-Tag",
+    SecPurpose => "Sec-Purpose",
+    SecTokenBinding => "Sec-Token-Binding",
+    SecWebsocketAccept => "Sec-WebSocket-Accept",
+    SecWebsocketExtensions => "Sec-WebSocket-Extensions",
+    SecWebsocketKey => "Sec-WebSocket-Key",
+    SecWebsocketProtocol => "Sec-WebSocket-Protocol",
+    SecWebsocketVersion => "Sec-WebSocket-Version",
+    Server => "Server",
+    ServerTiming => "Server-Timing",
+    SetCookie => "Set-Cookie",
+    Signature => "Signature",
+    SignatureInput => "Signature-Input",
+    Slug => "SLUG",
+    SoapAction => "SoapAction",
+    StatusUri => "Status-URI",
+    StrictTransportSecurity => "Strict-Transport-Security",
+    Sunset => "Sunset",
+    SurrogateCapability => "Surrogate-Capability",
+    SurrogateControl => "Surrogate-Control",
+    Tcn => "TCN",
+    Te => "TE",
+    Timeout => "Timeout",
+    Topic => "Topic",
+    Traceparent => "Traceparent",
+    Tracestate => "Tracestate",
+    Trailer => "Trailer",
+    TransferEncoding => "Transfer-Encoding",
+    Ttl => "TTL",
+    Upgrade => "Upgrade",
+    Urgency => "Urgency",
+    UserAgent => "User-Agent",
+    Vary => "Vary",
+    Via => "Via",
+    WantContentDigest => "Want-Content-Digest",
+    WantReprDigest => "Want-Repr-Digest",
+    WwwAuthenticate => "WWW-Authenticate",
+    XContentTypeOptions => "X-Content-Type-Options",
+    XFrameOptions => "X-Frame-Options",
 }
 
-impl HttpFieldName {
-    pub fn from_str(field_name: &str) -> Option<Self> {
-        // As field names are case-insensitive, `field_name` and the cases below must be the same case
-        match field_name.trim().to_ascii_lowercase().as_str() {
-            "host" => Some(Self::Host),
-            "content-length" => Some(Self::ContentLength),
-            _ => None
+impl FromStr for HttpFieldName {
+    type Err = ();
+
+    /// Parses a field name, which must be a token.
+    ///
+    /// Field names are case-insensitive, so registered field names are matched regardless
+    /// of case. Unregistered field names become [`HttpFieldName::Other`] with their case preserved.
+    fn from_str(field_name: &str) -> Result<Self, Self::Err> {
+        let field_name = field_name.trim();
+        if !syntax::is_token(field_name) {
+            return Err(())
+        }
+        match Self::from_registered(field_name) {
+            None => Ok(Self::Other(field_name.to_owned())),
+            Some(field_name) => Ok(field_name),
+        }
+    }
+}
+
+impl PartialEq for HttpFieldName {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Other(field_name), Self::Other(other_field_name)) => field_name.eq_ignore_ascii_case(other_field_name),
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for HttpFieldName {}
+
+impl Hash for HttpFieldName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Must agree with `PartialEq`, so `Other` field names are hashed case-insensitively
+        mem::discriminant(self).hash(state);
+        if let Self::Other(field_name) = self {
+            for byte in field_name.bytes() {
+                byte.to_ascii_lowercase().hash(state);
+            }
         }
     }
 }
 
 impl fmt::Display for HttpFieldName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let field_name = match self {
-            Self::Host => "Host",
-            Self::ContentLength => "Content-Length"
-        };
-        write!(f, "{field_name}")
+        write!(f, "{}", self.as_str())
     }
 }
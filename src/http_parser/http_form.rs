@@ -0,0 +1,612 @@
+use std::{fs::{self, File}, io::{self, Write}, mem, path::{Path, PathBuf}};
+
+use crate::helper::{file, percent, random, syntax};
+
+use super::{HttpContentType, HttpFieldName, HttpHeader, HttpParserMode, HttpRequest, HttpStatusCode};
+
+/// The largest header a part of a `multipart/form-data` body can have.
+const MAXIMUM_PART_HEADER_SIZE: usize = 8 * 1024;
+
+/// The limits on a form, so a request can't use unbounded memory or disk space.
+///
+/// Sizes are in bytes, and `0` means there is no limit.
+#[derive(Clone, Debug)]
+pub struct HttpFormLimits {
+    /// The largest value a field that isn't a file can have.
+    pub maximum_field_size: usize,
+    /// The largest file that can be uploaded.
+    pub maximum_file_size: usize,
+    /// The largest the values of every field and file can be together.
+    pub maximum_total_size: usize,
+    /// The most fields and files a form can have.
+    pub maximum_part_count: usize,
+    /// Files larger than this are written to a temporary file instead of being kept in memory.
+    pub memory_threshold: usize,
+    /// The directory temporary files are written to.
+    pub temporary_directory: PathBuf,
+}
+
+/// A field of a form that isn't a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpFormField {
+    pub name: String,
+    pub value: String,
+}
+
+/// A file uploaded in a `multipart/form-data` form.
+///
+/// A file larger than the [`HttpFormLimits`]' `memory_threshold` is kept in a temporary file,
+/// which is deleted when the [`HttpFormFile`] is dropped, unless it is moved with [`HttpFormFile::persist`].
+#[derive(Debug)]
+pub struct HttpFormFile {
+    /// The name of the form field the file was uploaded with.
+    pub field_name: String,
+    /// The filename given by the client, which may include a path and must not be trusted.
+    pub filename: String,
+    pub content_type: Option<HttpContentType>,
+    pub size: usize,
+    data: HttpFormFileData,
+}
+
+#[derive(Debug)]
+enum HttpFormFileData {
+    Memory(Vec<u8>),
+    Temporary(PathBuf, Option<File>),
+}
+
+/// The fields and files of a form, in the order they were given.
+#[derive(Debug, Default)]
+pub struct HttpForm {
+    pub fields: Vec<HttpFormField>,
+    pub files: Vec<HttpFormFile>,
+}
+
+/// A parser for a form body that is given to it as it is received.
+///
+/// Bodies are parsed as `application/x-www-form-urlencoded` or `multipart/form-data`, depending
+/// on the `Content-Type`. Errors are the status code to respond with: [`HttpStatusCode::ContentTooLarge413`]
+/// if a limit is exceeded, [`HttpStatusCode::BadRequest400`] if the body is malformed and
+/// [`HttpStatusCode::InternalServerError500`] if a temporary file can't be written.
+///
+/// # Examples
+///
+/// ```
+/// use webserver::http_parser::{HttpContentType, HttpFormLimits, HttpFormParser};
+///
+/// let content_type = "multipart/form-data; boundary=b".parse::<HttpContentType>().unwrap();
+/// let body = b"--b\r\nContent-Disposition: form-data; name=\"q\"\r\n\r\nrust\r\n--b--\r\n";
+/// let mut parser = HttpFormParser::new(&content_type, &HttpFormLimits::default()).unwrap();
+/// for byte in body {
+///     parser.push(&[*byte]).unwrap();
+/// }
+/// assert_eq!(Some("rust"), parser.finish().unwrap().get("q"));
+/// ```
+#[derive(Debug)]
+pub struct HttpFormParser {
+    limits: HttpFormLimits,
+    form: HttpForm,
+    encoding: HttpFormEncoding,
+    buffer: Vec<u8>,
+    total_size: usize,
+}
+
+#[derive(Debug)]
+enum HttpFormEncoding {
+    UrlEncoded,
+    Multipart { delimiter: Vec<u8>, state: MultipartState },
+}
+
+#[derive(Debug)]
+enum MultipartState {
+    /// Before the first delimiter.
+    Preamble,
+    /// After a delimiter, which is either followed by a line ending or `--` if it is the last one.
+    Delimiter,
+    Header,
+    Body(Part),
+    /// After the last delimiter, where anything is ignored.
+    Epilogue,
+}
+
+#[derive(Debug)]
+enum Part {
+    Field(String, Vec<u8>),
+    File(HttpFormFile),
+}
+
+impl Default for HttpFormLimits {
+    fn default() -> Self {
+        HttpFormLimits {
+            maximum_field_size: 64 * 1024,
+            maximum_file_size: 0,
+            maximum_total_size: 0,
+            maximum_part_count: 1000,
+            memory_threshold: 64 * 1024,
+            temporary_directory: std::env::temp_dir(),
+        }
+    }
+}
+
+impl HttpFormFile {
+    /// Reads the file's contents.
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        match &self.data {
+            HttpFormFileData::Memory(bytes) => Ok(bytes.clone()),
+            HttpFormFileData::Temporary(path, _) => fs::read(path),
+        }
+    }
+
+    /// Gets the path of the temporary file the contents are in, or [`None`] if they are in memory.
+    pub fn temporary_path(&self) -> Option<&Path> {
+        match &self.data {
+            HttpFormFileData::Memory(_) => None,
+            HttpFormFileData::Temporary(path, _) => Some(path),
+        }
+    }
+
+    /// Moves the file to `path`, replacing any file that is there, so readers never see a partly written file.
+    pub fn persist(mut self, path: &Path) -> io::Result<()> {
+        match mem::replace(&mut self.data, HttpFormFileData::Memory(Vec::new())) {
+            HttpFormFileData::Memory(bytes) => file::write_atomically(path, &bytes),
+            HttpFormFileData::Temporary(temporary_path, _) => {
+                let result = match fs::rename(&temporary_path, path) {
+                    // The temporary directory can be on another file system
+                    Err(error) if error.kind() == io::ErrorKind::CrossesDevices => file::copy_atomically(&temporary_path, path),
+                    result => result,
+                };
+                _ = fs::remove_file(&temporary_path);
+                result
+            },
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8], limits: &HttpFormLimits) -> Result<(), HttpStatusCode> {
+        self.size += bytes.len();
+        if limits.maximum_file_size > 0 && self.size > limits.maximum_file_size {
+            return Err(HttpStatusCode::ContentTooLarge413)
+        }
+        if let HttpFormFileData::Memory(memory) = &mut self.data {
+            if self.size <= limits.memory_threshold {
+                memory.extend_from_slice(bytes);
+                return Ok(())
+            }
+            let path = limits.temporary_directory.join(format!(".form-{:016x}.tmp", random::random_u64()));
+            let mut temporary_file = File::create_new(&path).map_err(|_| HttpStatusCode::InternalServerError500)?;
+            let result = temporary_file.write_all(memory);
+            self.data = HttpFormFileData::Temporary(path, Some(temporary_file));
+            result.map_err(|_| HttpStatusCode::InternalServerError500)?;
+        }
+        match &mut self.data {
+            HttpFormFileData::Temporary(_, Some(temporary_file)) => temporary_file.write_all(bytes).map_err(|_| HttpStatusCode::InternalServerError500),
+            _ => Ok(()),
+        }
+    }
+
+    /// Closes the temporary file once the whole file has been written.
+    fn finish(&mut self) -> Result<(), HttpStatusCode> {
+        match &mut self.data {
+            HttpFormFileData::Temporary(_, temporary_file) => match temporary_file.take() {
+                None => Ok(()),
+                Some(temporary_file) => temporary_file.sync_all().map_err(|_| HttpStatusCode::InternalServerError500),
+            },
+            HttpFormFileData::Memory(_) => Ok(()),
+        }
+    }
+}
+
+impl Drop for HttpFormFile {
+    fn drop(&mut self) {
+        if let HttpFormFileData::Temporary(path, _) = &self.data {
+            _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl HttpForm {
+    /// Parses the body of `http_request` as a form.
+    ///
+    /// A request without a body has an empty form, and a body that isn't a form gets
+    /// [`HttpStatusCode::UnsupportedMediaType415`].
+    pub fn from_request(http_request: &HttpRequest, limits: &HttpFormLimits) -> Result<Self, HttpStatusCode> {
+        let body = http_request.body.unwrap_or_default();
+        let content_type = http_request.header.as_ref().and_then(|header| header.content_type());
+        let content_type = match content_type {
+            None if body.is_empty() => return Ok(HttpForm::default()),
+            None => return Err(HttpStatusCode::UnsupportedMediaType415),
+            Some(content_type) => content_type,
+        };
+        let mut parser = HttpFormParser::new(&content_type, limits)?;
+        parser.push(body)?;
+        parser.finish()
+    }
+
+    /// Gets the first value of the field called `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|field| field.name == name).map(|field| field.value.as_str())
+    }
+
+    /// Gets every value of the field called `name`, in order.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.fields.iter().filter(|field| field.name == name).map(|field| field.value.as_str()).collect()
+    }
+
+    /// Gets the first file uploaded with the field called `field_name`.
+    pub fn file(&self, field_name: &str) -> Option<&HttpFormFile> {
+        self.files.iter().find(|file| file.field_name == field_name)
+    }
+}
+
+impl HttpFormParser {
+    /// Creates a parser for a body with `content_type`, which must be `application/x-www-form-urlencoded`,
+    /// or `multipart/form-data` with a boundary.
+    pub fn new(content_type: &HttpContentType, limits: &HttpFormLimits) -> Result<Self, HttpStatusCode> {
+        let encoding = match (content_type.media_type.as_str(), content_type.subtype.as_str()) {
+            ("application", "x-www-form-urlencoded") => HttpFormEncoding::UrlEncoded,
+            ("multipart", "form-data") => {
+                let boundary = content_type.boundary()
+                    .filter(|boundary| (1..=70).contains(&boundary.len()))
+                    .ok_or(HttpStatusCode::BadRequest400)?;
+                HttpFormEncoding::Multipart {
+                    delimiter: format!("\r\n--{boundary}").into_bytes(),
+                    state: MultipartState::Preamble,
+                }
+            },
+            _ => return Err(HttpStatusCode::UnsupportedMediaType415),
+        };
+        // The first delimiter has no line ending before it, so one is added to find it like the others
+        let buffer = match encoding {
+            HttpFormEncoding::UrlEncoded => Vec::new(),
+            HttpFormEncoding::Multipart { .. } => b"\r\n".to_vec(),
+        };
+        Ok(HttpFormParser {
+            limits: limits.clone(),
+            form: HttpForm::default(),
+            encoding,
+            buffer,
+            total_size: 0,
+        })
+    }
+
+    /// Parses the next part of the body.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<(), HttpStatusCode> {
+        self.buffer.extend_from_slice(bytes);
+        match self.encoding {
+            HttpFormEncoding::UrlEncoded => self.parse_url_encoded(false),
+            HttpFormEncoding::Multipart { .. } => self.parse_multipart(),
+        }
+    }
+
+    /// Finishes parsing once the whole body has been pushed, returning the form.
+    pub fn finish(mut self) -> Result<HttpForm, HttpStatusCode> {
+        match &self.encoding {
+            HttpFormEncoding::UrlEncoded => self.parse_url_encoded(true)?,
+            HttpFormEncoding::Multipart { state: MultipartState::Epilogue, .. } => (),
+            HttpFormEncoding::Multipart { .. } => return Err(HttpStatusCode::BadRequest400),
+        }
+        Ok(mem::take(&mut self.form))
+    }
+
+    /// Parses the complete `name=value` pairs in the buffer, and the rest of it if `is_finished` is `true`.
+    fn parse_url_encoded(&mut self, is_finished: bool) -> Result<(), HttpStatusCode> {
+        let pair_delimiter = b'&';
+        let value_delimiter = b'=';
+        // The last pair may not have been received in full, so it is kept until there is a delimiter after it
+        let complete_length = match is_finished {
+            true => self.buffer.len(),
+            false => self.buffer.iter().rposition(|byte| *byte == pair_delimiter).map_or(0, |index| index + 1),
+        };
+        let complete_pairs: Vec<u8> = self.buffer.drain(..complete_length).collect();
+        for pair in complete_pairs.split(|byte| *byte == pair_delimiter).filter(|pair| !pair.is_empty()) {
+            let (name, value) = match pair.iter().position(|byte| *byte == value_delimiter) {
+                None => (pair, &[][..]),
+                Some(index) => (&pair[..index], &pair[index + 1..]),
+            };
+            let decode = |bytes: &[u8]| percent::decode_bytes(bytes, true).and_then(|bytes| String::from_utf8(bytes).ok());
+            let name = decode(name).ok_or(HttpStatusCode::BadRequest400)?;
+            let value = decode(value).ok_or(HttpStatusCode::BadRequest400)?;
+            self.add_part_size(value.len(), self.limits.maximum_field_size)?;
+            self.form.fields.push(HttpFormField { name, value });
+        }
+        // The pair that isn't finished can already be too large
+        match self.limits.maximum_field_size > 0 && self.buffer.len() > 3 * self.limits.maximum_field_size {
+            false => Ok(()),
+            true => Err(HttpStatusCode::ContentTooLarge413),
+        }
+    }
+
+    fn parse_multipart(&mut self) -> Result<(), HttpStatusCode> {
+        let HttpFormEncoding::Multipart { delimiter, state } = &mut self.encoding else {
+            return Ok(())
+        };
+        let delimiter = delimiter.clone();
+        loop {
+            match state {
+                MultipartState::Preamble => match find(&self.buffer, &delimiter) {
+                    None => {
+                        // Keeps what could be the start of a delimiter
+                        let keep_from = self.buffer.len().saturating_sub(delimiter.len() - 1);
+                        self.buffer.drain(..keep_from);
+                        return Ok(())
+                    },
+                    Some(index) => {
+                        self.buffer.drain(..index + delimiter.len());
+                        *state = MultipartState::Delimiter;
+                    },
+                },
+                MultipartState::Delimiter => {
+                    if self.buffer.starts_with(b"--") {
+                        self.buffer.clear();
+                        *state = MultipartState::Epilogue;
+                        continue
+                    }
+                    // Transport padding can follow the delimiter
+                    let Some(line_end) = find(&self.buffer, b"\r\n") else {
+                        return match self.buffer.len() > MAXIMUM_PART_HEADER_SIZE {
+                            false => Ok(()),
+                            true => Err(HttpStatusCode::BadRequest400),
+                        }
+                    };
+                    if !self.buffer[..line_end].iter().all(|byte| matches!(byte, b' ' | b'\t')) {
+                        return Err(HttpStatusCode::BadRequest400)
+                    }
+                    self.buffer.drain(..line_end + 2);
+                    *state = MultipartState::Header;
+                },
+                MultipartState::Header => {
+                    let header_end = match find(&self.buffer, b"\r\n\r\n") {
+                        // A part without fields has only the empty line that ends the header
+                        _ if self.buffer.starts_with(b"\r\n") => 0,
+                        Some(index) if index <= MAXIMUM_PART_HEADER_SIZE => index + 2,
+                        Some(_) => return Err(HttpStatusCode::BadRequest400),
+                        None if self.buffer.len() > MAXIMUM_PART_HEADER_SIZE => return Err(HttpStatusCode::BadRequest400),
+                        None => return Ok(()),
+                    };
+                    let header = HttpHeader::from_bytes(&self.buffer[..header_end], HttpParserMode::Lenient)
+                        .map_err(|_| HttpStatusCode::BadRequest400)?
+                        .unwrap_or_default();
+                    self.buffer.drain(..header_end + 2);
+                    if self.limits.maximum_part_count > 0 && self.form.fields.len() + self.form.files.len() >= self.limits.maximum_part_count {
+                        return Err(HttpStatusCode::ContentTooLarge413)
+                    }
+                    *state = MultipartState::Body(Part::new(&header)?);
+                },
+                MultipartState::Body(part) => {
+                    let (data_length, is_complete) = match find(&self.buffer, &delimiter) {
+                        None => (self.buffer.len().saturating_sub(delimiter.len() - 1), false),
+                        Some(index) => (index, true),
+                    };
+                    let size_limit = match part {
+                        Part::Field(..) => self.limits.maximum_field_size,
+                        Part::File(_) => 0,
+                    };
+                    self.total_size += data_length;
+                    let part_size = match part {
+                        Part::Field(_, value) => value.len() + data_length,
+                        Part::File(file) => file.size + data_length,
+                    };
+                    if (size_limit > 0 && part_size > size_limit) || (self.limits.maximum_total_size > 0 && self.total_size > self.limits.maximum_total_size) {
+                        return Err(HttpStatusCode::ContentTooLarge413)
+                    }
+                    match part {
+                        Part::Field(_, value) => value.extend_from_slice(&self.buffer[..data_length]),
+                        Part::File(file) => file.write(&self.buffer[..data_length], &self.limits)?,
+                    }
+                    self.buffer.drain(..data_length);
+                    if !is_complete {
+                        return Ok(())
+                    }
+                    self.buffer.drain(..delimiter.len());
+                    match mem::replace(state, MultipartState::Delimiter) {
+                        MultipartState::Body(Part::Field(name, value)) => {
+                            let value = String::from_utf8(value).map_err(|_| HttpStatusCode::BadRequest400)?;
+                            self.form.fields.push(HttpFormField { name, value });
+                        },
+                        MultipartState::Body(Part::File(mut file)) => {
+                            file.finish()?;
+                            self.form.files.push(file);
+                        },
+                        _ => (),
+                    }
+                },
+                MultipartState::Epilogue => {
+                    self.buffer.clear();
+                    return Ok(())
+                },
+            }
+        }
+    }
+
+    /// Counts a value of `size` bytes towards the total size, checking it against `size_limit` and the total size limit.
+    fn add_part_size(&mut self, size: usize, size_limit: usize) -> Result<(), HttpStatusCode> {
+        self.total_size += size;
+        let part_count = self.form.fields.len() + self.form.files.len() + 1;
+        let exceeds_limit = (size_limit > 0 && size > size_limit)
+            || (self.limits.maximum_total_size > 0 && self.total_size > self.limits.maximum_total_size)
+            || (self.limits.maximum_part_count > 0 && part_count > self.limits.maximum_part_count);
+        match exceeds_limit {
+            false => Ok(()),
+            true => Err(HttpStatusCode::ContentTooLarge413),
+        }
+    }
+}
+
+impl Part {
+    /// Starts a part from its header, which must have a `Content-Disposition` of `form-data` with a name.
+    fn new(header: &HttpHeader) -> Result<Self, HttpStatusCode> {
+        let parameter_delimiter = ';';
+        let content_disposition = header.get_value(&HttpFieldName::ContentDisposition).ok_or(HttpStatusCode::BadRequest400)?;
+        let (disposition, parameters) = content_disposition.split_once(parameter_delimiter).unwrap_or((content_disposition, ""));
+        if !disposition.trim().eq_ignore_ascii_case("form-data") {
+            return Err(HttpStatusCode::BadRequest400)
+        }
+        let parameters = syntax::parse_parameters(parameters);
+        let parameter = |name: &str| parameters.iter().find(|(parameter_name, _)| parameter_name == name).map(|(_, value)| value.clone());
+        let name = parameter("name").ok_or(HttpStatusCode::BadRequest400)?;
+        match parameter("filename") {
+            None => Ok(Part::Field(name, Vec::new())),
+            Some(filename) => Ok(Part::File(HttpFormFile {
+                field_name: name,
+                filename,
+                content_type: header.content_type(),
+                size: 0,
+                data: HttpFormFileData::Memory(Vec::new()),
+            })),
+        }
+    }
+}
+
+/// Finds the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content_type: &str, body: &[u8], limits: &HttpFormLimits) -> Result<HttpForm, HttpStatusCode> {
+        let content_type = content_type.parse::<HttpContentType>().expect("`content_type` should be valid");
+        let mut parser = HttpFormParser::new(&content_type, limits)?;
+        parser.push(body)?;
+        parser.finish()
+    }
+
+    /// Parses `body` pushed one byte at a time.
+    fn parse_bytewise(content_type: &str, body: &[u8], limits: &HttpFormLimits) -> Result<HttpForm, HttpStatusCode> {
+        let content_type = content_type.parse::<HttpContentType>().expect("`content_type` should be valid");
+        let mut parser = HttpFormParser::new(&content_type, limits)?;
+        for byte in body {
+            parser.push(&[*byte])?;
+        }
+        parser.finish()
+    }
+
+    const URL_ENCODED: &str = "application/x-www-form-urlencoded";
+    const MULTIPART: &str = "multipart/form-data; boundary=boundary";
+
+    #[test]
+    fn parses_url_encoded() {
+        let body = b"q=rust+lang&empty=&flag&&tag=a%26b&tag=%E2%9C%93";
+        for form in [parse(URL_ENCODED, body, &HttpFormLimits::default()), parse_bytewise(URL_ENCODED, body, &HttpFormLimits::default())] {
+            let form = form.expect("form should be valid");
+            assert_eq!(form.get("q"), Some("rust lang"));
+            assert_eq!(form.get("empty"), Some(""));
+            assert_eq!(form.get("flag"), Some(""));
+            assert_eq!(form.get_all("tag"), vec!("a&b", "✓"));
+            assert_eq!(form.fields.len(), 5);
+        }
+        assert!(parse(URL_ENCODED, b"", &HttpFormLimits::default()).expect("form should be valid").fields.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_url_encoded() {
+        assert_eq!(parse(URL_ENCODED, b"a=%zz", &HttpFormLimits::default()).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse(URL_ENCODED, b"a=%FF", &HttpFormLimits::default()).err(), Some(HttpStatusCode::BadRequest400));
+    }
+
+    #[test]
+    fn limits_url_encoded() {
+        let limits = HttpFormLimits { maximum_field_size: 4, maximum_part_count: 2, ..Default::default() };
+        assert!(parse(URL_ENCODED, b"a=1234&b=1", &limits).is_ok());
+        assert_eq!(parse(URL_ENCODED, b"a=12345", &limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+        assert_eq!(parse(URL_ENCODED, b"a=1&b=2&c=3", &limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+        let total_limits = HttpFormLimits { maximum_total_size: 3, ..Default::default() };
+        assert_eq!(parse(URL_ENCODED, b"a=12&b=34", &total_limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+
+        // A pair is rejected before it is finished if it can't fit in the limit
+        let content_type = URL_ENCODED.parse::<HttpContentType>().expect("content type should be valid");
+        let mut parser = HttpFormParser::new(&content_type, &limits).expect("parser should be created");
+        assert_eq!(parser.push(&[b'a'; 13]).err(), Some(HttpStatusCode::ContentTooLarge413));
+    }
+
+    #[test]
+    fn parses_many_url_encoded_pairs_in_linear_time() {
+        let body = "a=1&".repeat(200_000);
+        let limits = HttpFormLimits { maximum_part_count: 0, ..Default::default() };
+        assert_eq!(parse(URL_ENCODED, body.as_bytes(), &limits).expect("form should be valid").fields.len(), 200_000);
+    }
+
+    #[test]
+    fn parses_multipart() {
+        let body = concat!(
+            "preamble\r\n",
+            "--boundary  \r\n",
+            "Content-Disposition: form-data; name=\"title\"\r\n\r\n",
+            "Hello\r\n--boundar\r\n",
+            "--boundary\r\n",
+            "Content-Disposition: form-data; name=\"upload\"; filename=\"a.txt\"\r\n",
+            "Content-Type: text/plain\r\n\r\n",
+            "file\r\ncontents\r\n",
+            "--boundary--\r\n",
+            "epilogue",
+        ).as_bytes();
+        for form in [parse(MULTIPART, body, &HttpFormLimits::default()), parse_bytewise(MULTIPART, body, &HttpFormLimits::default())] {
+            let form = form.expect("form should be valid");
+            assert_eq!(form.get("title"), Some("Hello\r\n--boundar"));
+            let file = form.file("upload").expect("file should exist");
+            assert_eq!(file.filename, "a.txt");
+            assert!(file.content_type.as_ref().is_some_and(|content_type| content_type.is("text", "plain")));
+            assert_eq!(file.size, 14);
+            assert_eq!(file.bytes().ok().as_deref(), Some(&b"file\r\ncontents"[..]));
+            assert_eq!(file.temporary_path(), None);
+        }
+    }
+
+    #[test]
+    fn writes_large_files_to_temporary_files() {
+        let body = b"--boundary\r\nContent-Disposition: form-data; name=\"f\"; filename=\"f\"\r\n\r\n0123456789\r\n--boundary--";
+        let limits = HttpFormLimits { memory_threshold: 4, ..Default::default() };
+        let mut form = parse_bytewise(MULTIPART, body, &limits).expect("form should be valid");
+        let file = form.files.pop().expect("file should exist");
+        let temporary_path = file.temporary_path().expect("file should be temporary").to_path_buf();
+        assert_eq!(fs::read(&temporary_path).ok().as_deref(), Some(&b"0123456789"[..]));
+        drop(file);
+        assert!(!temporary_path.exists());
+
+        let mut form = parse(MULTIPART, body, &limits).expect("form should be valid");
+        let file = form.files.pop().expect("file should exist");
+        let temporary_path = file.temporary_path().expect("file should be temporary").to_path_buf();
+        let persisted_path = std::env::temp_dir().join(format!("form-test-{}", random::uuid()));
+        file.persist(&persisted_path).expect("file should be persisted");
+        assert!(!temporary_path.exists());
+        assert_eq!(fs::read(&persisted_path).ok().as_deref(), Some(&b"0123456789"[..]));
+        _ = fs::remove_file(&persisted_path);
+    }
+
+    #[test]
+    fn rejects_invalid_multipart() {
+        let limits = HttpFormLimits::default();
+        let field = |header: &str| format!("--boundary\r\n{header}\r\n\r\nvalue\r\n--boundary--");
+        assert_eq!(parse(MULTIPART, field("Content-Disposition: form-data").as_bytes(), &limits).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse(MULTIPART, field("Content-Disposition: attachment; name=\"a\"").as_bytes(), &limits).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse(MULTIPART, field("Content-Type: text/plain").as_bytes(), &limits).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse(MULTIPART, b"--boundary\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nvalue", &limits).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse(MULTIPART, b"--boundary junk\r\n", &limits).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse("multipart/form-data", b"", &limits).err(), Some(HttpStatusCode::BadRequest400));
+        assert_eq!(parse("text/plain", b"", &limits).err(), Some(HttpStatusCode::UnsupportedMediaType415));
+    }
+
+    #[test]
+    fn limits_multipart() {
+        let part = |name: &str, filename: Option<&str>, value: &str| {
+            let filename = filename.map(|filename| format!("; filename=\"{filename}\"")).unwrap_or_default();
+            format!("--boundary\r\nContent-Disposition: form-data; name=\"{name}\"{filename}\r\n\r\n{value}\r\n")
+        };
+        let body = |parts: &[String]| format!("{}--boundary--", parts.concat());
+
+        let field_limits = HttpFormLimits { maximum_field_size: 3, ..Default::default() };
+        assert!(parse(MULTIPART, body(&[part("a", None, "123")]).as_bytes(), &field_limits).is_ok());
+        assert_eq!(parse(MULTIPART, body(&[part("a", None, "1234")]).as_bytes(), &field_limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+        // The field size limit doesn't apply to files
+        assert!(parse(MULTIPART, body(&[part("a", Some("a"), "1234")]).as_bytes(), &field_limits).is_ok());
+
+        let file_limits = HttpFormLimits { maximum_file_size: 3, ..Default::default() };
+        assert_eq!(parse(MULTIPART, body(&[part("a", Some("a"), "1234")]).as_bytes(), &file_limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+
+        let count_limits = HttpFormLimits { maximum_part_count: 1, ..Default::default() };
+        assert_eq!(parse(MULTIPART, body(&[part("a", None, "1"), part("b", None, "2")]).as_bytes(), &count_limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+
+        let total_limits = HttpFormLimits { maximum_total_size: 3, ..Default::default() };
+        assert_eq!(parse(MULTIPART, body(&[part("a", None, "12"), part("b", Some("b"), "34")]).as_bytes(), &total_limits).err(), Some(HttpStatusCode::ContentTooLarge413));
+    }
+}
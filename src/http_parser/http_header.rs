@@ -1,82 +1,226 @@
 use core::fmt;
 use std::collections::{hash_map, HashMap};
 
-use crate::helper::bytes;
+use super::{HttpAccept, HttpAuthorization, HttpCacheControl, HttpContentType, HttpDate, HttpFieldName, HttpParseError, HttpParserMode};
 
-#[derive(Clone, Debug)]
-pub struct HttpHeader(pub HashMap<String, String>);
+#[derive(Clone, Debug, Default)]
+pub struct HttpHeader(pub HashMap<HttpFieldName, String>);
 
 impl HttpHeader {
     pub fn new() -> Self {
         HttpHeader(HashMap::new())
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        let mut fields = HashMap::new();
-        let field_name_delimiter = b":";
-        let line_delimiter = b"\r\n";
-        let mut unprocessed_bytes = &bytes[..];
-        while unprocessed_bytes.len() > 0 {
-            let field_name_separator_index = match bytes::find(unprocessed_bytes, field_name_delimiter) {
-                None => break,
-                Some(index) => index,
-            };
-            let field_name = &unprocessed_bytes[..field_name_separator_index];
-            let new_start_index = if field_name_separator_index >= unprocessed_bytes.len() { unprocessed_bytes.len() } else { field_name_separator_index + field_name_delimiter.len() };
-            unprocessed_bytes = &unprocessed_bytes[new_start_index..];
+    /// Parses the field lines of a request, not including the empty line that ends them.
+    ///
+    /// Fields with the same name are combined into a single comma-separated value.
+    ///
+    /// # Bad Data
+    /// In [`HttpParserMode::Strict`], any malformed field line, obs-fold, bare CR or LF, or a
+    /// repeated `Host` field returns a [`HttpParseError`], whose offset is relative to the start of `bytes`.
+    ///
+    /// In [`HttpParserMode::Lenient`], a bare LF is accepted as a line ending, obs-fold is
+    /// replaced with a space, whitespace around the field name is trimmed and any other
+    /// malformed field line (including non-UTF8 ones) is ignored.
+    pub fn from_bytes(bytes: &[u8], mode: HttpParserMode) -> Result<Option<Self>, HttpParseError> {
+        let field_name_delimiter = b':';
+        let mut fields: HashMap<HttpFieldName, String> = HashMap::new();
+        let mut last_field_name: Option<HttpFieldName> = None;
+        for (offset, line) in Self::split_lines(bytes, mode)? {
+            if line.is_empty() {
+                continue;
+            }
+
+            // obs-fold: a line starting with whitespace continues the previous field's value
+            if is_whitespace(line[0]) {
+                match (mode, &last_field_name) {
+                    (HttpParserMode::Lenient, Some(field_name)) => {
+                        if let Ok(continuation) = std::str::from_utf8(line) {
+                            let value = fields.get_mut(field_name).expect("`last_field_name` should be in `fields`");
+                            value.push(' ');
+                            value.push_str(trim_whitespace(continuation));
+                        }
+                        continue;
+                    },
+                    (HttpParserMode::Lenient, None) => continue,
+                    (HttpParserMode::Strict, _) => return Err(HttpParseError::ObsoleteLineFolding { offset }),
+                }
+            }
 
-            let field_value_separator_index = match bytes::find(unprocessed_bytes, line_delimiter) {
-                None => unprocessed_bytes.len(),
+            let field_name_separator_index = match line.iter().position(|byte| *byte == field_name_delimiter) {
+                None if mode == HttpParserMode::Lenient => continue,
+                None => return Err(HttpParseError::MissingColon { offset }),
                 Some(index) => index,
             };
-            let field_value = &unprocessed_bytes[..field_value_separator_index];
-            let new_start_index = if field_value_separator_index >= unprocessed_bytes.len() { unprocessed_bytes.len() } else { field_value_separator_index + line_delimiter.len() };
-            unprocessed_bytes = &unprocessed_bytes[new_start_index..];
+            let field_name = &line[..field_name_separator_index];
+            let field_value = &line[(field_name_separator_index + 1)..];
+            let field_value_offset = offset + field_name_separator_index + 1;
 
-            let field_name = match std::str::from_utf8(field_name) {
-                Err(_) => continue,
-                Ok(field_name) => field_name.trim(),
+            if mode == HttpParserMode::Strict && field_name.last().is_some_and(|byte| byte.is_ascii_whitespace()) {
+                return Err(HttpParseError::WhitespaceBeforeColon { offset: offset + field_name_separator_index - 1 })
+            }
+            let field_name = match std::str::from_utf8(field_name).ok().and_then(|field_name| field_name.parse::<HttpFieldName>().ok()) {
+                None if mode == HttpParserMode::Lenient => continue,
+                None => return Err(HttpParseError::InvalidFieldName { offset }),
+                Some(field_name) => field_name,
             };
             let field_value = match std::str::from_utf8(field_value) {
-                Err(_) => continue,
-                Ok(field_value) => field_value.trim(),
+                Err(_) if mode == HttpParserMode::Lenient => continue,
+                Err(error) => return Err(HttpParseError::InvalidFieldValue { offset: field_value_offset + error.valid_up_to(), field_name: field_name.to_string() }),
+                Ok(field_value) => field_value,
             };
+            if mode == HttpParserMode::Strict {
+                if let Some(index) = field_value.bytes().position(|byte| byte.is_ascii_control() && byte != b'\t') {
+                    return Err(HttpParseError::InvalidFieldValue { offset: field_value_offset + index, field_name: field_name.to_string() })
+                }
+            }
+            let field_value = trim_whitespace(field_value);
 
-            match fields.entry(field_name.to_owned()) {
+            match fields.entry(field_name.clone()) {
                 hash_map::Entry::Vacant(entry) => { entry.insert(field_value.to_owned()); },
                 hash_map::Entry::Occupied(mut entry) => {
-                    // let old_value = entry.get();
-                    // let new_value = format!("{old_value}, {field_value}");
-                    // entry.insert(new_value);
-
-                    // let old_value = entry.get_mut();
-                    // *old_value = format!("{old_value}, {field_value}");
-
+                    // A request with several hosts can be routed differently by each server that handles it
+                    if mode == HttpParserMode::Strict && field_name == HttpFieldName::Host {
+                        return Err(HttpParseError::DuplicateHost { offset })
+                    }
                     *entry.get_mut() = format!("{}, {field_value}", entry.get());
                 },
             };
+            last_field_name = Some(field_name);
         }
         match fields.len() {
-            0 => None,
-            _ => Some(HttpHeader(fields)),
+            0 => Ok(None),
+            _ => Ok(Some(HttpHeader(fields))),
         }
     }
 
-    pub fn get_value(&self, key: &str) -> Option<&String> {
+    /// Splits the field lines on CRLF, or on LF in lenient mode, returning each line with its offset.
+    ///
+    /// In strict mode, a CR or LF that isn't part of a CRLF is rejected.
+    fn split_lines(bytes: &[u8], mode: HttpParserMode) -> Result<Vec<(usize, &[u8])>, HttpParseError> {
+        let carriage_return = b'\r';
+        let line_feed = b'\n';
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        let mut unprocessed_lines = bytes.split(|byte| *byte == line_feed).peekable();
+        while let Some(line) = unprocessed_lines.next() {
+            let is_last_line = unprocessed_lines.peek().is_none();
+            let line_length = line.len();
+            let line = match (line.strip_suffix(&[carriage_return]), is_last_line, mode) {
+                (Some(line), false, _) => line,
+                (None, false, HttpParserMode::Strict) => return Err(HttpParseError::BareLineFeed { offset: offset + line_length }),
+                // A bare LF in lenient mode, or the last line, which has no line ending
+                _ => line,
+            };
+            if mode == HttpParserMode::Strict {
+                if let Some(index) = line.iter().position(|byte| *byte == carriage_return) {
+                    return Err(HttpParseError::BareCarriageReturn { offset: offset + index })
+                }
+            }
+            lines.push((offset, line));
+            offset += line_length + 1;
+        }
+        Ok(lines)
+    }
+
+    pub fn get_value(&self, key: &HttpFieldName) -> Option<&String> {
         self.0.get(key)
     }
 
-    pub fn insert(&mut self, key: &str, value: &str) {
-        self.0.insert(key.to_owned(), value.to_owned());
+    pub fn insert(&mut self, key: HttpFieldName, value: &str) {
+        self.0.insert(key, value.to_owned());
+    }
+
+    /// Adds a field, combining it with an existing field with the same name into a comma-separated value.
+    ///
+    /// `Set-Cookie` values can contain commas, so they are kept on separate lines instead, and are
+    /// written as separate field lines.
+    pub fn append(&mut self, key: HttpFieldName, value: &str) {
+        let delimiter = match key {
+            HttpFieldName::SetCookie => "\n",
+            _ => ", ",
+        };
+        match self.0.entry(key) {
+            hash_map::Entry::Vacant(entry) => { entry.insert(value.to_owned()); },
+            hash_map::Entry::Occupied(mut entry) => entry.get_mut().push_str(&format!("{delimiter}{value}")),
+        }
+    }
+
+    pub fn remove(&mut self, key: &HttpFieldName) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    pub fn contains(&self, key: &HttpFieldName) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// Gets the value of `key` parsed as `T`, or [`None`] if it is missing or malformed.
+    pub fn get_parsed<T: std::str::FromStr>(&self, key: &HttpFieldName) -> Option<T> {
+        self.get_value(key).and_then(|value| value.parse().ok())
+    }
+
+    pub fn content_type(&self) -> Option<HttpContentType> {
+        self.get_parsed(&HttpFieldName::ContentType)
+    }
+
+    pub fn content_length(&self) -> Option<usize> {
+        self.get_parsed(&HttpFieldName::ContentLength)
+    }
+
+    pub fn date(&self) -> Option<HttpDate> {
+        self.get_parsed(&HttpFieldName::Date)
+    }
+
+    pub fn cache_control(&self) -> Option<HttpCacheControl> {
+        self.get_parsed(&HttpFieldName::CacheControl)
+    }
+
+    pub fn accept(&self) -> Option<HttpAccept> {
+        self.get_parsed(&HttpFieldName::Accept)
+    }
+
+    pub fn accept_charset(&self) -> Option<HttpAccept> {
+        self.get_parsed(&HttpFieldName::AcceptCharset)
+    }
+
+    pub fn accept_encoding(&self) -> Option<HttpAccept> {
+        self.get_parsed(&HttpFieldName::AcceptEncoding)
+    }
+
+    pub fn accept_language(&self) -> Option<HttpAccept> {
+        self.get_parsed(&HttpFieldName::AcceptLanguage)
+    }
+
+    pub fn authorization(&self) -> Option<HttpAuthorization> {
+        self.get_parsed(&HttpFieldName::Authorization)
+    }
+
+    /// Sets `key` to the formatted `value`, replacing any existing value.
+    pub fn insert_typed<T: fmt::Display>(&mut self, key: HttpFieldName, value: &T) {
+        self.insert(key, &value.to_string());
     }
 }
 
 impl fmt::Display for HttpHeader {
+    /// Writes each field line followed by `\r\n`, without the empty line that ends the header.
+    ///
+    /// A value with several lines, such as the `Set-Cookie` values added with [`HttpHeader::append`],
+    /// is written as a field line for each.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut output = String::new();
         for (key, value) in &self.0 {
-            output.push_str(&format!("{key}: {value}"));
+            for value in value.split('\n') {
+                write!(f, "{key}: {value}\r\n")?;
+            }
         }
-        write!(f, "{output}")
+        Ok(())
     }
 }
+
+/// Returns `true` if `byte` is optional whitespace (a space or horizontal tab).
+fn is_whitespace(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+fn trim_whitespace(slice: &str) -> &str {
+    slice.trim_matches([' ', '\t'])
+}
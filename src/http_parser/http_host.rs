@@ -0,0 +1,206 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::helper::punycode;
+
+use super::HttpUri;
+
+/// The host a request is for, as given by the `Host` field or an absolute-form target.
+///
+/// The name is normalized so it can be compared with a plain string comparison: it is
+/// lowercased, a trailing `.` is removed and internationalized labels are converted to
+/// their `xn--` form. The port is kept separately, as it doesn't select a virtual host.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HttpHost {
+    pub name: String,
+    pub port: Option<u16>,
+}
+
+impl HttpHost {
+    /// Returns `true` if the name is an IP address rather than a domain name.
+    pub fn is_ip_address(&self) -> bool {
+        self.name.contains(':') || self.name.split('.').all(|label| !label.is_empty() && label.bytes().all(|byte| byte.is_ascii_digit()))
+    }
+
+    /// Gets the name in Unicode, decoding any `xn--` labels.
+    pub fn unicode_name(&self) -> String {
+        punycode::domain_to_unicode(&self.name).unwrap_or_else(|| self.name.clone())
+    }
+
+    /// Matches the host against a domain name pattern, returning the labels before the matched part.
+    ///
+    /// A plain pattern, like `example.com`, matches the domain itself, returning `""`, and any of its
+    /// subdomains, returning e.g. `uk.shop` for `uk.shop.example.com`. A pattern starting with `*.`
+    /// only matches subdomains, and the pattern `*` matches any host, returning `""`.
+    ///
+    /// Only whole labels are matched, so `example.com` doesn't match `notexample.com` or
+    /// `example.com.attacker.net`. Patterns are normalized in the same way as the host.
+    pub fn strip_domain(&self, pattern: &str) -> Option<&str> {
+        let wildcard = "*";
+        let wildcard_prefix = "*.";
+        let label_delimiter = '.';
+        if pattern == wildcard {
+            return Some("")
+        }
+        let (domain, subdomain_required) = match pattern.strip_prefix(wildcard_prefix) {
+            None => (pattern, false),
+            Some(domain) => (domain, true),
+        };
+        let domain = Self::normalize_name(domain)?;
+        if self.is_ip_address() {
+            return match !subdomain_required && self.name == domain {
+                true => Some(""),
+                false => None,
+            }
+        }
+        match self.name.strip_suffix(&domain) {
+            Some("") if !subdomain_required => Some(""),
+            Some(subdomain) => match subdomain.strip_suffix(label_delimiter) {
+                Some(subdomain) if !subdomain.is_empty() => Some(subdomain),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Returns `true` if the host matches the domain name `pattern`, as described by [`HttpHost::strip_domain`].
+    pub fn matches(&self, pattern: &str) -> bool {
+        self.strip_domain(pattern).is_some()
+    }
+
+    /// Lowercases `name`, removes a trailing `.` and converts it to ASCII, checking that each
+    /// label only contains letters, digits, `-` and `_`.
+    fn normalize_name(name: &str) -> Option<String> {
+        let label_delimiter = '.';
+        let maximum_name_length = 253;
+        let maximum_label_length = 63;
+        let name = name.strip_suffix(label_delimiter).unwrap_or(name);
+        let name = punycode::domain_to_ascii(name)?;
+        let valid = !name.is_empty()
+            && name.len() <= maximum_name_length
+            && name.split(label_delimiter).all(|label| {
+                !label.is_empty()
+                    && label.len() <= maximum_label_length
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.bytes().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_'))
+            });
+        match valid {
+            true => Some(name),
+            false => None,
+        }
+    }
+}
+
+impl FromStr for HttpHost {
+    type Err = ();
+
+    /// Parses a host and optional port, as given by the `Host` field.
+    ///
+    /// # Bad Data
+    /// Returns an error for user information, a path, an invalid port, or a name that isn't a valid
+    /// domain name or IP address.
+    fn from_str(host: &str) -> Result<Self, Self::Err> {
+        let host = host.trim();
+        let is_ascii = host.is_ascii();
+        // A Unicode name isn't allowed in a URI, so its port is split off without parsing it as one
+        let uri = match is_ascii {
+            true => format!("//{host}").parse::<HttpUri>()?,
+            false => {
+                let (name, port) = match host.rsplit_once(':') {
+                    None => (host, None),
+                    Some((name, port)) => (name, Some(port.parse::<u16>().map_err(|_| ())?)),
+                };
+                HttpUri {
+                    host: Some(name.to_owned()),
+                    port,
+                    ..HttpUri::new()
+                }
+            },
+        };
+        if uri.userinfo.is_some() || !uri.path.is_empty() || uri.query.is_some() || uri.fragment.is_some() {
+            return Err(())
+        }
+        let name = uri.host.ok_or(())?;
+        let name = match name.contains(':') {
+            true => name.to_ascii_lowercase(),
+            false => Self::normalize_name(&name).ok_or(())?,
+        };
+        Ok(HttpHost {
+            name,
+            port: uri.port,
+        })
+    }
+}
+
+impl fmt::Display for HttpHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name.contains(':') {
+            true => write!(f, "[{}]", self.name)?,
+            false => write!(f, "{}", self.name)?,
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(host: &str) -> HttpHost {
+        host.parse().expect("`host` should be valid")
+    }
+
+    #[test]
+    fn normalizes_names() {
+        assert_eq!(host("Example.COM."), HttpHost { name: "example.com".to_owned(), port: None });
+        assert_eq!(host("example.com:8080"), HttpHost { name: "example.com".to_owned(), port: Some(8080) });
+        assert_eq!(host("[::1]:443"), HttpHost { name: "::1".to_owned(), port: Some(443) });
+        assert_eq!(host("[2001:DB8::1]").to_string(), "[2001:db8::1]");
+        assert_eq!(host("127.0.0.1:80").to_string(), "127.0.0.1:80");
+    }
+
+    #[test]
+    fn converts_unicode_names() {
+        let unicode_host = host("Bücher.example:8080");
+        assert_eq!(unicode_host.name, "xn--bcher-kva.example");
+        assert_eq!(unicode_host.port, Some(8080));
+        assert_eq!(unicode_host.unicode_name(), "bücher.example");
+        assert_eq!(host("xn--bcher-kva.example:8080"), unicode_host);
+    }
+
+    #[test]
+    fn rejects_invalid_hosts() {
+        let long_label = "a".repeat(64);
+        let long_name = format!("{}a", "a.".repeat(127));
+        for invalid in ["", "user@example.com", "example.com/path", "example.com:99999", "example.com:x", "-example.com", "exa mple.com", "example..com", "%41.com", "[::1", &long_label, &long_name, "xn--!.com"] {
+            assert!(invalid.parse::<HttpHost>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn identifies_ip_addresses() {
+        assert!(host("192.168.0.1").is_ip_address());
+        assert!(host("[::1]").is_ip_address());
+        assert!(!host("example.com").is_ip_address());
+        assert!(!host("1.example").is_ip_address());
+    }
+
+    #[test]
+    fn strips_domains() {
+        let subdomain_host = host("uk.shop.example.com");
+        assert_eq!(subdomain_host.strip_domain("example.com"), Some("uk.shop"));
+        assert_eq!(subdomain_host.strip_domain("*.example.com"), Some("uk.shop"));
+        assert_eq!(subdomain_host.strip_domain("*"), Some(""));
+        assert_eq!(host("example.com").strip_domain("Example.com."), Some(""));
+        assert_eq!(host("example.com").strip_domain("*.example.com"), None);
+        assert_eq!(host("notexample.com").strip_domain("example.com"), None);
+        assert_eq!(host("example.com.attacker.net").strip_domain("example.com"), None);
+        assert_eq!(host("shop.bücher.example").strip_domain("bücher.example"), Some("shop"));
+        assert!(host("127.0.0.1").matches("127.0.0.1"));
+        assert!(!host("1.127.0.0.1").matches("127.0.0.1"));
+    }
+}
@@ -1,6 +1,9 @@
 use core::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Debug)]
+use crate::helper::syntax;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HttpMethod {
     Get,
     Head,
@@ -10,28 +13,22 @@ pub enum HttpMethod {
     Connect,
     Options,
     Trace,
+    Patch,
+    // WebDAV
+    Propfind,
+    Proppatch,
+    Mkcol,
+    Copy,
+    Move,
+    Lock,
+    Unlock,
+    /// Any other method, which must be a token.
+    Extension(String),
 }
 
 impl HttpMethod {
-    pub fn from_str(method: &str) -> Option<Self> {
-        // Methods are case-sensitive
-        match method.trim() {
-            "GET" => Some(HttpMethod::Get),
-            "HEAD" => Some(HttpMethod::Head),
-            "POST" => Some(HttpMethod::Post),
-            "PUT" => Some(HttpMethod::Put),
-            "DELETE" => Some(HttpMethod::Delete),
-            "CONNECT" => Some(HttpMethod::Connect),
-            "OPTIONS" => Some(HttpMethod::Options),
-            "TRACE" => Some(HttpMethod::Trace),
-            _ => None
-        }
-    }
-}
-
-impl fmt::Display for HttpMethod {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let method = match self {
+    pub fn as_str(&self) -> &str {
+        match self {
             Self::Get => "GET",
             Self::Head => "HEAD",
             Self::Post => "POST",
@@ -40,7 +37,75 @@ impl fmt::Display for HttpMethod {
             Self::Connect => "CONNECT",
             Self::Options => "OPTIONS",
             Self::Trace => "TRACE",
+            Self::Patch => "PATCH",
+            Self::Propfind => "PROPFIND",
+            Self::Proppatch => "PROPPATCH",
+            Self::Mkcol => "MKCOL",
+            Self::Copy => "COPY",
+            Self::Move => "MOVE",
+            Self::Lock => "LOCK",
+            Self::Unlock => "UNLOCK",
+            Self::Extension(method) => method,
+        }
+    }
+
+    /// Returns `true` if the method is read-only, so it can't change the state of the server.
+    ///
+    /// Extension methods are assumed to be unsafe.
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Self::Get | Self::Head | Self::Options | Self::Trace | Self::Propfind)
+    }
+
+    /// Returns `true` if repeating the request has the same effect as making it once,
+    /// so it can be automatically retried.
+    ///
+    /// Extension methods are assumed not to be idempotent.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Self::Put | Self::Delete | Self::Proppatch | Self::Mkcol | Self::Copy | Self::Move | Self::Unlock)
+    }
+
+    /// Returns `true` if responses to the method can be stored by a cache.
+    ///
+    /// `POST` responses are only cacheable if they have explicit freshness information.
+    pub fn is_cacheable(&self) -> bool {
+        matches!(self, Self::Get | Self::Head | Self::Post)
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = ();
+
+    /// Parses a method, which must be a token.
+    ///
+    /// Methods are case-sensitive, so a method that only differs from a known method by case
+    /// becomes an [`HttpMethod::Extension`].
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        let method = match method.trim() {
+            "GET" => HttpMethod::Get,
+            "HEAD" => HttpMethod::Head,
+            "POST" => HttpMethod::Post,
+            "PUT" => HttpMethod::Put,
+            "DELETE" => HttpMethod::Delete,
+            "CONNECT" => HttpMethod::Connect,
+            "OPTIONS" => HttpMethod::Options,
+            "TRACE" => HttpMethod::Trace,
+            "PATCH" => HttpMethod::Patch,
+            "PROPFIND" => HttpMethod::Propfind,
+            "PROPPATCH" => HttpMethod::Proppatch,
+            "MKCOL" => HttpMethod::Mkcol,
+            "COPY" => HttpMethod::Copy,
+            "MOVE" => HttpMethod::Move,
+            "LOCK" => HttpMethod::Lock,
+            "UNLOCK" => HttpMethod::Unlock,
+            method if syntax::is_token(method) => HttpMethod::Extension(method.to_owned()),
+            _ => return Err(())
         };
-        write!(f, "{method}")
+        Ok(method)
+    }
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
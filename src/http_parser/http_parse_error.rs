@@ -0,0 +1,146 @@
+use core::fmt;
+use std::error::Error;
+
+use super::HttpStatusCode;
+
+/// Why a request couldn't be parsed.
+///
+/// Where an error relates to a specific part of the request, `offset` is the index of the
+/// byte in the request where the problem was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpParseError {
+    /// The request line isn't UTF8 or doesn't have exactly a method, target and version.
+    MalformedRequestLine { offset: usize },
+    /// The method contains characters that aren't allowed in a token.
+    InvalidMethod { offset: usize, method: String },
+    MalformedTarget { offset: usize, target: String },
+    MalformedVersion { offset: usize, version: String },
+    /// The version is well-formed but not supported by the server.
+    UnsupportedVersion { offset: usize, version: String },
+    /// A line ended with a LF that wasn't preceded by a CR.
+    BareLineFeed { offset: usize },
+    /// A CR appeared that wasn't followed by a LF.
+    BareCarriageReturn { offset: usize },
+    /// A field line started with whitespace, continuing the previous line (obs-fold).
+    ObsoleteLineFolding { offset: usize },
+    /// A field line has no colon separating the name from the value.
+    MissingColon { offset: usize },
+    WhitespaceBeforeColon { offset: usize },
+    /// A field name isn't UTF8 or contains characters that aren't allowed in a token.
+    InvalidFieldName { offset: usize },
+    /// A field value isn't UTF8 or contains control characters.
+    InvalidFieldValue { offset: usize, field_name: String },
+    /// A HTTP/1.1 request has no `Host`.
+    MissingHost,
+    /// A request has more than one `Host`.
+    DuplicateHost { offset: usize },
+    /// A `Content-Length` isn't a number, or several were given with different values.
+    InvalidContentLength { value: String },
+    /// A request has both a `Content-Length` and a `Transfer-Encoding`.
+    ConflictingFraming,
+    UnsupportedTransferCoding { coding: String },
+    /// The request line is longer than `limit` bytes.
+    RequestLineTooLong { limit: usize },
+    /// The field line starting at `offset` is longer than `limit` bytes.
+    FieldLineTooLong { offset: usize, limit: usize },
+    /// There are more than `limit` field lines.
+    TooManyFields { limit: usize },
+    /// The field lines don't fit in `limit` bytes.
+    HeaderTooLarge { limit: usize },
+    /// The body doesn't fit in `limit` bytes.
+    BodyTooLarge { limit: usize },
+    /// The full request wasn't received within `seconds`.
+    Timeout { seconds: usize },
+}
+
+impl HttpParseError {
+    /// Gets the recommended status code for responding to the request.
+    pub fn status_code(&self) -> HttpStatusCode {
+        match self {
+            Self::UnsupportedTransferCoding { .. } => HttpStatusCode::NotImplemented501,
+            Self::UnsupportedVersion { .. } => HttpStatusCode::HttpVersionNotSupported505,
+            Self::RequestLineTooLong { .. } => HttpStatusCode::UriTooLong414,
+            Self::FieldLineTooLong { .. } | Self::TooManyFields { .. } | Self::HeaderTooLarge { .. } => HttpStatusCode::RequestHeaderFieldsTooLarge431,
+            Self::BodyTooLarge { .. } => HttpStatusCode::ContentTooLarge413,
+            Self::Timeout { .. } => HttpStatusCode::RequestTimeout408,
+            _ => HttpStatusCode::BadRequest400,
+        }
+    }
+
+    /// Gets the index of the byte in the request where the problem was found, if it relates to a specific byte.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::MalformedRequestLine { offset }
+            | Self::InvalidMethod { offset, .. }
+            | Self::MalformedTarget { offset, .. }
+            | Self::MalformedVersion { offset, .. }
+            | Self::UnsupportedVersion { offset, .. }
+            | Self::BareLineFeed { offset }
+            | Self::BareCarriageReturn { offset }
+            | Self::ObsoleteLineFolding { offset }
+            | Self::MissingColon { offset }
+            | Self::WhitespaceBeforeColon { offset }
+            | Self::InvalidFieldName { offset }
+            | Self::InvalidFieldValue { offset, .. }
+            | Self::DuplicateHost { offset }
+            | Self::FieldLineTooLong { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Moves the error's offset forward by `amount` bytes.
+    ///
+    /// This is used when the error was found by parsing a slice that doesn't start at the beginning of the request.
+    pub fn offset_by(mut self, amount: usize) -> Self {
+        match &mut self {
+            Self::MalformedRequestLine { offset }
+            | Self::InvalidMethod { offset, .. }
+            | Self::MalformedTarget { offset, .. }
+            | Self::MalformedVersion { offset, .. }
+            | Self::UnsupportedVersion { offset, .. }
+            | Self::BareLineFeed { offset }
+            | Self::BareCarriageReturn { offset }
+            | Self::ObsoleteLineFolding { offset }
+            | Self::MissingColon { offset }
+            | Self::WhitespaceBeforeColon { offset }
+            | Self::InvalidFieldName { offset }
+            | Self::InvalidFieldValue { offset, .. }
+            | Self::DuplicateHost { offset }
+            | Self::FieldLineTooLong { offset, .. } => *offset += amount,
+            _ => (),
+        }
+        self
+    }
+}
+
+impl fmt::Display for HttpParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedRequestLine { offset } => write!(f, "malformed request line at byte {offset}"),
+            Self::InvalidMethod { offset, method } => write!(f, "invalid method `{method}` at byte {offset}"),
+            Self::MalformedTarget { offset, target } => write!(f, "malformed target `{target}` at byte {offset}"),
+            Self::MalformedVersion { offset, version } => write!(f, "malformed version `{version}` at byte {offset}"),
+            Self::UnsupportedVersion { offset, version } => write!(f, "unsupported version `{version}` at byte {offset}"),
+            Self::BareLineFeed { offset } => write!(f, "bare LF at byte {offset}"),
+            Self::BareCarriageReturn { offset } => write!(f, "bare CR at byte {offset}"),
+            Self::ObsoleteLineFolding { offset } => write!(f, "obsolete line folding at byte {offset}"),
+            Self::MissingColon { offset } => write!(f, "field line without a colon at byte {offset}"),
+            Self::WhitespaceBeforeColon { offset } => write!(f, "whitespace before a field's colon at byte {offset}"),
+            Self::InvalidFieldName { offset } => write!(f, "invalid field name at byte {offset}"),
+            Self::InvalidFieldValue { offset, field_name } => write!(f, "invalid value for field `{field_name}` at byte {offset}"),
+            Self::MissingHost => write!(f, "missing `Host` field"),
+            Self::DuplicateHost { offset } => write!(f, "duplicate `Host` field at byte {offset}"),
+            Self::InvalidContentLength { value } => write!(f, "invalid `Content-Length` `{value}`"),
+            Self::ConflictingFraming => write!(f, "both `Content-Length` and `Transfer-Encoding` fields"),
+            Self::UnsupportedTransferCoding { coding } => write!(f, "unsupported transfer coding `{coding}`"),
+            Self::RequestLineTooLong { limit } => write!(f, "request line longer than {limit} bytes"),
+            Self::FieldLineTooLong { offset, limit } => write!(f, "field line longer than {limit} bytes at byte {offset}"),
+            Self::TooManyFields { limit } => write!(f, "more than {limit} field lines"),
+            Self::HeaderTooLarge { limit } => write!(f, "header larger than {limit} bytes"),
+            Self::BodyTooLarge { limit } => write!(f, "body larger than {limit} bytes"),
+            Self::Timeout { seconds } => write!(f, "request not received within {seconds} seconds"),
+        }
+    }
+}
+
+impl Error for HttpParseError {}
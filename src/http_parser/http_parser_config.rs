@@ -0,0 +1,36 @@
+/// How strictly a request is checked against RFC 9112 while it is parsed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HttpParserMode {
+    /// Rejects anything that could be interpreted differently by another server, such as
+    /// obs-fold, whitespace before a field's colon, invalid token characters, bare LF line
+    /// endings and conflicting framing fields, which are used in request-smuggling attacks.
+    #[default]
+    Strict,
+    /// Accepts the same malformed requests as older versions of the parser, where possible,
+    /// for compatibility with clients that don't follow the RFC.
+    Lenient,
+}
+
+/// The options that control how a [`super::HttpRequest`] is parsed.
+///
+/// The limits are checked as the request is received, so an oversized request is rejected
+/// without waiting for the rest of it. A limit of `0` means there is no limit.
+#[derive(Clone, Debug, Default)]
+pub struct HttpParserConfig {
+    pub mode: HttpParserMode,
+    /// The maximum size of the request line in bytes, not including its line ending.
+    pub maximum_request_line_size: usize,
+    /// The maximum size of a single field line in bytes, not including its line ending.
+    pub maximum_field_line_size: usize,
+    /// The maximum number of field lines.
+    pub maximum_field_count: usize,
+    /// The maximum size of all of the field lines in bytes, including their line endings.
+    pub maximum_header_size: usize,
+}
+
+impl HttpParserConfig {
+    /// Returns `true` if `size` is larger than `limit`, where a `limit` of `0` is unlimited.
+    pub(crate) fn exceeds(size: usize, limit: usize) -> bool {
+        limit > 0 && size > limit
+    }
+}
@@ -4,13 +4,16 @@ use super::{HttpRequest, HttpVersion};
 pub struct PartialHttpRequest<'a> {
     pub request: HttpRequest<'a>,
     pub next_byte: usize,
+    /// Whether the request line and all of the field lines have been parsed.
+    pub header_complete: bool,
 }
 
 impl PartialHttpRequest<'_> {
     pub fn new() -> Self {
         PartialHttpRequest {
             request: HttpRequest::default(),
-            next_byte: 0
+            next_byte: 0,
+            header_complete: false,
         }
     }
 
@@ -18,3 +21,9 @@ impl PartialHttpRequest<'_> {
         &self.request.version
     }
 }
+
+impl Default for PartialHttpRequest<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
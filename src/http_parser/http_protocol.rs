@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HttpProtocol {
     Http,
     Https
@@ -10,4 +11,21 @@ impl HttpProtocol {
             HttpProtocol::Https => 443
         }
     }
+
+    /// Gets the URI scheme for the protocol.
+    pub fn scheme(&self) -> &str {
+        match self {
+            HttpProtocol::Http => "http",
+            HttpProtocol::Https => "https"
+        }
+    }
+
+    /// Gets the protocol for a URI scheme, ignoring case.
+    pub fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" => Some(HttpProtocol::Http),
+            "https" => Some(HttpProtocol::Https),
+            _ => None
+        }
+    }
 }
@@ -1,8 +1,6 @@
-use std::{collections::hash_map, io};
-
 use crate::helper::{bytes, enums::Processing};
 
-use super::{HttpFieldName, HttpHeader, HttpMethod, HttpStatusCode, HttpTarget, HttpVersion, PartialHttpRequest};
+use super::{HttpFieldName, HttpHeader, HttpHost, HttpMethod, HttpParseError, HttpParserConfig, HttpParserMode, HttpTarget, HttpTargetForm, HttpVersion, PartialHttpRequest};
 
 #[derive(Clone, Default, Debug)]
 pub struct HttpRequest<'a> {
@@ -31,86 +29,140 @@ impl HttpRequest<'_> {
     /// more bytes to process, or the [`HttpRequest`] inside it if processing is finished.
     /// 
     /// # Bad Data
-    /// If the request doesn't contain a full, understood request header (method, target
-    /// and HTTP version), this function will return a [`Processing<Finished<Result<(Error, HttpStatusCode)>>>`]
-    /// with a recommended [`HttpStatusCode`]. If field names are unknown, the field will be ignored.
-    /// If field names or field values contain non-UTF8 characters, the entire field line will be ignored.
+    /// If the request doesn't contain a full, understood request line (method, target
+    /// and HTTP version), this function will return a [`Processing<Finished<Result<HttpParseError>>>`],
+    /// which can be used to get a recommended [`super::HttpStatusCode`].
+    /// 
+    /// How field lines are checked depends on the `config`'s [`HttpParserMode`] (see [`HttpHeader::from_bytes`]).
+    /// In both modes, a `Content-Length` that isn't a number, or several `Content-Length`s with different
+    /// values, are rejected, as they make the end of the body ambiguous. In strict mode, a request with both
+    /// `Content-Length` and `Transfer-Encoding`, or a HTTP/1.1 request without a `Host`, is also rejected.
     /// No parsing will be done on the body.
-    pub fn try_parse<'a>(partial_request: &PartialHttpRequest<'a>, request_bytes: &'a [u8]) -> Processing<PartialHttpRequest<'a>, Result<HttpRequest<'a>, (io::Error, HttpStatusCode)>> {
+    pub fn try_parse<'a>(config: &HttpParserConfig, partial_request: &PartialHttpRequest<'a>, request_bytes: &'a [u8]) -> Processing<PartialHttpRequest<'a>, Result<HttpRequest<'a>, HttpParseError>> {
         let mut partial_request = partial_request.clone();
-        let word_delimiter = b" ";
-        let line_delimiter = b"\r\n";
-        let body_delimiter = b"\r\n\r\n";
-        let bad_request = Processing::Finished(Err((io::ErrorKind::InvalidInput.into(), HttpStatusCode::NotImplemented501)));
-        let not_implemented = Processing::Finished(Err((io::ErrorKind::InvalidInput.into(), HttpStatusCode::NotImplemented501)));
+        let request_line_delimiter = b' ';
 
-        // Method
+        // Request line
         if partial_request.request.method.is_none() {
-            partial_request.request.method = match Self::find_until(&mut partial_request, request_bytes, word_delimiter) {
-                None => return Processing::InProgress(partial_request),
-                Some(before_delimiter) => match std::str::from_utf8(before_delimiter) {
-                    Err(_) => return not_implemented,
-                    Ok(slice) => match HttpMethod::from_str(slice) {
-                        None => return not_implemented,
-                        Some(method) => Some(method),
-                    },
-                },
-            }
+            // Empty lines received before the request line should be ignored
+            let request_line_too_long = Processing::Finished(Err(HttpParseError::RequestLineTooLong { limit: config.maximum_request_line_size }));
+            let (request_line_offset, request_line) = loop {
+                let line_start_index = partial_request.next_byte;
+                match Self::find_line(&mut partial_request, request_bytes, config.mode) {
+                    Err(error) => return Processing::Finished(Err(error)),
+                    Ok(None) if HttpParserConfig::exceeds(request_bytes.len() - line_start_index, config.maximum_request_line_size) => return request_line_too_long,
+                    Ok(None) => return Processing::InProgress(partial_request),
+                    Ok(Some([])) => continue,
+                    Ok(Some(line)) if HttpParserConfig::exceeds(line.len(), config.maximum_request_line_size) => return request_line_too_long,
+                    Ok(Some(line)) => break (line_start_index, line),
+                }
+            };
+            let request_line = match std::str::from_utf8(request_line) {
+                Err(_) => return Processing::Finished(Err(HttpParseError::MalformedRequestLine { offset: request_line_offset })),
+                Ok(request_line) => request_line,
+            };
+            let parts: Vec<&str> = match config.mode {
+                HttpParserMode::Strict => request_line.split(request_line_delimiter as char).collect(),
+                HttpParserMode::Lenient => request_line.split_whitespace().collect(),
+            };
+            let [method, target, version] = parts.as_slice() else {
+                return Processing::Finished(Err(HttpParseError::MalformedRequestLine { offset: request_line_offset }))
+            };
+            let offset_of = |part: &str| request_line_offset + (part.as_ptr() as usize - request_line.as_ptr() as usize);
+
+            let method = match method.parse::<HttpMethod>() {
+                Err(_) => return Processing::Finished(Err(HttpParseError::InvalidMethod { offset: offset_of(method), method: method.to_string() })),
+                Ok(method) => method,
+            };
+            let target = match target.parse::<HttpTarget>() {
+                Ok(parsed_target) if parsed_target.is_allowed_for(&method) => parsed_target,
+                _ => return Processing::Finished(Err(HttpParseError::MalformedTarget { offset: offset_of(target), target: target.to_string() })),
+            };
+            let version = match version.parse::<HttpVersion>() {
+                Err(_) if HttpVersion::is_well_formed(version) => return Processing::Finished(Err(HttpParseError::UnsupportedVersion { offset: offset_of(version), version: version.to_string() })),
+                Err(_) => return Processing::Finished(Err(HttpParseError::MalformedVersion { offset: offset_of(version), version: version.to_string() })),
+                Ok(version) => version,
+            };
+            partial_request.request.method = Some(method);
+            partial_request.request.target = Some(target);
+            partial_request.request.version = Some(version);
         }
 
-        // Target
-        if partial_request.request.target.is_none() {
-            partial_request.request.target = match Self::find_until(&mut partial_request, request_bytes, word_delimiter) {
-                None => return Processing::InProgress(partial_request),
-                Some(before_delimiter) => match std::str::from_utf8(before_delimiter) {
-                    Err(_) => return not_implemented,
-                    Ok(slice) => match HttpTarget::from_str(slice) {
-                        Err(_) => return not_implemented,
-                        Ok(target) => Some(target),
+        // Header
+        if !partial_request.header_complete {
+            let field_lines_start_index = partial_request.next_byte;
+            let mut field_count = 0;
+            let field_lines_end_index = loop {
+                let line_start_index = partial_request.next_byte;
+                let line = match Self::find_line(&mut partial_request, request_bytes, config.mode) {
+                    Err(error) => return Processing::Finished(Err(error)),
+                    Ok(Some([])) => break line_start_index,
+                    Ok(line) => line,
+                };
+                // If the line hasn't been fully received, the limits are checked against what has been received so far
+                let (line_length, header_size) = match line {
+                    None => (request_bytes.len() - line_start_index, request_bytes.len() - field_lines_start_index),
+                    Some(line) => {
+                        field_count += 1;
+                        (line.len(), partial_request.next_byte - field_lines_start_index)
                     },
-                },
-            }
-        }
-        
-        // Version
-        if partial_request.request.version.is_none() {
-            partial_request.request.version = match Self::find_until(&mut partial_request, request_bytes, line_delimiter) {
-                None => return Processing::InProgress(partial_request),
-                Some(before_delimiter) => match std::str::from_utf8(before_delimiter) {
-                    Err(_) => return not_implemented,
-                    Ok(slice) => match HttpVersion::from_str(slice) {
-                        None => return not_implemented,
-                        Some(method) => Some(method),
-                    },
-                },
-            }
+                };
+                if HttpParserConfig::exceeds(line_length, config.maximum_field_line_size) {
+                    return Processing::Finished(Err(HttpParseError::FieldLineTooLong { offset: line_start_index, limit: config.maximum_field_line_size }))
+                }
+                if HttpParserConfig::exceeds(field_count, config.maximum_field_count) {
+                    return Processing::Finished(Err(HttpParseError::TooManyFields { limit: config.maximum_field_count }))
+                }
+                if HttpParserConfig::exceeds(header_size, config.maximum_header_size) {
+                    return Processing::Finished(Err(HttpParseError::HeaderTooLarge { limit: config.maximum_header_size }))
+                }
+                if line.is_none() {
+                    return Processing::InProgress(partial_request)
+                }
+            };
+            partial_request.request.header = match HttpHeader::from_bytes(&request_bytes[field_lines_start_index..field_lines_end_index], config.mode) {
+                Err(error) => return Processing::Finished(Err(error.offset_by(field_lines_start_index))),
+                Ok(header) => header,
+            };
+            partial_request.header_complete = true;
         }
 
-        // Header
-        if partial_request.request.header.is_none() {
-            partial_request.request.header = match Self::find_until(&mut partial_request, request_bytes, body_delimiter) {
-                None => return Processing::InProgress(partial_request),
-                Some(before_delimiter) => match HttpHeader::from_bytes(before_delimiter) {
-                    None => None,
-                    Some(header) => Some(header),
-                },
+        if config.mode == HttpParserMode::Strict {
+            let has_host = partial_request.request.header.as_ref().is_some_and(|header| header.contains(&HttpFieldName::Host));
+            if matches!(partial_request.request.version, Some(HttpVersion::Http1Dot1)) && !has_host {
+                return Processing::Finished(Err(HttpParseError::MissingHost))
             }
         }
 
         //Body
         if partial_request.request.body.is_none() {
-            partial_request.request.body = match &mut partial_request.request.header {
+            partial_request.request.body = match &partial_request.request.header {
                 None => None,
                 Some(header) => {
-                    match header.0.entry(HttpFieldName::ContentLength.to_string()) {
-                        hash_map::Entry::Vacant(_) => None,
-                        hash_map::Entry::Occupied(length) => {
-                            match length.get().parse() {
-                                Err(_) => return bad_request,
-                                Ok(end_index) => {
-                                    Some(&request_bytes[partial_request.next_byte..end_index])
-                                },
-                            }
+                    let content_length = header.get_value(&HttpFieldName::ContentLength);
+                    if let Some(transfer_encoding) = header.get_value(&HttpFieldName::TransferEncoding) {
+                        // Both framing fields being present is a sign of request smuggling
+                        if content_length.is_some() && config.mode == HttpParserMode::Strict {
+                            return Processing::Finished(Err(HttpParseError::ConflictingFraming))
+                        }
+                        // `Transfer-Encoding` overrides `Content-Length`, but no transfer codings are supported yet
+                        return Processing::Finished(Err(HttpParseError::UnsupportedTransferCoding { coding: transfer_encoding.to_owned() }))
+                    }
+                    match content_length {
+                        None => None,
+                        Some(content_length) => match Self::parse_content_length(content_length) {
+                            None => return Processing::Finished(Err(HttpParseError::InvalidContentLength { value: content_length.to_owned() })),
+                            Some(length) => {
+                                // A length this large can't be buffered, and would overflow the end index
+                                let end_index = match partial_request.next_byte.checked_add(length) {
+                                    None => return Processing::Finished(Err(HttpParseError::BodyTooLarge { limit: usize::MAX - partial_request.next_byte })),
+                                    Some(end_index) => end_index,
+                                };
+                                if end_index > request_bytes.len() {
+                                    return Processing::InProgress(partial_request)
+                                }
+                                Some(&request_bytes[partial_request.next_byte..end_index])
+                            },
                         },
                     }
                 },
@@ -120,64 +172,66 @@ impl HttpRequest<'_> {
         Processing::Finished(Ok(partial_request.request))
     }
 
-    pub fn subdomain(&self, domain_names: Vec<&str>) -> Option<&str> {
-        if let None = self.header {
-            return None
+    /// Gets the host the request is for.
+    /// 
+    /// An absolute-form target's authority overrides the `Host` field, as required by RFC 9112.
+    pub fn host(&self) -> Option<&str> {
+        if let Some(HttpTarget { form: HttpTargetForm::Absolute, authority: Some(authority), .. }) = &self.target {
+            return Some(authority)
         }
-        let header = self.header.as_ref().expect("`self.header` should be `Some`");
-        let host = match header.get_value(HttpFieldName::Host.to_string().as_str()) {
-            None => return None,
-            Some(host) => host,
+        self.header.as_ref()?.get_value(&HttpFieldName::Host).map(|host| host.as_str())
+    }
+
+    /// Gets the subdomain of the most specific of `domain_names` that the host is in, or `""` if
+    /// the host is one of the `domain_names` itself.
+    ///
+    /// The host is matched as described by [`HttpHost::strip_domain`]. Returns [`None`] if there is
+    /// no host, it isn't valid, or it isn't in any of the `domain_names`.
+    pub fn subdomain(&self, domain_names: &[&str]) -> Option<String> {
+        let host = self.host()?.parse::<HttpHost>().ok()?;
+        domain_names.iter()
+            .filter_map(|domain_name| host.strip_domain(domain_name))
+            .min_by_key(|subdomain| subdomain.len())
+            .map(|subdomain| subdomain.to_owned())
+    }
+
+    /// Finds the next line, returning it without its line ending.
+    /// 
+    /// Lines end with CRLF, or a bare LF in lenient mode. In strict mode, a bare LF returns an error.
+    /// If the end of the line hasn't been received yet, [`None`] is returned.
+    fn find_line<'a>(partial_request: &mut PartialHttpRequest, request_bytes: &'a [u8], mode: HttpParserMode) -> Result<Option<&'a [u8]>, HttpParseError> {
+        let carriage_return = b'\r';
+        let line_feed = b"\n";
+        let line = match Self::find_until(partial_request, request_bytes, line_feed) {
+            None => return Ok(None),
+            Some(line) => line,
         };
-        let subdomain_delimiter = '.';
-        for domain_name in domain_names {
-            match host.find(domain_name) {
-                None => continue,
-                Some(index) => {
-                    return if index > 0 {
-                        let subdomain = &host[..index];
-                        if subdomain.ends_with(subdomain_delimiter) {
-                            Some(&subdomain[..(subdomain.len() - subdomain_delimiter.len_utf8())])
-                        } else {
-                            Some(subdomain)
-                        }
-                    } else {
-                        None
-                    }
-                },
+        match line.strip_suffix(&[carriage_return]) {
+            Some(line) => Ok(Some(line)),
+            None if mode == HttpParserMode::Lenient => Ok(Some(line)),
+            None => Err(HttpParseError::BareLineFeed { offset: partial_request.next_byte - line_feed.len() }),
+        }
+    }
+
+    /// Parses a `Content-Length` value, which may have been combined from several fields.
+    /// 
+    /// Returns [`None`] if any value isn't a number or if the values differ.
+    fn parse_content_length(content_length: &str) -> Option<usize> {
+        let value_delimiter = ',';
+        let mut length = None;
+        for value in content_length.split(value_delimiter).map(|value| value.trim()) {
+            // `parse` would accept a leading `+`, which other servers may not
+            if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None
+            }
+            let value = value.parse::<usize>().ok()?;
+            match length {
+                Some(length) if length != value => return None,
+                _ => length = Some(value),
             }
         }
-        None
-    }
-
-    // pub fn get_target_filepath(&self) -> Filepath {
-    //     let file_path = match &self.target {
-    //         None => return Filepath::empty(),
-    //         Some(target) => {
-    //             if target.len() <= 0 {
-    //                 return Filepath::empty()
-    //             }
-    //             match target.find('?') {
-    //                 None => self.target.expect("`self.target` should be `Some`"),
-    //                 Some(index) => self.target.expect("`self.target` should be `Some`")[..index],
-    //             }
-    //         },
-    //     }.as_str();
-
-    //     let directory_delimiter = '/';
-    //     let (directory, file_name) = match file_path.rfind(directory_delimiter) {
-    //         None => ("", file_path),
-    //         Some(index) => {
-    //             let file_name_start_index = if index >= file_path.len() - 1 { index } else { index + 1 };
-    //             (&file_path[..=index], &file_path[(file_name_start_index)..])
-    //         },
-    //     };
-
-    //     Filepath {
-    //         directory: directory.to_owned(),
-    //         filename: file_name.to_owned(),
-    //     }
-    // }
+        length
+    }
 
     fn find_until<'a>(partial_request: &mut PartialHttpRequest, request_bytes: &'a [u8], delimiter: &[u8]) -> Option<&'a [u8]> {
         let start_index = partial_request.next_byte;
@@ -192,3 +246,206 @@ impl HttpRequest<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_parser::HttpStatusCode;
+
+    fn parse(mode: HttpParserMode, request_bytes: &[u8]) -> Processing<PartialHttpRequest<'_>, Result<HttpRequest<'_>, HttpParseError>> {
+        let config = HttpParserConfig { mode, ..Default::default() };
+        HttpRequest::try_parse(&config, &PartialHttpRequest::new(), request_bytes)
+    }
+
+    /// Parses a whole request, which must be accepted.
+    fn parse_ok(mode: HttpParserMode, request_bytes: &[u8]) -> HttpRequest<'_> {
+        match parse(mode, request_bytes) {
+            Processing::Finished(Ok(request)) => request,
+            Processing::Finished(Err(error)) => panic!("request should be parsed, but got `{error}`"),
+            Processing::InProgress(_) => panic!("request should be complete"),
+        }
+    }
+
+    /// Parses a request, which must be rejected.
+    fn parse_error(mode: HttpParserMode, request_bytes: &[u8]) -> HttpParseError {
+        match parse(mode, request_bytes) {
+            Processing::Finished(Err(error)) => error,
+            _ => panic!("request should be rejected"),
+        }
+    }
+
+    fn field_value<'a>(request: &'a HttpRequest, field_name: &str) -> Option<&'a str> {
+        let field_name = field_name.parse::<HttpFieldName>().expect("field name should be valid");
+        request.header.as_ref()?.get_value(&field_name).map(|value| value.as_str())
+    }
+
+    #[test]
+    fn parses_body_with_content_length() {
+        let request_bytes = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\n\r\nhello";
+        match parse(HttpParserMode::Strict, request_bytes) {
+            Processing::Finished(Ok(request)) => assert_eq!(request.body, Some(&b"hello"[..])),
+            _ => panic!("request should be parsed"),
+        }
+    }
+
+    #[test]
+    fn waits_for_rest_of_body() {
+        let request_bytes = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 10\r\n\r\nhello";
+        assert!(matches!(parse(HttpParserMode::Strict, request_bytes), Processing::InProgress(_)));
+    }
+
+    #[test]
+    fn rejects_content_length_overflowing_end_index() {
+        let request_bytes = format!("POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: {}\r\n\r\n", usize::MAX);
+        for mode in [HttpParserMode::Strict, HttpParserMode::Lenient] {
+            match parse(mode, request_bytes.as_bytes()) {
+                Processing::Finished(Err(error)) => assert_eq!(error.status_code(), HttpStatusCode::ContentTooLarge413),
+                _ => panic!("request should be rejected"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_content_length_too_large_for_usize() {
+        let request_bytes = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 99999999999999999999999\r\n\r\n";
+        match parse(HttpParserMode::Strict, request_bytes) {
+            Processing::Finished(Err(error)) => assert_eq!(error.status_code(), HttpStatusCode::BadRequest400),
+            _ => panic!("request should be rejected"),
+        }
+    }
+
+    #[test]
+    fn handles_obsolete_line_folding() {
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: example.com\r\nX-Folded: a\r\n b\r\n\r\n";
+        assert!(matches!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::ObsoleteLineFolding { .. }));
+        assert_eq!(field_value(&parse_ok(HttpParserMode::Lenient, request_bytes), "X-Folded"), Some("a b"));
+    }
+
+    #[test]
+    fn handles_whitespace_before_colon() {
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: example.com\r\nContent-Length : 5\r\n\r\nhello";
+        assert!(matches!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::WhitespaceBeforeColon { .. }));
+        // The field name is trimmed, so the body is framed by it as another server would
+        assert_eq!(parse_ok(HttpParserMode::Lenient, request_bytes).body, Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn handles_bare_line_feed() {
+        for request_bytes in [&b"GET / HTTP/1.1\nHost: example.com\r\n\r\n"[..], b"GET / HTTP/1.1\r\nHost: example.com\nX-Other: a\r\n\r\n"] {
+            assert!(matches!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::BareLineFeed { .. }));
+            assert_eq!(parse_ok(HttpParserMode::Lenient, request_bytes).host(), Some("example.com"));
+        }
+    }
+
+    #[test]
+    fn handles_bare_carriage_return() {
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: example.com\rX-Other: a\r\n\r\n";
+        assert!(matches!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::BareCarriageReturn { .. }));
+        // The CR is kept in the value, so the invalid host is rejected when the request is routed
+        assert_eq!(parse_ok(HttpParserMode::Lenient, request_bytes).host(), Some("example.com\rX-Other: a"));
+    }
+
+    #[test]
+    fn handles_content_length_with_transfer_encoding() {
+        let request_bytes = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\nhello";
+        assert_eq!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::ConflictingFraming);
+        // `Transfer-Encoding` overrides `Content-Length`, and isn't supported
+        assert!(matches!(parse_error(HttpParserMode::Lenient, request_bytes), HttpParseError::UnsupportedTransferCoding { .. }));
+    }
+
+    #[test]
+    fn handles_duplicate_content_length() {
+        let request_bytes = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!";
+        let same_request_bytes = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\nhello";
+        for mode in [HttpParserMode::Strict, HttpParserMode::Lenient] {
+            assert!(matches!(parse_error(mode, request_bytes), HttpParseError::InvalidContentLength { .. }));
+            assert!(matches!(parse_error(mode, b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: +5\r\n\r\nhello"), HttpParseError::InvalidContentLength { .. }));
+            assert_eq!(parse_ok(mode, same_request_bytes).body, Some(&b"hello"[..]));
+        }
+    }
+
+    #[test]
+    fn handles_duplicate_host() {
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: example.com\r\nHost: attacker.net\r\n\r\n";
+        assert!(matches!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::DuplicateHost { .. }));
+        // The hosts are combined, so the invalid host is rejected when the request is routed
+        assert_eq!(parse_ok(HttpParserMode::Lenient, request_bytes).host(), Some("example.com, attacker.net"));
+    }
+
+    #[test]
+    fn handles_missing_host() {
+        let request_bytes = b"GET / HTTP/1.1\r\nAccept: */*\r\n\r\n";
+        assert_eq!(parse_error(HttpParserMode::Strict, request_bytes), HttpParseError::MissingHost);
+        assert_eq!(parse_ok(HttpParserMode::Lenient, request_bytes).host(), None);
+    }
+
+    fn parse_with_limits(config: &HttpParserConfig, request_bytes: &[u8]) -> Result<(), HttpParseError> {
+        match HttpRequest::try_parse(config, &PartialHttpRequest::new(), request_bytes) {
+            Processing::Finished(result) => result.map(|_| ()),
+            Processing::InProgress(_) => panic!("request should be finished"),
+        }
+    }
+
+    #[test]
+    fn limits_request_line_size() {
+        let request_line = "GET /aaaaaaaaaa HTTP/1.1";
+        let request_bytes = format!("{request_line}\r\nHost: example.com\r\n\r\n");
+        let config = HttpParserConfig { maximum_request_line_size: request_line.len(), ..Default::default() };
+        assert_eq!(parse_with_limits(&config, request_bytes.as_bytes()), Ok(()));
+
+        let config = HttpParserConfig { maximum_request_line_size: request_line.len() - 1, ..Default::default() };
+        let error = parse_with_limits(&config, request_bytes.as_bytes()).expect_err("request line should be too long");
+        assert_eq!(error, HttpParseError::RequestLineTooLong { limit: request_line.len() - 1 });
+        assert_eq!(error.status_code(), HttpStatusCode::UriTooLong414);
+        // The limit is checked before the end of the line is received
+        assert!(parse_with_limits(&config, request_line.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn limits_field_line_size() {
+        let field_line = "X-Long: aaaaaaaaaaaaaaaaaaaaaaaaa";
+        let request_bytes = format!("GET / HTTP/1.1\r\nHost: example.com\r\n{field_line}\r\n\r\n");
+        let config = HttpParserConfig { maximum_field_line_size: field_line.len(), ..Default::default() };
+        assert_eq!(parse_with_limits(&config, request_bytes.as_bytes()), Ok(()));
+
+        let config = HttpParserConfig { maximum_field_line_size: field_line.len() - 1, ..Default::default() };
+        let error = parse_with_limits(&config, request_bytes.as_bytes()).expect_err("field line should be too long");
+        assert!(matches!(error, HttpParseError::FieldLineTooLong { offset: 35, .. }));
+        assert_eq!(error.status_code(), HttpStatusCode::RequestHeaderFieldsTooLarge431);
+        let partial_request_bytes = format!("GET / HTTP/1.1\r\nHost: example.com\r\n{field_line}");
+        assert!(parse_with_limits(&config, partial_request_bytes.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn limits_field_count() {
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: example.com\r\nAccept: */*\r\n\r\n";
+        let config = HttpParserConfig { maximum_field_count: 2, ..Default::default() };
+        assert_eq!(parse_with_limits(&config, request_bytes), Ok(()));
+
+        let config = HttpParserConfig { maximum_field_count: 1, ..Default::default() };
+        let error = parse_with_limits(&config, request_bytes).expect_err("there should be too many fields");
+        assert_eq!(error, HttpParseError::TooManyFields { limit: 1 });
+        assert_eq!(error.status_code(), HttpStatusCode::RequestHeaderFieldsTooLarge431);
+    }
+
+    #[test]
+    fn limits_header_size() {
+        let field_lines = "Host: example.com\r\nAccept: */*\r\n";
+        let request_bytes = format!("GET / HTTP/1.1\r\n{field_lines}\r\n");
+        let config = HttpParserConfig { maximum_header_size: field_lines.len(), ..Default::default() };
+        assert_eq!(parse_with_limits(&config, request_bytes.as_bytes()), Ok(()));
+
+        let config = HttpParserConfig { maximum_header_size: field_lines.len() - 1, ..Default::default() };
+        let error = parse_with_limits(&config, request_bytes.as_bytes()).expect_err("header should be too large");
+        assert_eq!(error, HttpParseError::HeaderTooLarge { limit: field_lines.len() - 1 });
+        assert_eq!(error.status_code(), HttpStatusCode::RequestHeaderFieldsTooLarge431);
+    }
+
+    #[test]
+    fn treats_zero_limits_as_unlimited() {
+        let long_value = "a".repeat(10_000);
+        let many_fields: String = (0..200).map(|index| format!("X-Field-{index}: {long_value}\r\n")).collect();
+        let request_bytes = format!("GET /{long_value} HTTP/1.1\r\nHost: example.com\r\n{many_fields}\r\n");
+        assert_eq!(parse_with_limits(&HttpParserConfig::default(), request_bytes.as_bytes()), Ok(()));
+    }
+}
@@ -1,6 +1,6 @@
 use core::fmt;
 
-use super::{HttpHeader, HttpStatusCode, HttpVersion};
+use super::{HttpDate, HttpFieldName, HttpHeader, HttpMethod, HttpStatusCode, HttpVersion};
 
 pub struct HttpResponse {
     pub version: HttpVersion,
@@ -13,24 +13,104 @@ impl HttpResponse {
     pub fn new(version: &HttpVersion, status_code: &HttpStatusCode, header: &Option<&HttpHeader>, body: &Option<&[u8]>) -> Self {
         HttpResponse {
             version: version.clone(),
-            status_code: status_code.clone(),
+            status_code: *status_code,
             header: header.cloned(),
             body: body.map(|bytes| bytes.to_vec()),
         }
     }
 
+    /// Returns `true` if the status code never allows a body (1xx, 204 and 304).
+    pub fn status_forbids_body(&self) -> bool {
+        self.status_code.is_informational()
+            || self.status_code == HttpStatusCode::NoContent204
+            || self.status_code == HttpStatusCode::NotModified304
+    }
+
+    /// Returns `true` if the body is sent with the chunked transfer coding.
+    pub fn is_chunked(&self) -> bool {
+        self.header.as_ref()
+            .and_then(|header| header.get_value(&HttpFieldName::TransferEncoding))
+            .is_some_and(|transfer_encoding| transfer_encoding.split(',').next_back().is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked")))
+    }
+
+    /// Adds the fields every response needs and decides how the body is framed, before the response is sent.
+    ///
+    /// - `Date` is added if it is missing, as is `Server` if `server` is given.
+    /// - `Connection: close` is added unless `keep_alive` is `true`.
+    /// - Responses with a 1xx, 204 or 304 status code never have a body. 1xx and 204 responses
+    ///   have no `Content-Length` or `Transfer-Encoding`, but a 304 response keeps the
+    ///   `Content-Length` of the response it replaces.
+    /// - A response to a `HEAD` request has no body, but keeps the `Content-Length` it would
+    ///   have had, which is set from the body if it is missing.
+    /// - Any other response is sent with `Transfer-Encoding: chunked` if it has been set, and
+    ///   otherwise with a `Content-Length` matching its body, which is `0` if it has none.
+    pub fn finalize(&mut self, method: Option<&HttpMethod>, keep_alive: bool, server: Option<&str>) {
+        let status_forbids_body = self.status_forbids_body();
+        let is_chunked = self.is_chunked();
+        let body_length = self.body.as_ref().map_or(0, |body| body.len());
+        let header = self.header.get_or_insert_with(HttpHeader::new);
+
+        if !header.contains(&HttpFieldName::Date) {
+            header.insert_typed(HttpFieldName::Date, &HttpDate::now());
+        }
+        if let Some(server) = server.filter(|server| !server.is_empty()) {
+            if !header.contains(&HttpFieldName::Server) {
+                header.insert(HttpFieldName::Server, server);
+            }
+        }
+        match keep_alive {
+            false => header.insert(HttpFieldName::Connection, "close"),
+            true => {
+                header.remove(&HttpFieldName::Connection);
+            },
+        }
+
+        if status_forbids_body {
+            header.remove(&HttpFieldName::TransferEncoding);
+            if self.status_code != HttpStatusCode::NotModified304 {
+                header.remove(&HttpFieldName::ContentLength);
+            }
+            self.body = None;
+            return
+        }
+        if method == Some(&HttpMethod::Head) {
+            if !header.contains(&HttpFieldName::ContentLength) && !is_chunked {
+                header.insert(HttpFieldName::ContentLength, &body_length.to_string());
+            }
+            self.body = None;
+            return
+        }
+        match is_chunked {
+            true => {
+                header.remove(&HttpFieldName::ContentLength);
+                self.body.get_or_insert_with(Vec::new);
+            },
+            false => header.insert(HttpFieldName::ContentLength, &body_length.to_string()),
+        }
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
         bytes.extend_from_slice(self.version.to_string().as_bytes());
-        bytes.extend_from_slice(&[b' ']);
+        bytes.push(b' ');
         bytes.extend_from_slice(self.status_code.to_string().as_bytes());
-        bytes.extend_from_slice(&[b'\r', b'\n']);
+        bytes.extend_from_slice(b"\r\n");
         if let Some(header) = &self.header {
             bytes.extend_from_slice(header.to_string().as_bytes());
         }
-        bytes.extend_from_slice(&[b'\r', b'\n', b'\r', b'\n']);
+        bytes.extend_from_slice(b"\r\n");
         if let Some(body) = &self.body {
-            bytes.extend_from_slice(body);
+            match self.is_chunked() {
+                false => bytes.extend_from_slice(body),
+                true => {
+                    if !body.is_empty() {
+                        bytes.extend_from_slice(format!("{:x}\r\n", body.len()).as_bytes());
+                        bytes.extend_from_slice(body);
+                        bytes.extend_from_slice(b"\r\n");
+                    }
+                    bytes.extend_from_slice(b"0\r\n\r\n");
+                },
+            }
         }
         bytes
     }
@@ -42,6 +122,86 @@ impl fmt::Display for HttpResponse {
             Some(header) => header.to_string(),
             None => String::new(),
         };
-        write!(f, "{} {}\r\n{}\r\n\r\n[Body]", self.version, self.status_code, header)
+        write!(f, "{} {}\r\n{}\r\n[Body]", self.version, self.status_code, header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finalize(status_code: HttpStatusCode, fields: &[(HttpFieldName, &str)], body: Option<&[u8]>, method: HttpMethod) -> HttpResponse {
+        let mut http_header = HttpHeader::new();
+        for (field_name, value) in fields {
+            http_header.insert(field_name.clone(), value);
+        }
+        let mut http_response = HttpResponse::new(&HttpVersion::Http1Dot1, &status_code, &Some(&http_header), &body);
+        http_response.finalize(Some(&method), false, Some("webserver"));
+        http_response
+    }
+
+    fn field<'a>(http_response: &'a HttpResponse, field_name: &HttpFieldName) -> Option<&'a str> {
+        http_response.header.as_ref()?.get_value(field_name).map(|value| value.as_str())
+    }
+
+    #[test]
+    fn keeps_content_length_for_head() {
+        let http_response = finalize(HttpStatusCode::OK200, &[], Some(b"hello"), HttpMethod::Head);
+        assert_eq!(field(&http_response, &HttpFieldName::ContentLength), Some("5"));
+        assert_eq!(http_response.body, None);
+
+        let http_response = finalize(HttpStatusCode::OK200, &[(HttpFieldName::ContentLength, "1024")], None, HttpMethod::Head);
+        assert_eq!(field(&http_response, &HttpFieldName::ContentLength), Some("1024"));
+    }
+
+    #[test]
+    fn removes_framing_for_informational_and_no_content() {
+        for status_code in [HttpStatusCode::Continue100, HttpStatusCode::EarlyHints103, HttpStatusCode::NoContent204] {
+            let fields = [(HttpFieldName::ContentLength, "5"), (HttpFieldName::TransferEncoding, "chunked")];
+            let http_response = finalize(status_code, &fields, Some(b"hello"), HttpMethod::Get);
+            assert_eq!(field(&http_response, &HttpFieldName::ContentLength), None);
+            assert_eq!(field(&http_response, &HttpFieldName::TransferEncoding), None);
+            assert_eq!(http_response.body, None);
+        }
+    }
+
+    #[test]
+    fn keeps_content_length_for_not_modified() {
+        let http_response = finalize(HttpStatusCode::NotModified304, &[(HttpFieldName::ContentLength, "1024")], Some(b"hello"), HttpMethod::Get);
+        assert_eq!(field(&http_response, &HttpFieldName::ContentLength), Some("1024"));
+        assert_eq!(http_response.body, None);
+    }
+
+    #[test]
+    fn sets_content_length_from_body() {
+        let http_response = finalize(HttpStatusCode::NotFound404, &[], None, HttpMethod::Get);
+        assert_eq!(field(&http_response, &HttpFieldName::ContentLength), Some("0"));
+        let http_response = finalize(HttpStatusCode::OK200, &[(HttpFieldName::ContentLength, "1024")], Some(b"hello"), HttpMethod::Post);
+        assert_eq!(field(&http_response, &HttpFieldName::ContentLength), Some("5"));
+        assert!(http_response.as_bytes().ends_with(b"\r\n\r\nhello"));
+    }
+
+    #[test]
+    fn removes_content_length_when_chunked() {
+        let fields = [(HttpFieldName::ContentLength, "5"), (HttpFieldName::TransferEncoding, "gzip, chunked")];
+        let http_response = finalize(HttpStatusCode::OK200, &fields, Some(b"hello"), HttpMethod::Get);
+        assert_eq!(field(&http_response, &HttpFieldName::ContentLength), None);
+        assert!(http_response.as_bytes().ends_with(b"\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn adds_date_server_and_connection() {
+        let http_response = finalize(HttpStatusCode::OK200, &[], None, HttpMethod::Get);
+        assert!(field(&http_response, &HttpFieldName::Date).is_some_and(|date| date.parse::<HttpDate>().is_ok()));
+        assert_eq!(field(&http_response, &HttpFieldName::Server), Some("webserver"));
+        assert_eq!(field(&http_response, &HttpFieldName::Connection), Some("close"));
+
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let fields = [(HttpFieldName::Date, date), (HttpFieldName::Server, "cgi-script"), (HttpFieldName::Connection, "close")];
+        let mut http_response = finalize(HttpStatusCode::OK200, &fields, None, HttpMethod::Get);
+        assert_eq!(field(&http_response, &HttpFieldName::Date), Some(date));
+        assert_eq!(field(&http_response, &HttpFieldName::Server), Some("cgi-script"));
+        http_response.finalize(Some(&HttpMethod::Get), true, None);
+        assert_eq!(field(&http_response, &HttpFieldName::Connection), None);
     }
 }
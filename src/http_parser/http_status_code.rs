@@ -1,55 +1,154 @@
 use core::fmt;
 use std::io;
 
-#[derive(Clone)]
-pub enum HttpStatusCode {
-    Continue100,
-    SwitchingProtocols101,
-    OK200,
-    Created201,
-    Accepted202,
-    NonAuthoritativeInformation203,
-    NoContent204,
-    ResetContent205,
-    PartialContent206,
-    MultipleChoices300,
-    MovedPermanently301,
-    Found302,
-    SeeOther303,
-    NotModified304,
-    UseProxy305,
-    TemporaryRedirect307,
-    PermanentRedirect308,
-    BadRequest400,
-    Unauthorized401,
-    PaymentRequired402,
-    Forbidden403,
-    NotFound404,
-    MethodNowAllowed405,
-    NotAcceptable406,
-    ProxyAuthenticationRequired407,
-    RequestTimeout408,
-    Conflict409,
-    Gone410,
-    LengthRequired411,
-    PreconditionFailed412,
-    ContentTooLarge413,
-    UriTooLong414,
-    UnsupportedMediaType415,
-    RangeNotSatisfiable416,
-    ExpectationFailed417,
-    MisdirectedRequest421,
-    UnprocessableContent422,
-    UpgradeRequired426,
-    InternalServerError500,
-    NotImplemented501,
-    BadGateway502,
-    ServiceUnavailable503,
-    GatewayTimeout504,
-    HttpVersionNotSupported505,
+/// Defines [`HttpStatusCode`] from a list of registered status codes and their canonical reason phrases.
+macro_rules! http_status_codes {
+    ($($variant:ident => ($code:literal, $reason_phrase:literal),)+) => {
+        /// A HTTP status code.
+        ///
+        /// Every status code in the IANA HTTP Status Code Registry (and 418) has its own variant. Any other
+        /// status code between 100 and 599 is kept as an [`HttpStatusCode::Extension`], which can only be
+        /// created by [`HttpStatusCode::from_u16`].
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum HttpStatusCode {
+            $($variant,)+
+            Extension(ExtensionStatusCode),
+        }
+
+        impl HttpStatusCode {
+            pub fn as_u16(&self) -> u16 {
+                match self {
+                    $(Self::$variant => $code,)+
+                    Self::Extension(code) => code.0,
+                }
+            }
+
+            /// Gets the status code for `code`, or [`None`] if it isn't between 100 and 599.
+            pub fn from_u16(code: u16) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$variant),)+
+                    100..=599 => Some(Self::Extension(ExtensionStatusCode(code))),
+                    _ => None,
+                }
+            }
+
+            /// Gets the canonical reason phrase, which is empty for extension status codes.
+            pub fn reason_phrase(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $reason_phrase,)+
+                    Self::Extension(_) => "",
+                }
+            }
+        }
+    };
+}
+
+/// A status code between 100 and 599 without its own [`HttpStatusCode`] variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ExtensionStatusCode(u16);
+
+impl ExtensionStatusCode {
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+}
+
+http_status_codes! {
+    Continue100 => (100, "Continue"),
+    SwitchingProtocols101 => (101, "Switching Protocols"),
+    Processing102 => (102, "Processing"),
+    EarlyHints103 => (103, "Early Hints"),
+    OK200 => (200, "OK"),
+    Created201 => (201, "Created"),
+    Accepted202 => (202, "Accepted"),
+    NonAuthoritativeInformation203 => (203, "Non-Authoritative Information"),
+    NoContent204 => (204, "No Content"),
+    ResetContent205 => (205, "Reset Content"),
+    PartialContent206 => (206, "Partial Content"),
+    MultiStatus207 => (207, "Multi-Status"),
+    AlreadyReported208 => (208, "Already Reported"),
+    ImUsed226 => (226, "IM Used"),
+    MultipleChoices300 => (300, "Multiple Choices"),
+    MovedPermanently301 => (301, "Moved Permanently"),
+    Found302 => (302, "Found"),
+    SeeOther303 => (303, "See Other"),
+    NotModified304 => (304, "Not Modified"),
+    UseProxy305 => (305, "Use Proxy"),
+    TemporaryRedirect307 => (307, "Temporary Redirect"),
+    PermanentRedirect308 => (308, "Permanent Redirect"),
+    BadRequest400 => (400, "Bad Request"),
+    Unauthorized401 => (401, "Unauthorized"),
+    PaymentRequired402 => (402, "Payment Required"),
+    Forbidden403 => (403, "Forbidden"),
+    NotFound404 => (404, "Not Found"),
+    MethodNotAllowed405 => (405, "Method Not Allowed"),
+    NotAcceptable406 => (406, "Not Acceptable"),
+    ProxyAuthenticationRequired407 => (407, "Proxy Authentication Required"),
+    RequestTimeout408 => (408, "Request Timeout"),
+    Conflict409 => (409, "Conflict"),
+    Gone410 => (410, "Gone"),
+    LengthRequired411 => (411, "Length Required"),
+    PreconditionFailed412 => (412, "Precondition Failed"),
+    ContentTooLarge413 => (413, "Content Too Large"),
+    UriTooLong414 => (414, "URI Too Long"),
+    UnsupportedMediaType415 => (415, "Unsupported Media Type"),
+    RangeNotSatisfiable416 => (416, "Range Not Satisfiable"),
+    ExpectationFailed417 => (417, "Expectation Failed"),
+    ImATeapot418 => (418, "I'm a teapot"),
+    MisdirectedRequest421 => (421, "Misdirected Request"),
+    UnprocessableContent422 => (422, "Unprocessable Content"),
+    Locked423 => (423, "Locked"),
+    FailedDependency424 => (424, "Failed Dependency"),
+    TooEarly425 => (425, "Too Early"),
+    UpgradeRequired426 => (426, "Upgrade Required"),
+    PreconditionRequired428 => (428, "Precondition Required"),
+    TooManyRequests429 => (429, "Too Many Requests"),
+    RequestHeaderFieldsTooLarge431 => (431, "Request Header Fields Too Large"),
+    UnavailableForLegalReasons451 => (451, "Unavailable For Legal Reasons"),
+    InternalServerError500 => (500, "Internal Server Error"),
+    NotImplemented501 => (501, "Not Implemented"),
+    BadGateway502 => (502, "Bad Gateway"),
+    ServiceUnavailable503 => (503, "Service Unavailable"),
+    GatewayTimeout504 => (504, "Gateway Timeout"),
+    HttpVersionNotSupported505 => (505, "HTTP Version Not Supported"),
+    VariantAlsoNegotiates506 => (506, "Variant Also Negotiates"),
+    InsufficientStorage507 => (507, "Insufficient Storage"),
+    LoopDetected508 => (508, "Loop Detected"),
+    NotExtended510 => (510, "Not Extended"),
+    NetworkAuthenticationRequired511 => (511, "Network Authentication Required"),
 }
 
 impl HttpStatusCode {
+    /// Returns `true` for 1xx status codes.
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.as_u16())
+    }
+
+    /// Returns `true` for 2xx status codes.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.as_u16())
+    }
+
+    /// Returns `true` for 3xx status codes.
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.as_u16())
+    }
+
+    /// Returns `true` for 4xx status codes.
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    /// Returns `true` for 5xx status codes.
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.as_u16())
+    }
+
+    /// Returns `true` for 4xx and 5xx status codes.
+    pub fn is_error(&self) -> bool {
+        self.is_client_error() || self.is_server_error()
+    }
+
     pub fn from_io_error(error: &io::Error) -> Self {
         // Errors commented out below are unstable.
         match error.kind() {
@@ -67,9 +166,9 @@ impl HttpStatusCode {
             io::ErrorKind::BrokenPipe => Self::InternalServerError500,
             io::ErrorKind::AlreadyExists => Self::Conflict409,
             io::ErrorKind::WouldBlock => Self::InternalServerError500,
-            //io::ErrorKind::NotADirectory => todo!(),
-            //io::ErrorKind::IsADirectory => todo!(),
-            //io::ErrorKind::DirectoryNotEmpty => todo!(),
+            io::ErrorKind::NotADirectory => Self::NotFound404,
+            io::ErrorKind::IsADirectory => Self::NotFound404,
+            io::ErrorKind::DirectoryNotEmpty => Self::Conflict409,
             //io::ErrorKind::ReadOnlyFilesystem => todo!(),
             //io::ErrorKind::FilesystemLoop => todo!(),
             //io::ErrorKind::StaleNetworkFileHandle => todo!(),
@@ -100,52 +199,40 @@ impl HttpStatusCode {
 
 impl fmt::Display for HttpStatusCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let text = match self {
-            HttpStatusCode::Continue100 => "100 CONTINUE",
-            HttpStatusCode::SwitchingProtocols101 => "101 SWITCHING PROTOCOLS",
-            HttpStatusCode::OK200 => "200 OK",
-            HttpStatusCode::Created201 => "201 CREATED",
-            HttpStatusCode::Accepted202 => "202 ACCEPTED",
-            HttpStatusCode::NonAuthoritativeInformation203 => "203 NON AUTHORITATIVE INFORMATION",
-            HttpStatusCode::NoContent204 => "204 NO CONTENT",
-            HttpStatusCode::ResetContent205 => "205 RESET CONTENT",
-            HttpStatusCode::PartialContent206 => "206 PARTIAL CONTENT",
-            HttpStatusCode::MultipleChoices300 => "300 MULTIPLE CHOICES",
-            HttpStatusCode::MovedPermanently301 => "301 MOVED PERMANENTLY",
-            HttpStatusCode::Found302 => "302 FOUND",
-            HttpStatusCode::SeeOther303 => "303 SEE OTHER",
-            HttpStatusCode::NotModified304 => "304 NOT MODIFIED",
-            HttpStatusCode::UseProxy305 => "305 USE PROXY",
-            HttpStatusCode::TemporaryRedirect307 => "307 TEMPORARY REDIRECT",
-            HttpStatusCode::PermanentRedirect308 => "308 PERMANENT REDIRECT",
-            HttpStatusCode::BadRequest400 => "400 BAD REQUEST",
-            HttpStatusCode::Unauthorized401 => "401 UNAUTHORIZED",
-            HttpStatusCode::PaymentRequired402 => "402 PAYMENT REQUIRED",
-            HttpStatusCode::Forbidden403 => "403 FORBIDDEN",
-            HttpStatusCode::NotFound404 => "404 NOT FOUND",
-            HttpStatusCode::MethodNowAllowed405 => "405 METHOD NOW ALLOWED",
-            HttpStatusCode::NotAcceptable406 => "406 NOT ACCEPTABLE",
-            HttpStatusCode::ProxyAuthenticationRequired407 => "407 PROXY AUTHENTICATION REQUIRED",
-            HttpStatusCode::RequestTimeout408 => "408 REQUEST TIMEOUT",
-            HttpStatusCode::Conflict409 => "409 CONFLICT",
-            HttpStatusCode::Gone410 => "410 GONE",
-            HttpStatusCode::LengthRequired411 => "411 LENGTH REQUIRED",
-            HttpStatusCode::PreconditionFailed412 => "412 PRECONDITION FAILED",
-            HttpStatusCode::ContentTooLarge413 => "413 CONTENT TOO LARGE",
-            HttpStatusCode::UriTooLong414 => "414 URI TOO LONG",
-            HttpStatusCode::UnsupportedMediaType415 => "415 UNSUPPORTED MEDIA TYPE",
-            HttpStatusCode::RangeNotSatisfiable416 => "416 RANGE NOT SATISFIABLE",
-            HttpStatusCode::ExpectationFailed417 => "417 EXPECTATION FAILED",
-            HttpStatusCode::MisdirectedRequest421 => "421 MISDIRECTED REQUEST",
-            HttpStatusCode::UnprocessableContent422 => "422 UNPROCESSABLE CONTENT",
-            HttpStatusCode::UpgradeRequired426 => "426 UPGRADE REQUIRED",
-            HttpStatusCode::InternalServerError500 => "500 INTERNAL SERVER ERROR",
-            HttpStatusCode::NotImplemented501 => "501 NOT IMPLEMENTED",
-            HttpStatusCode::BadGateway502 => "502 BAD GATEWAY",
-            HttpStatusCode::ServiceUnavailable503 => "503 SERVICE UNAVAILABLE",
-            HttpStatusCode::GatewayTimeout504 => "504 GATEWAY TIMEOUT",
-            HttpStatusCode::HttpVersionNotSupported505 => "505 HTTP VERSION NOT SUPPORTED",
-        };
-        write!(f, "{text}")
+        write!(f, "{} {}", self.as_u16(), self.reason_phrase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_codes() {
+        for code in 100..=599 {
+            let status_code = HttpStatusCode::from_u16(code).expect("`code` should be in range");
+            assert_eq!(status_code.as_u16(), code);
+        }
+        assert_eq!(HttpStatusCode::from_u16(404), Some(HttpStatusCode::NotFound404));
+        assert_eq!(HttpStatusCode::from_u16(418), Some(HttpStatusCode::ImATeapot418));
+        assert!(matches!(HttpStatusCode::from_u16(299), Some(HttpStatusCode::Extension(code)) if code.as_u16() == 299));
+    }
+
+    #[test]
+    fn rejects_codes_out_of_range() {
+        for code in [0, 99, 600, 1000, u16::MAX] {
+            assert_eq!(HttpStatusCode::from_u16(code), None);
+        }
+    }
+
+    #[test]
+    fn gets_reason_phrases() {
+        assert_eq!(HttpStatusCode::OK200.reason_phrase(), "OK");
+        assert_eq!(HttpStatusCode::ContentTooLarge413.reason_phrase(), "Content Too Large");
+        assert_eq!(HttpStatusCode::NotFound404.to_string(), "404 Not Found");
+        let extension = HttpStatusCode::from_u16(299).expect("299 should be in range");
+        assert_eq!(extension.reason_phrase(), "");
+        assert_eq!(extension.to_string(), "299 ");
+        assert!(extension.is_success());
     }
 }
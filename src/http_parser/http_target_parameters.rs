@@ -1,55 +1,162 @@
-use std::collections::{hash_map, HashMap};
+use core::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Debug)]
-pub struct HttpTargetParameters(HashMap<String, Vec<String>>);
+use crate::helper::percent;
+
+/// The parameters in a target's query string, in the order they were given.
+///
+/// A parameter without a `=` (e.g. `?debug`) is a flag, and has no value. A parameter with
+/// a `=` but nothing after it (e.g. `?debug=`) has an empty value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpTargetParameters(Vec<(String, Option<String>)>);
 
 impl HttpTargetParameters {
-    pub fn from_str(target: &str) -> Result<Self, ()> {
-        let mut parameters = HashMap::new();
-        let query_delimiter = '?';
-        let target = match target.find(query_delimiter) {
-            None => &target[..],
-            Some(index) => &target[(index + 1)..],
-        };
+    pub fn new() -> Self {
+        HttpTargetParameters(Vec::new())
+    }
+
+    /// Gets the first value of `key`. Flags have an empty value.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter()
+            .find(|(parameter_key, _)| parameter_key == key)
+            .map(|(_, value)| value.as_deref().unwrap_or(""))
+    }
+
+    /// Gets every value of `key`, in order. Flags have an empty value.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.0.iter()
+            .filter(|(parameter_key, _)| parameter_key == key)
+            .map(|(_, value)| value.as_deref().unwrap_or(""))
+            .collect()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.iter().any(|(parameter_key, _)| parameter_key == key)
+    }
+
+    /// Returns `true` if `key` is given as a flag, without a `=`.
+    pub fn is_flag(&self, key: &str) -> bool {
+        self.0.iter().any(|(parameter_key, value)| parameter_key == key && value.is_none())
+    }
+
+    /// Adds a parameter after the existing ones.
+    pub fn push(&mut self, key: &str, value: Option<&str>) {
+        self.0.push((key.to_owned(), value.map(|value| value.to_owned())));
+    }
+
+    /// Removes every parameter called `key`.
+    pub fn remove(&mut self, key: &str) {
+        self.0.retain(|(parameter_key, _)| parameter_key != key);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_deref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the parameters as a query string, without the leading `?`.
+    pub fn to_query_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FromStr for HttpTargetParameters {
+    type Err = ();
+
+    /// Parses a query string, without the leading `?`, into its parameters.
+    ///
+    /// Keys and values are percent-decoded, and `+` is decoded as a space. Empty parameters
+    /// (e.g. between `&&`) are skipped. An invalid escape or an escape that doesn't decode to
+    /// UTF8 returns an error.
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut parameters = Vec::new();
         let key_delimiter = '=';
         let parameter_delimiter = '&';
-        let mut unprocessed_text = &target[..];
-        while unprocessed_text.len() > 0 {
-            let key_separator_index = match unprocessed_text.find(key_delimiter) {
-                None => break,
-                Some(index) => index,
+        for parameter in query.split(parameter_delimiter) {
+            if parameter.is_empty() {
+                continue;
+            }
+            let (key, value) = match parameter.split_once(key_delimiter) {
+                None => (parameter, None),
+                Some((key, value)) => (key, Some(value)),
             };
-            let key = &unprocessed_text[..key_separator_index];
-            let new_start_index = if key_separator_index >= unprocessed_text.len() { unprocessed_text.len() } else { key_separator_index + 1 };
-            unprocessed_text = &unprocessed_text[new_start_index..];
-
-            let parameter_separator_index = match unprocessed_text.find(parameter_delimiter) {
-                None => unprocessed_text.len(),
-                Some(index) => index,
+            let key = percent::decode(key, true).ok_or(())?;
+            let value = match value {
+                None => None,
+                Some(value) => Some(percent::decode(value, true).ok_or(())?),
             };
-            let value = &unprocessed_text[..parameter_separator_index];
-            let new_start_index = if parameter_separator_index >= unprocessed_text.len() { unprocessed_text.len() } else { parameter_separator_index + 1 };
-            unprocessed_text = &unprocessed_text[new_start_index..];
-
-            match parameters.entry(key.to_owned()) {
-                hash_map::Entry::Vacant(entry) => {
-                    let mut values = Vec::new();
-                    values.push(value.to_owned());
-                    entry.insert(values);
-                },
-                hash_map::Entry::Occupied(mut entry) => {
-                    let values = entry.get_mut();
-                    values.push(value.to_owned());
-                },
-            }
+            parameters.push((key, value));
         }
-        match parameters.len() {
-            0 => Err(()),
-            _ => Ok(Self(parameters)),
+        Ok(Self(parameters))
+    }
+}
+
+impl fmt::Display for HttpTargetParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parameters: Vec<String> = self.0.iter()
+            .map(|(key, value)| {
+                let key = percent::encode(key, true, percent::is_query_component_character);
+                match value {
+                    None => key,
+                    Some(value) => format!("{key}={}", percent::encode(value, true, percent::is_query_component_character)),
+                }
+            })
+            .collect();
+        write!(f, "{}", parameters.join("&"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(query: &str) -> HttpTargetParameters {
+        query.parse().expect("`query` should be valid")
+    }
+
+    #[test]
+    fn parses_keys_values_and_flags() {
+        let parameters = parameters("a=1&&debug&empty=&a=2");
+        assert_eq!(parameters.len(), 4);
+        assert_eq!(parameters.get("a"), Some("1"));
+        assert_eq!(parameters.get_all("a"), vec!("1", "2"));
+        assert!(parameters.is_flag("debug"));
+        assert!(!parameters.is_flag("empty"));
+        assert_eq!(parameters.get("empty"), Some(""));
+        assert_eq!(parameters.get("missing"), None);
+    }
+
+    #[test]
+    fn decodes_escapes_and_plus() {
+        let parameters = parameters("first+name=J%C3%B6rg+M%c3%bcller&sum=1%2B1%3D2&a%26b=c%3Dd");
+        assert_eq!(parameters.get("first name"), Some("Jörg Müller"));
+        assert_eq!(parameters.get("sum"), Some("1+1=2"));
+        assert_eq!(parameters.get("a&b"), Some("c=d"));
+    }
+
+    #[test]
+    fn rejects_invalid_escapes() {
+        for query in ["a=%", "a=%4", "a=%zz", "%g0=1", "a=%C3", "a=%FF", "%C3%28=1"] {
+            assert_eq!(query.parse::<HttpTargetParameters>(), Err(()), "{query}");
         }
     }
 
-    pub fn get(&self, key: &str) -> Option<&Vec<String>> {
-        self.0.get(key)
+    #[test]
+    fn encodes_when_displayed() {
+        let mut parameters = HttpTargetParameters::new();
+        parameters.push("first name", Some("Jörg"));
+        parameters.push("sum", Some("1+1=2&3"));
+        parameters.push("debug", None);
+        parameters.push("empty", Some(""));
+        let query = parameters.to_query_string();
+        assert_eq!(query, "first+name=J%C3%B6rg&sum=1%2B1%3D2%263&debug&empty=");
+        assert_eq!(query.parse::<HttpTargetParameters>(), Ok(parameters));
     }
 }
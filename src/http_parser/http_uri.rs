@@ -0,0 +1,494 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::helper::percent;
+
+use super::HttpUriBuilder;
+
+/// A URI or relative reference, as defined by RFC 3986.
+///
+/// Each component is kept as it was given, including its `%XX` escapes, until [`HttpUri::normalize`]
+/// is used. The host of an IPv6 address is kept without its brackets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpUri {
+    pub scheme: Option<String>,
+    pub userinfo: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub path: String,
+    /// The query, without the leading `?`.
+    pub query: Option<String>,
+    /// The fragment, without the leading `#`.
+    pub fragment: Option<String>,
+}
+
+impl HttpUri {
+    /// Creates a new, empty relative reference.
+    pub fn new() -> Self {
+        HttpUri::default()
+    }
+
+    /// Creates a [`HttpUriBuilder`], which encodes each component as it is set.
+    pub fn builder() -> HttpUriBuilder {
+        HttpUriBuilder::new()
+    }
+
+    /// Parses an absolute path with an optional query, as used by an origin-form request target,
+    /// returning [`None`] if it isn't valid.
+    ///
+    /// Unlike parsing a URI, a path starting with `//` is kept as a path instead of being read as an authority.
+    pub fn from_path_and_query(path_and_query: &str) -> Option<Self> {
+        let query_delimiter = '?';
+        let valid = path_and_query.starts_with('/')
+            && path_and_query.bytes().all(|byte| Self::is_uri_character(byte) && !matches!(byte, b'#' | b'[' | b']'))
+            && percent::decode_bytes(path_and_query.as_bytes(), false).is_some();
+        if !valid {
+            return None
+        }
+        let (path, query) = match path_and_query.split_once(query_delimiter) {
+            None => (path_and_query, None),
+            Some((path, query)) => (path, Some(query.to_owned())),
+        };
+        Some(HttpUri {
+            path: path.to_owned(),
+            query,
+            ..HttpUri::new()
+        })
+    }
+
+    /// Returns `true` if the URI has a scheme, so it isn't a relative reference.
+    pub fn is_absolute(&self) -> bool {
+        self.scheme.is_some()
+    }
+
+    /// Gets the userinfo, host and port, or [`None`] if the URI has no authority.
+    pub fn authority(&self) -> Option<String> {
+        let host = self.host.as_ref()?;
+        let mut authority = String::new();
+        if let Some(userinfo) = &self.userinfo {
+            authority.push_str(userinfo);
+            authority.push('@');
+        }
+        match host.contains(':') {
+            true => authority.push_str(&format!("[{host}]")),
+            false => authority.push_str(host),
+        }
+        if let Some(port) = self.port {
+            authority.push_str(&format!(":{port}"));
+        }
+        Some(authority)
+    }
+
+    /// Gets the path and query, as used by an origin-form request target.
+    pub fn path_and_query(&self) -> String {
+        match &self.query {
+            None => self.path.clone(),
+            Some(query) => format!("{}?{query}", self.path),
+        }
+    }
+
+    /// Gets the port, or the default port for the scheme if there isn't one.
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.port.or_else(|| Self::default_port(self.scheme.as_deref()?))
+    }
+
+    /// Gets the port used when a URI with `scheme` doesn't give one.
+    pub fn default_port(scheme: &str) -> Option<u16> {
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" | "ws" => Some(80),
+            "https" | "wss" => Some(443),
+            "ftp" => Some(21),
+            _ => None,
+        }
+    }
+
+    /// Gets the percent-decoded segments of the path, without the empty segment before a leading `/`.
+    ///
+    /// Returns [`None`] if a segment doesn't decode to UTF8.
+    pub fn path_segments(&self) -> Option<Vec<String>> {
+        let directory_delimiter = '/';
+        let path = self.path.strip_prefix(directory_delimiter).unwrap_or(&self.path);
+        path.split(directory_delimiter)
+            .map(|segment| percent::decode(segment, false))
+            .collect()
+    }
+
+    /// Gets the equivalent normalized URI.
+    ///
+    /// The scheme and host are lowercased, escapes are normalized with [`percent::normalize`],
+    /// dot segments are removed from the path, a default port is removed and an empty path
+    /// with an authority becomes `/`.
+    pub fn normalize(&self) -> Self {
+        let normalize = |component: &str| percent::normalize(component).unwrap_or_else(|| component.to_owned());
+        let scheme = self.scheme.as_ref().map(|scheme| scheme.to_ascii_lowercase());
+        let port = match (self.port, scheme.as_deref().and_then(Self::default_port)) {
+            (Some(port), Some(default_port)) if port == default_port => None,
+            (port, _) => port,
+        };
+        let mut path = normalize(&self.path);
+        if scheme.is_some() || self.host.is_some() || path.starts_with('/') {
+            path = Self::remove_dot_segments(&path);
+        }
+        if path.is_empty() && self.host.is_some() {
+            path.push('/');
+        }
+        HttpUri {
+            scheme,
+            userinfo: self.userinfo.as_deref().map(normalize),
+            host: self.host.as_ref().map(|host| normalize(host).to_ascii_lowercase()),
+            port,
+            path,
+            query: self.query.as_deref().map(normalize),
+            fragment: self.fragment.as_deref().map(normalize),
+        }
+    }
+
+    /// Resolves `reference` against this URI, as described by RFC 3986 section 5.2.
+    ///
+    /// This is used to turn a relative `Location` or link into the URI it refers to.
+    pub fn resolve(&self, reference: &HttpUri) -> Self {
+        let mut target = HttpUri {
+            fragment: reference.fragment.clone(),
+            ..HttpUri::new()
+        };
+        if reference.scheme.is_some() {
+            target.scheme = reference.scheme.clone();
+            target.set_authority_from(reference);
+            target.path = Self::remove_dot_segments(&reference.path);
+            target.query = reference.query.clone();
+            return target
+        }
+        target.scheme = self.scheme.clone();
+        if reference.host.is_some() {
+            target.set_authority_from(reference);
+            target.path = Self::remove_dot_segments(&reference.path);
+            target.query = reference.query.clone();
+            return target
+        }
+        target.set_authority_from(self);
+        if reference.path.is_empty() {
+            target.path = self.path.clone();
+            target.query = reference.query.clone().or_else(|| self.query.clone());
+            return target
+        }
+        target.path = match reference.path.starts_with('/') {
+            true => Self::remove_dot_segments(&reference.path),
+            false => Self::remove_dot_segments(&self.merge(&reference.path)),
+        };
+        target.query = reference.query.clone();
+        target
+    }
+
+    /// Removes the `.` and `..` segments from `path`, as described by RFC 3986 section 5.2.4.
+    ///
+    /// A `..` segment can't remove the segments before the start of the path, so the result never
+    /// points above its root.
+    pub fn remove_dot_segments(path: &str) -> String {
+        let mut input = path;
+        let mut output = String::with_capacity(path.len());
+        while !input.is_empty() {
+            if let Some(rest) = input.strip_prefix("../").or_else(|| input.strip_prefix("./")) {
+                input = rest;
+            } else if input.starts_with("/./") {
+                input = &input[2..];
+            } else if input == "/." {
+                input = "/";
+            } else if input.starts_with("/../") || input == "/.." {
+                input = match input.len() {
+                    3 => "/",
+                    _ => &input[3..],
+                };
+                let last_segment_index = output.rfind('/').unwrap_or(0);
+                output.truncate(last_segment_index);
+            } else if input == "." || input == ".." {
+                input = "";
+            } else {
+                let segment_end_index = match input.starts_with('/') {
+                    true => input[1..].find('/').map_or(input.len(), |index| index + 1),
+                    false => input.find('/').unwrap_or(input.len()),
+                };
+                output.push_str(&input[..segment_end_index]);
+                input = &input[segment_end_index..];
+            }
+        }
+        output
+    }
+
+    /// Merges a relative path with this URI's path, as described by RFC 3986 section 5.2.3.
+    fn merge(&self, relative_path: &str) -> String {
+        if self.host.is_some() && self.path.is_empty() {
+            return format!("/{relative_path}")
+        }
+        match self.path.rfind('/') {
+            None => relative_path.to_owned(),
+            Some(index) => format!("{}{relative_path}", &self.path[..=index]),
+        }
+    }
+
+    fn set_authority_from(&mut self, uri: &HttpUri) {
+        self.userinfo = uri.userinfo.clone();
+        self.host = uri.host.clone();
+        self.port = uri.port;
+    }
+
+    /// Parses an authority into its userinfo, host and port.
+    fn parse_authority(authority: &str) -> Result<(Option<String>, String, Option<u16>), ()> {
+        let userinfo_delimiter = '@';
+        let port_delimiter = ':';
+        let (userinfo, host_and_port) = match authority.rsplit_once(userinfo_delimiter) {
+            None => (None, authority),
+            Some((userinfo, host_and_port)) => (Some(userinfo.to_owned()), host_and_port),
+        };
+        // IPv6 addresses are enclosed in brackets, as they contain the port delimiter
+        let (host, port) = match host_and_port.strip_prefix('[') {
+            Some(ip_literal) => {
+                let (host, after_host) = ip_literal.split_once(']').ok_or(())?;
+                if !host.bytes().all(|byte| byte.is_ascii_hexdigit() || matches!(byte, b':' | b'.')) {
+                    return Err(())
+                }
+                match after_host {
+                    "" => (host, None),
+                    after_host => (host, Some(after_host.strip_prefix(port_delimiter).ok_or(())?)),
+                }
+            },
+            None => match host_and_port.rsplit_once(port_delimiter) {
+                None => (host_and_port, None),
+                Some((host, port)) => (host, Some(port)),
+            },
+        };
+        if host.contains(['[', ']']) {
+            return Err(())
+        }
+        // An empty port is allowed, and means the same as no port
+        let port = match port {
+            None | Some("") => None,
+            Some(port) if port.bytes().all(|byte| byte.is_ascii_digit()) => Some(port.parse::<u16>().map_err(|_| ())?),
+            Some(_) => return Err(()),
+        };
+        Ok((userinfo, host.to_owned(), port))
+    }
+
+    /// Returns `true` for the characters that can appear in a URI, including the `%` of an escape.
+    fn is_uri_character(byte: u8) -> bool {
+        percent::is_unreserved(byte)
+            || matches!(byte, b':' | b'/' | b'?' | b'#' | b'[' | b']' | b'@')
+            || matches!(byte, b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=')
+            || byte == b'%'
+    }
+}
+
+impl FromStr for HttpUri {
+    type Err = ();
+
+    /// Parses a URI or relative reference.
+    ///
+    /// # Bad Data
+    /// Returns an error if it contains characters that aren't allowed in a URI, an invalid escape,
+    /// an invalid scheme or an invalid port.
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        if !uri.bytes().all(Self::is_uri_character) || percent::decode_bytes(uri.as_bytes(), false).is_none() {
+            return Err(())
+        }
+        let scheme_delimiter = ':';
+        let authority_prefix = "//";
+        let query_delimiter = '?';
+        let fragment_delimiter = '#';
+        let mut rest = uri;
+        let mut parsed_uri = HttpUri::new();
+
+        let (before_fragment, fragment) = match rest.split_once(fragment_delimiter) {
+            None => (rest, None),
+            Some((before_fragment, fragment)) => (before_fragment, Some(fragment.to_owned())),
+        };
+        parsed_uri.fragment = fragment;
+        rest = before_fragment;
+
+        let (before_query, query) = match rest.split_once(query_delimiter) {
+            None => (rest, None),
+            Some((before_query, query)) => (before_query, Some(query.to_owned())),
+        };
+        parsed_uri.query = query;
+        rest = before_query;
+
+        // A colon before the first `/` ends the scheme, otherwise it's part of a relative path
+        let scheme_end_index = rest.find(['/', scheme_delimiter]).filter(|index| rest[*index..].starts_with(scheme_delimiter));
+        if let Some(index) = scheme_end_index {
+            let scheme = &rest[..index];
+            let valid_scheme = scheme.chars().next().is_some_and(|first| first.is_ascii_alphabetic())
+                && scheme.bytes().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'-' | b'.'));
+            if !valid_scheme {
+                return Err(())
+            }
+            parsed_uri.scheme = Some(scheme.to_owned());
+            rest = &rest[(index + 1)..];
+        }
+
+        if let Some(after_prefix) = rest.strip_prefix(authority_prefix) {
+            let authority_end_index = after_prefix.find('/').unwrap_or(after_prefix.len());
+            let (userinfo, host, port) = Self::parse_authority(&after_prefix[..authority_end_index])?;
+            parsed_uri.userinfo = userinfo;
+            parsed_uri.host = Some(host);
+            parsed_uri.port = port;
+            rest = &after_prefix[authority_end_index..];
+        }
+
+        if rest.contains(['[', ']']) {
+            return Err(())
+        }
+        parsed_uri.path = rest.to_owned();
+        Ok(parsed_uri)
+    }
+}
+
+impl fmt::Display for HttpUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{scheme}:")?;
+        }
+        if let Some(authority) = self.authority() {
+            write!(f, "//{authority}")?;
+        }
+        // Without an authority, a path starting with `//` would be read as one
+        if self.host.is_none() && self.path.starts_with("//") {
+            write!(f, "/.")?;
+        }
+        write!(f, "{}", self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{query}")?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{fragment}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(uri: &str) -> HttpUri {
+        uri.parse().expect("`uri` should be valid")
+    }
+
+    fn resolve(reference: &str) -> String {
+        uri("http://a/b/c/d;p?q").resolve(&uri(reference)).to_string()
+    }
+
+    /// The examples in RFC 3986 section 5.4.1.
+    #[test]
+    fn resolves_normal_examples() {
+        let examples = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+        ];
+        for (reference, expected) in examples {
+            assert_eq!(resolve(reference), expected, "{reference}");
+        }
+    }
+
+    /// The examples in RFC 3986 section 5.4.2, using the strict parser behaviour for `http:g`.
+    #[test]
+    fn resolves_abnormal_examples() {
+        let examples = [
+            ("../../../g", "http://a/g"),
+            ("../../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            ("g?y/./x", "http://a/b/c/g?y/./x"),
+            ("g?y/../x", "http://a/b/c/g?y/../x"),
+            ("g#s/./x", "http://a/b/c/g#s/./x"),
+            ("g#s/../x", "http://a/b/c/g#s/../x"),
+            ("http:g", "http:g"),
+        ];
+        for (reference, expected) in examples {
+            assert_eq!(resolve(reference), expected, "{reference}");
+        }
+    }
+
+    #[test]
+    fn resolves_against_base_without_path() {
+        assert_eq!(uri("http://a").resolve(&uri("g")).to_string(), "http://a/g");
+        assert_eq!(uri("http://a?q").resolve(&uri("")).to_string(), "http://a?q");
+    }
+
+    #[test]
+    fn removes_dot_segments() {
+        assert_eq!(HttpUri::remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(HttpUri::remove_dot_segments("mid/content=5/../6"), "mid/6");
+        assert_eq!(HttpUri::remove_dot_segments("/../../etc/passwd"), "/etc/passwd");
+        assert_eq!(HttpUri::remove_dot_segments("/a/.."), "/");
+    }
+
+    /// The equivalent URIs in RFC 3986 section 6.2.
+    #[test]
+    fn normalizes() {
+        assert_eq!(uri("HTTP://www.Example.com:80/").normalize().to_string(), "http://www.example.com/");
+        assert_eq!(uri("http://example.com").normalize().to_string(), "http://example.com/");
+        assert_eq!(uri("http://example.com:/~smith/").normalize().to_string(), "http://example.com/~smith/");
+        assert_eq!(uri("http://example.com/%7Esmith/").normalize().to_string(), "http://example.com/~smith/");
+        assert_eq!(uri("http://example.com/%7esmith/").normalize().to_string(), "http://example.com/~smith/");
+        assert_eq!(uri("example://a/b/c/%7Bfoo%7D").normalize(), uri("eXAMPLE://a/./b/../b/%63/%7bfoo%7d").normalize());
+        assert_eq!(uri("https://example.com:8443/a/../b").normalize().to_string(), "https://example.com:8443/b");
+        assert_eq!(uri("a/../b").normalize().to_string(), "a/../b");
+    }
+
+    #[test]
+    fn parses_components() {
+        let parsed = uri("https://user:pass@[::1]:8443/a%20b?x=1#top");
+        assert_eq!(parsed.scheme.as_deref(), Some("https"));
+        assert_eq!(parsed.userinfo.as_deref(), Some("user:pass"));
+        assert_eq!(parsed.host.as_deref(), Some("::1"));
+        assert_eq!(parsed.port, Some(8443));
+        assert_eq!(parsed.path, "/a%20b");
+        assert_eq!(parsed.query.as_deref(), Some("x=1"));
+        assert_eq!(parsed.fragment.as_deref(), Some("top"));
+        assert_eq!(parsed.to_string(), "https://user:pass@[::1]:8443/a%20b?x=1#top");
+    }
+
+    #[test]
+    fn rejects_invalid_uris() {
+        for invalid in ["http://a b/", "http://a/%zz", "1http://a/", "http://a:99999/", "http://a:x/", "http://[::1/", "/a[b]", "http://a/<b>"] {
+            assert!(invalid.parse::<HttpUri>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn keeps_path_starting_with_two_slashes_as_path() {
+        let parsed = HttpUri::from_path_and_query("//a/b?c").expect("path should be valid");
+        assert_eq!(parsed.host, None);
+        assert_eq!(parsed.path, "//a/b");
+        assert_eq!(parsed.to_string(), "/.//a/b?c");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::helper::percent;
+
+use super::{HttpTargetParameters, HttpUri};
+
+/// Builds a [`HttpUri`] from decoded components, percent-encoding each one as it is set.
+///
+/// This is used to generate `Location` fields and links, where paths and queries may contain
+/// characters that aren't allowed in a URI.
+///
+/// # Examples
+///
+/// ```
+/// use webserver::http_parser::HttpUri;
+///
+/// let uri = HttpUri::builder()
+///     .scheme("https")
+///     .host("example.com")
+///     .path("/my files/")
+///     .parameter("q", Some("a&b"))
+///     .build();
+/// assert_eq!("https://example.com/my%20files/?q=a%26b", uri.to_string());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HttpUriBuilder {
+    uri: HttpUri,
+    parameters: Option<HttpTargetParameters>,
+}
+
+impl HttpUriBuilder {
+    pub fn new() -> Self {
+        HttpUriBuilder::default()
+    }
+
+    /// Starts from an existing URI, whose components are kept as they are.
+    pub fn from_uri(uri: &HttpUri) -> Self {
+        HttpUriBuilder {
+            uri: uri.clone(),
+            parameters: None,
+        }
+    }
+
+    pub fn scheme(mut self, scheme: &str) -> Self {
+        self.uri.scheme = Some(scheme.to_ascii_lowercase());
+        self
+    }
+
+    /// Sets the host, which may be an IPv6 address without brackets.
+    pub fn host(mut self, host: &str) -> Self {
+        self.uri.host = Some(match host.contains(':') {
+            true => host.to_owned(),
+            false => percent::encode(host, false, Self::is_host_character),
+        });
+        self
+    }
+
+    pub fn port(mut self, port: Option<u16>) -> Self {
+        self.uri.port = port;
+        self
+    }
+
+    /// Sets the path, encoding each segment but keeping the `/` between them.
+    pub fn path(mut self, path: &str) -> Self {
+        let directory_delimiter = "/";
+        self.uri.path = path.split(directory_delimiter)
+            .map(|segment| percent::encode(segment, false, percent::is_path_segment_character))
+            .collect::<Vec<String>>()
+            .join(directory_delimiter);
+        self
+    }
+
+    /// Appends a segment to the path, encoding any `/` in it.
+    pub fn segment(mut self, segment: &str) -> Self {
+        let directory_delimiter = '/';
+        if !self.uri.path.ends_with(directory_delimiter) {
+            self.uri.path.push(directory_delimiter);
+        }
+        self.uri.path.push_str(&percent::encode(segment, false, percent::is_path_segment_character));
+        self
+    }
+
+    /// Sets the query, replacing any parameters added so far.
+    pub fn query(mut self, parameters: &HttpTargetParameters) -> Self {
+        self.parameters = Some(parameters.clone());
+        self
+    }
+
+    /// Adds a parameter to the query. A value of [`None`] adds a flag.
+    pub fn parameter(mut self, key: &str, value: Option<&str>) -> Self {
+        self.parameters.get_or_insert_with(HttpTargetParameters::new).push(key, value);
+        self
+    }
+
+    pub fn fragment(mut self, fragment: &str) -> Self {
+        self.uri.fragment = Some(percent::encode(fragment, false, percent::is_fragment_character));
+        self
+    }
+
+    pub fn build(self) -> HttpUri {
+        let mut uri = self.uri;
+        if let Some(parameters) = self.parameters {
+            uri.query = Some(parameters.to_query_string());
+        }
+        // A path must start with `/` if there is an authority
+        if uri.host.is_some() && !uri.path.is_empty() && !uri.path.starts_with('/') {
+            uri.path.insert(0, '/');
+        }
+        uri
+    }
+
+    fn is_host_character(byte: u8) -> bool {
+        percent::is_unreserved(byte) || matches!(byte, b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=')
+    }
+}
@@ -1,4 +1,5 @@
 use core::fmt;
+use std::str::FromStr;
 
 #[derive(Clone, Debug)]
 pub enum HttpVersion {
@@ -6,10 +7,24 @@ pub enum HttpVersion {
 }
 
 impl HttpVersion {
-    pub fn from_str(version: &str) -> Option<Self> {
+    /// Returns `true` if `version` has the syntax of a HTTP version (`HTTP/x.y`), even if it isn't supported.
+    pub fn is_well_formed(version: &str) -> bool {
+        let version = version.as_bytes();
+        version.len() == 8
+            && version.starts_with(b"HTTP/")
+            && version[5].is_ascii_digit()
+            && version[6] == b'.'
+            && version[7].is_ascii_digit()
+    }
+}
+
+impl FromStr for HttpVersion {
+    type Err = ();
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
         match version.trim() {
-            "HTTP/1.1" => Some(Self::Http1Dot1),
-            _ => None
+            "HTTP/1.1" => Ok(Self::Http1Dot1),
+            _ => Err(())
         }
     }
 }
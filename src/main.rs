@@ -1,22 +1,41 @@
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr};
 
-use webserver::{http_parser::HttpProtocol, network, server};
+use webserver::{http_parser::{HttpParserMode, HttpProtocol, HttpStatusCode}, network, server};
 
 fn main() {
     let http_protocol = HttpProtocol::Http;
     let port = http_protocol.port();
     let local_ipv4_address = network::get_local_ipv4_address();
     let config = server::Config {
-        domain_names: vec!("example.com".to_owned(), "www.example.com".to_owned()),
         top_directory: "content".to_owned(),
-        root_directory: "root".to_owned(),
-        subdomain_directory: "subdomains".to_owned(),
+        virtual_hosts: vec!(server::VirtualHost {
+            name: "example".to_owned(),
+            host_patterns: vec!("example.com".to_owned()),
+            document_root: "root".to_owned(),
+            subdomain_mapping: server::SubdomainMapping::ReversedNesting { directory: "subdomains".to_owned() },
+            subdomain_aliases: HashMap::from([("www".to_owned(), String::new())]),
+            index_filenames: vec!("index.html".to_owned()),
+            error_pages: HashMap::from([(HttpStatusCode::NotFound404, "404.html".to_owned())]),
+            canonical_redirect: server::CanonicalRedirect {
+                host: server::CanonicalHost::Apex,
+                excluded_paths: vec!("/.well-known/acme-challenge/".to_owned()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        unknown_host_policy: server::UnknownHostPolicy::MisdirectedRequest,
         socket: SocketAddr::new(local_ipv4_address, port),
+        protocol: http_protocol,
+        request_trust_forwarded_protocol: false,
+        response_server: Some("webserver".to_owned()),
         request_initial_buffer_size_kilobytes: 16,
         request_maximum_buffer_size_kilobytes: 1024,
-        request_default_filename: "index.html".to_owned(),
-        not_found_filename: "404.html".to_owned(),
         request_timeout_seconds: 5,
+        request_parser_mode: HttpParserMode::Strict,
+        request_maximum_request_line_size_bytes: 8192,
+        request_maximum_field_line_size_bytes: 8192,
+        request_maximum_field_count: 100,
+        request_maximum_header_size_kilobytes: 64,
     };
     server::start_server(&config);
 }
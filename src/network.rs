@@ -1,8 +1,8 @@
 use std::{
-    error::Error, io::{self, BufReader, Read, Write}, net::{IpAddr, TcpListener, TcpStream}, time::Instant
+    error::Error, io::{self, BufReader, Read, Write}, net::{IpAddr, TcpListener, TcpStream}, time::{Duration, Instant}
 };
 
-use crate::{helper::enums::Processing, http_parser::{HttpRequest, HttpStatusCode, PartialHttpRequest}, server};
+use crate::{helper::enums::Processing, http_parser::{HttpParseError, HttpRequest, PartialHttpRequest}, server};
 
 pub fn start_listener(config: &server::Config) {
     let tcp_listener = TcpListener::bind(config.socket).expect("Should be able to bind to local IP address");
@@ -16,8 +16,7 @@ pub fn start_listener(config: &server::Config) {
 
 pub fn get_local_ipv4_address() -> IpAddr {
     let get_ipv4_stream = TcpStream::connect("ipv4.icanhazip.com:443").expect("Should be able to connect to icanhazip.com");
-    let local_ipv4_address = get_ipv4_stream.local_addr().expect("Should be able to read local socket address").ip();
-    return local_ipv4_address;
+    get_ipv4_stream.local_addr().expect("Should be able to read local socket address").ip()
 }
 
 fn accept_connection(config: &server::Config, mut stream: TcpStream) {
@@ -25,34 +24,58 @@ fn accept_connection(config: &server::Config, mut stream: TcpStream) {
     let stream_ip_address = stream.peer_addr().expect("`Stream` should contain the socket address of the remote peer");
     println!("Connection request from: {stream_ip_address}.");
 
+    if config.request_timeout_seconds > 0 {
+        // Stops a read from blocking forever so that the request can time out
+        let timeout = Duration::from_secs(config.request_timeout_seconds as u64);
+        if stream.set_read_timeout(Some(timeout)).is_err() {
+            return
+        }
+    }
+
     let mut buf_reader = BufReader::new(&mut stream);
     const BYTES_IN_KILOBYTE: usize = 1024;
     let buffer_size_bytes = BYTES_IN_KILOBYTE * config.request_initial_buffer_size_kilobytes;
     let buffer_maximum_size_bytes = BYTES_IN_KILOBYTE * config.request_maximum_buffer_size_kilobytes;
     let mut buf = vec!(0; buffer_size_bytes);
     let mut buf_received_bytes = 0;
-    let mut http_request = PartialHttpRequest::new();
+    let http_request = PartialHttpRequest::new();
+    let mut header_complete = false;
+    let parser_config = config.parser_config();
 
     let mut http_request = loop {
-        if (config.request_timeout_seconds > 0) && (now.elapsed().as_secs() > 5) {
-            server::handle_request(config, &mut stream, &mut Err((io::ErrorKind::Other.into(), HttpStatusCode::RequestTimeout408)));
+        if (config.request_timeout_seconds > 0) && (now.elapsed().as_secs() >= config.request_timeout_seconds as u64) {
+            server::handle_request(config, &mut stream, &mut Err(HttpParseError::Timeout { seconds: config.request_timeout_seconds }));
             println!("Request from {} timed out", stream_ip_address);
             return;
         }
-        let bytes_read = buf_reader.read(&mut buf);
-        if let Err(error) = bytes_read {
-            match error.kind() {
-                io::ErrorKind::Interrupted => continue,
-                _ => return,
+        if buf_received_bytes == buf.len() {
+            if buf.len() >= buffer_maximum_size_bytes {
+                let error = match header_complete {
+                    true => HttpParseError::BodyTooLarge { limit: buffer_maximum_size_bytes },
+                    false => HttpParseError::HeaderTooLarge { limit: buffer_maximum_size_bytes },
+                };
+                server::handle_request(config, &mut stream, &mut Err(error));
+                return
             }
+            buf.resize((buf.len() * 2).clamp(1, buffer_maximum_size_bytes), 0);
         }
-        buf_received_bytes += bytes_read.expect("`bytes_read` should be `Ok` here");
-        if buf_received_bytes > buffer_maximum_size_bytes {
-            server::handle_request(config, &mut stream, &mut Err((io::ErrorKind::Other.into(), HttpStatusCode::ContentTooLarge413)));
-            return
-        }
-        match HttpRequest::try_parse(&mut http_request, &buf) {
-            Processing::InProgress(_) => continue,
+        let bytes_read = match buf_reader.read(&mut buf[buf_received_bytes..]) {
+            Err(error) => match error.kind() {
+                io::ErrorKind::Interrupted => continue,
+                // The read timed out, which is checked at the start of the loop
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => continue,
+                _ => return,
+            },
+            // The client closed the connection before sending a full request
+            Ok(0) => return,
+            Ok(bytes_read) => bytes_read,
+        };
+        buf_received_bytes += bytes_read;
+        match HttpRequest::try_parse(&parser_config, &http_request, &buf[..buf_received_bytes]) {
+            Processing::InProgress(partial_request) => {
+                header_complete = partial_request.header_complete;
+                continue
+            },
             Processing::Finished(result) => break result
         }
     };
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::http_parser::HttpRequest;
+
+/// Returns `true` if the request has `Basic` credentials for one of the `users`, which are
+/// passwords by user ID.
+pub fn is_authenticated(users: &HashMap<String, String>, http_request: &HttpRequest) -> bool {
+    let credentials = http_request.header.as_ref()
+        .and_then(|header| header.authorization())
+        .and_then(|authorization| authorization.basic());
+    match credentials {
+        None => false,
+        Some((user_id, password)) => users.get(&user_id).is_some_and(|expected_password| constant_time_equals(expected_password.as_bytes(), password.as_bytes())),
+    }
+}
+
+/// Gets the `WWW-Authenticate` challenge that asks for `Basic` credentials for `realm`.
+pub fn challenge(realm: &str) -> String {
+    format!("Basic realm={}, charset=\"UTF-8\"", escape_quoted(realm))
+}
+
+/// Quotes `slice` as a quoted-string.
+fn escape_quoted(slice: &str) -> String {
+    format!("\"{}\"", slice.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Compares two byte strings in a time that doesn't depend on where they differ, so a password
+/// can't be guessed from how long the comparison takes.
+fn constant_time_equals(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false
+    }
+    a.iter().zip(b).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
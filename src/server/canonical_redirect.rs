@@ -0,0 +1,86 @@
+use crate::http_parser::{HttpHost, HttpMethod, HttpProtocol, HttpRequest, HttpStatusCode, HttpUri, HttpUriBuilder};
+
+use super::VirtualHostMatch;
+
+/// Which of a domain and its `www` subdomain a [`super::VirtualHost`] is served on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CanonicalHost {
+    /// Both are served, without redirecting.
+    #[default]
+    Any,
+    /// `www.example.com` is redirected to `example.com`.
+    Apex,
+    /// `example.com` is redirected to `www.example.com`.
+    Www,
+}
+
+/// The redirects that send a client to a [`super::VirtualHost`]'s canonical host and scheme.
+///
+/// The path and query are kept. `GET` and `HEAD` requests get [`HttpStatusCode::MovedPermanently301`],
+/// and other methods get [`HttpStatusCode::PermanentRedirect308`] so the method and body are kept.
+#[derive(Clone, Debug, Default)]
+pub struct CanonicalRedirect {
+    pub host: CanonicalHost,
+    /// Redirects HTTP requests to HTTPS.
+    pub https: bool,
+    /// The port HTTPS is served on, if it isn't `443`.
+    pub https_port: Option<u16>,
+    /// The `max-age` of the `Strict-Transport-Security` field sent with HTTPS responses. `0` means it isn't sent.
+    pub hsts_max_age_seconds: usize,
+    pub hsts_include_subdomains: bool,
+    pub hsts_preload: bool,
+    /// Path prefixes that are never redirected, such as `/.well-known/acme-challenge/`.
+    pub excluded_paths: Vec<String>,
+}
+
+impl CanonicalRedirect {
+    /// Gets where to redirect `http_request` to, or [`None`] if it is already canonical.
+    ///
+    /// A HTTP request is redirected to HTTPS on the same host first, before the host is changed,
+    /// so that HSTS is set for the host the client asked for.
+    pub fn location(&self, site: &VirtualHostMatch, http_request: &HttpRequest, protocol: HttpProtocol) -> Option<HttpUri> {
+        let target = http_request.target.as_ref()?;
+        let path = target.path.as_deref()?;
+        if self.excluded_paths.iter().any(|excluded_path| path.starts_with(excluded_path.as_str())) {
+            return None
+        }
+        let host = http_request.host()?.parse::<HttpHost>().ok()?;
+        let uri = HttpUriBuilder::from_uri(&target.to_uri());
+
+        if self.https && protocol == HttpProtocol::Http {
+            let https_port = self.https_port.filter(|port| *port != HttpProtocol::Https.port());
+            return Some(uri.scheme(HttpProtocol::Https.scheme()).host(&host.name).port(https_port).build())
+        }
+
+        let www_prefix = "www.";
+        let canonical_name = match self.host {
+            CanonicalHost::Apex if site.subdomain == "www" => host.name.strip_prefix(www_prefix)?.to_owned(),
+            CanonicalHost::Www if site.subdomain.is_empty() && !host.is_ip_address() => format!("{www_prefix}{}", host.name),
+            _ => return None,
+        };
+        Some(uri.scheme(protocol.scheme()).host(&canonical_name).port(host.port).build())
+    }
+
+    /// Gets the status code to redirect a request with `method` with.
+    pub fn status_code(method: &HttpMethod) -> HttpStatusCode {
+        match method {
+            HttpMethod::Get | HttpMethod::Head => HttpStatusCode::MovedPermanently301,
+            _ => HttpStatusCode::PermanentRedirect308,
+        }
+    }
+
+    /// Gets the value of the `Strict-Transport-Security` field, or [`None`] if it isn't sent.
+    pub fn strict_transport_security(&self) -> Option<String> {
+        if self.hsts_max_age_seconds == 0 {
+            return None
+        }
+        let mut value = format!("max-age={}", self.hsts_max_age_seconds);
+        if self.hsts_include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if self.hsts_preload {
+            value.push_str("; preload");
+        }
+        Some(value)
+    }
+}
@@ -0,0 +1,366 @@
+use std::{collections::HashMap, error::Error, io::{self, Read, Write}, net::IpAddr, path::{Path, PathBuf}, process::{Child, Command, Stdio}, sync::mpsc, thread, time::{Duration, Instant}};
+
+use crate::{helper::file, http_parser::{HttpFieldName, HttpHeader, HttpHost, HttpRequest, HttpResponse, HttpStatusCode}};
+
+use super::VirtualHostMatch;
+
+/// The fields that aren't passed to scripts as `HTTP_*` variables. Credentials are kept from
+/// scripts, and `Proxy` would set `HTTP_PROXY`, which many programs use as their proxy.
+const CGI_EXCLUDED_FIELDS: [&str; 5] = ["authorization", "proxy-authorization", "proxy", "content-type", "content-length"];
+
+/// The fields of a script's output that aren't passed on, as the server decides how the response is framed.
+const CGI_DROPPED_FIELDS: [HttpFieldName; 3] = [HttpFieldName::ContentLength, HttpFieldName::TransferEncoding, HttpFieldName::Connection];
+
+/// Runs files in the document root of a [`super::VirtualHost`] as CGI/1.1 scripts (RFC 3875),
+/// for `GET`, `HEAD` and `POST` requests.
+///
+/// A file is a script if its path is under one of the `locations`, or it has one of the
+/// `extensions`. Any path after the script, such as `/info` in `/cgi-bin/script.pl/info`, is
+/// given to it as `PATH_INFO`. The request body is written to the script's standard input, and
+/// the header the script writes to its standard output becomes the response's header. Its
+/// standard error is written to the server's.
+///
+/// A `Location` field with a local path is sent to the client as a redirect, rather than being
+/// followed by the server.
+#[derive(Clone, Debug)]
+pub struct Cgi {
+    /// The path prefixes whose files are run as scripts, such as `/cgi-bin/`.
+    pub locations: Vec<String>,
+    /// The extensions of files that are run as scripts anywhere, such as `pl`, without the `.`.
+    pub extensions: Vec<String>,
+    /// The program that runs scripts with each extension, such as `/usr/bin/perl` for `pl`.
+    /// Other scripts are run directly, so they must be executable.
+    pub interpreters: HashMap<String, String>,
+    /// How long a script can run before it is killed and the request gets
+    /// [`HttpStatusCode::GatewayTimeout504`]. `0` means there is no limit.
+    pub timeout_seconds: u64,
+    /// The largest output a script can write, in kilobytes. `0` means there is no limit.
+    pub maximum_output_size_kilobytes: usize,
+}
+
+impl Default for Cgi {
+    fn default() -> Self {
+        Cgi {
+            locations: Vec::new(),
+            extensions: Vec::new(),
+            interpreters: HashMap::new(),
+            timeout_seconds: 30,
+            maximum_output_size_kilobytes: 0,
+        }
+    }
+}
+
+/// The script a request is for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CgiScript {
+    /// The full path of the script.
+    pub path: PathBuf,
+    /// The decoded path of the script in the request, which is its `SCRIPT_NAME`.
+    pub script_name: String,
+    /// The decoded path after the script, which is its `PATH_INFO`.
+    pub path_info: String,
+}
+
+impl Cgi {
+    /// Finds the script for the decoded `path`, which is the first file along it that is a script.
+    pub fn script_for(&self, site: &VirtualHostMatch, path: &str) -> Option<CgiScript> {
+        let directory_delimiter = '/';
+        let root = Path::new(&site.document_root);
+        let mut script_name = String::new();
+        let mut segments = path.split(directory_delimiter).filter(|segment| !segment.is_empty());
+        for segment in segments.by_ref() {
+            script_name.push(directory_delimiter);
+            script_name.push_str(segment);
+            let script_path = root.join(script_name.trim_start_matches(directory_delimiter));
+            if script_path.is_file() {
+                let is_script = self.locations.iter().any(|location| is_under(&script_name, location))
+                    || Path::new(segment).extension().is_some_and(|extension| self.extensions.iter().any(|script_extension| extension.eq_ignore_ascii_case(script_extension.as_str())));
+                if !is_script || !file::is_confined(root, &script_path) {
+                    return None
+                }
+                let mut path_info: String = segments.map(|segment| format!("{directory_delimiter}{segment}")).collect();
+                if path.ends_with(directory_delimiter) && !path_info.is_empty() {
+                    path_info.push(directory_delimiter);
+                }
+                return Some(CgiScript { path: script_path, script_name, path_info })
+            }
+            if !script_path.is_dir() {
+                return None
+            }
+        }
+        None
+    }
+
+    /// Runs `script` for `http_request` and responds with its output.
+    ///
+    /// `client_address` is the script's `REMOTE_ADDR`, and `server_port` is its `SERVER_PORT`
+    /// if the request's host has no port.
+    pub fn respond(&self, site: &VirtualHostMatch, http_request: &HttpRequest, script: &CgiScript, client_address: Option<IpAddr>, server_port: u16) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let http_version = http_request.version.as_ref().expect("`http_request.version` should be `Some`");
+        let failure = |status_code: HttpStatusCode, error: Box<dyn Error>| (HttpResponse::new(http_version, &status_code, &None, &None), error);
+        let environment = environment(site, http_request, script, client_address, server_port);
+        let output = self.run(script, environment, http_request.body.unwrap_or_default()).map_err(|error| {
+            let status_code = match error.kind() {
+                io::ErrorKind::TimedOut => HttpStatusCode::GatewayTimeout504,
+                io::ErrorKind::InvalidData => HttpStatusCode::BadGateway502,
+                _ => HttpStatusCode::from_io_error(&error),
+            };
+            failure(status_code, Box::new(error))
+        })?;
+        let (status_code, http_header, body) = parse_output(&output).ok_or_else(|| {
+            failure(HttpStatusCode::BadGateway502, Box::new(io::Error::other("CGI script's output has no valid header")))
+        })?;
+        Ok(HttpResponse::new(http_version, &status_code, &Some(&http_header), &Some(body)))
+    }
+
+    /// Runs the script with `environment`, writing `body` to it, and returns its output.
+    fn run(&self, script: &CgiScript, environment: Vec<(String, String)>, body: &[u8]) -> io::Result<Vec<u8>> {
+        // The script is run in its own directory, so its path can't be relative
+        let script_path = script.path.canonicalize()?;
+        let extension = script_path.extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+        let mut command = match self.interpreters.get(&extension) {
+            None => Command::new(&script_path),
+            Some(interpreter) => {
+                let mut command = Command::new(interpreter);
+                command.arg(&script_path);
+                command
+            },
+        };
+        if let Some(directory) = script_path.parent() {
+            command.current_dir(directory);
+        }
+        // The script gets its own process group, so anything it starts can be killed with it
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command.env_clear()
+            .envs(environment)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let deadline = (self.timeout_seconds > 0).then(|| Instant::now() + Duration::from_secs(self.timeout_seconds));
+
+        // The body is written and the output read on other threads, so a script that writes
+        // before it has read its input can't leave both sides waiting
+        let mut stdin = child.stdin.take().expect("`child.stdin` should be `Some`");
+        let body = body.to_vec();
+        thread::spawn(move || _ = stdin.write_all(&body));
+        let stdout = child.stdout.take().expect("`child.stdout` should be `Some`");
+        let maximum_output_size = match self.maximum_output_size_kilobytes {
+            0 => u64::MAX,
+            kilobytes => 1024 * kilobytes as u64,
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let result = stdout.take(maximum_output_size.saturating_add(1)).read_to_end(&mut output).map(|_| output);
+            _ = sender.send(result);
+        });
+
+        let output = match deadline {
+            None => receiver.recv().map_err(|_| io::Error::other("CGI script's output couldn't be read")),
+            Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "CGI script timed out")),
+        };
+        let output = match output {
+            Err(error) => {
+                stop(&mut child);
+                return Err(error)
+            },
+            Ok(output) => output,
+        };
+        let output = match output {
+            Ok(output) if output.len() as u64 > maximum_output_size => {
+                stop(&mut child);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "CGI script's output is too large"))
+            },
+            result => result?,
+        };
+        wait_until(&mut child, deadline);
+        Ok(output)
+    }
+}
+
+/// Returns `true` if `path` is `location` or a path under it, where `location` is a whole
+/// number of segments, so `/cgi` doesn't contain `/cgi-bin`.
+fn is_under(path: &str, location: &str) -> bool {
+    let directory_delimiter = '/';
+    let location = location.trim_end_matches(directory_delimiter);
+    path.strip_prefix(location).is_some_and(|rest| rest.is_empty() || rest.starts_with(directory_delimiter))
+}
+
+/// Builds the environment a script is run with, as described by RFC 3875.
+fn environment(site: &VirtualHostMatch, http_request: &HttpRequest, script: &CgiScript, client_address: Option<IpAddr>, server_port: u16) -> Vec<(String, String)> {
+    let target = http_request.target.as_ref().expect("`http_request.target` should be `Some`");
+    let method = http_request.method.as_ref().expect("`http_request.method` should be `Some`");
+    let version = http_request.version.as_ref().expect("`http_request.version` should be `Some`");
+    let host = http_request.host().and_then(|host| host.parse::<HttpHost>().ok());
+    let remote_address = client_address.map(|client_address| client_address.to_string()).unwrap_or_default();
+
+    let mut environment = vec!(
+        ("GATEWAY_INTERFACE".to_owned(), "CGI/1.1".to_owned()),
+        ("SERVER_SOFTWARE".to_owned(), concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_owned()),
+        ("SERVER_PROTOCOL".to_owned(), version.to_string()),
+        ("SERVER_NAME".to_owned(), host.as_ref().map(|host| host.name.clone()).unwrap_or_default()),
+        ("SERVER_PORT".to_owned(), host.and_then(|host| host.port).unwrap_or(server_port).to_string()),
+        ("REQUEST_METHOD".to_owned(), method.to_string()),
+        ("REQUEST_URI".to_owned(), target.to_string()),
+        ("QUERY_STRING".to_owned(), target.parameters.as_ref().map(|parameters| parameters.to_query_string()).unwrap_or_default()),
+        ("SCRIPT_NAME".to_owned(), script.script_name.clone()),
+        ("SCRIPT_FILENAME".to_owned(), script.path.to_string_lossy().into_owned()),
+        ("DOCUMENT_ROOT".to_owned(), site.document_root.clone()),
+        ("REMOTE_ADDR".to_owned(), remote_address.clone()),
+        // The client's host name isn't looked up, so its address is used instead
+        ("REMOTE_HOST".to_owned(), remote_address),
+    );
+    if !script.path_info.is_empty() {
+        let path_translated = Path::new(&site.document_root).join(script.path_info.trim_start_matches('/'));
+        environment.push(("PATH_INFO".to_owned(), script.path_info.clone()));
+        environment.push(("PATH_TRANSLATED".to_owned(), path_translated.to_string_lossy().into_owned()));
+    }
+    if let Some(body) = http_request.body.filter(|body| !body.is_empty()) {
+        environment.push(("CONTENT_LENGTH".to_owned(), body.len().to_string()));
+    }
+    if let Some(header) = &http_request.header {
+        if let Some(content_type) = header.get_value(&HttpFieldName::ContentType) {
+            environment.push(("CONTENT_TYPE".to_owned(), content_type.clone()));
+        }
+        for (field_name, value) in &header.0 {
+            let field_name = field_name.to_string();
+            let is_valid_name = field_name.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-');
+            if !is_valid_name || CGI_EXCLUDED_FIELDS.contains(&field_name.to_ascii_lowercase().as_str()) {
+                continue
+            }
+            environment.push((format!("HTTP_{}", field_name.to_ascii_uppercase().replace('-', "_")), value.clone()));
+        }
+    }
+    // Interpreters run with `/usr/bin/env` need to find the program
+    if let Ok(path) = std::env::var("PATH") {
+        environment.push(("PATH".to_owned(), path));
+    }
+    environment
+}
+
+/// Splits a script's output into the status code, header and body of the response.
+///
+/// The status code is taken from the `Status` field, and is [`HttpStatusCode::Found302`] if there
+/// is only a `Location` field, or [`HttpStatusCode::OK200`] otherwise. Returns [`None`] if the
+/// header is missing or malformed.
+fn parse_output(output: &[u8]) -> Option<(HttpStatusCode, HttpHeader, &[u8])> {
+    // Scripts often end lines with only LF
+    let header_end = [&b"\r\n\r\n"[..], &b"\n\n"[..]].iter()
+        .filter_map(|delimiter| output.windows(delimiter.len()).position(|window| window == *delimiter).map(|index| (index, index + delimiter.len())))
+        .min_by_key(|(index, _)| *index);
+    let (header_end, body_start) = header_end?;
+    let header_text = std::str::from_utf8(&output[..header_end]).ok()?;
+
+    let status_field = HttpFieldName::Other("Status".to_owned());
+    let mut status_code = None;
+    let mut http_header = HttpHeader::new();
+    for line in header_text.lines() {
+        let (field_name, value) = line.split_once(':')?;
+        let field_name = field_name.trim().parse::<HttpFieldName>().ok()?;
+        let value = value.trim();
+        if field_name == status_field {
+            let code = value.split(' ').next()?.parse::<u16>().ok()?;
+            status_code = Some(HttpStatusCode::from_u16(code)?);
+            continue
+        }
+        if CGI_DROPPED_FIELDS.contains(&field_name) {
+            continue
+        }
+        http_header.append(field_name, value);
+    }
+    if status_code.is_none() && http_header.0.is_empty() {
+        return None
+    }
+    let status_code = status_code.unwrap_or(match http_header.contains(&HttpFieldName::Location) {
+        false => HttpStatusCode::OK200,
+        true => HttpStatusCode::Found302,
+    });
+    Some((status_code, http_header, &output[body_start..]))
+}
+
+/// Kills a script and the processes it started, and waits for it, so it doesn't remain as a zombie process.
+fn stop(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        extern "C" {
+            fn kill(pid: i32, signal: i32) -> i32;
+        }
+        let kill_signal = 9;
+        // The script leads its own process group, so the negated ID signals everything in it, and
+        // the ID can't be reused by another group until the script has been waited for below
+        if let Ok(process_group) = i32::try_from(child.id()) {
+            // SAFETY: `kill` only sends a signal, and doesn't access any memory
+            unsafe { kill(-process_group, kill_signal) };
+        }
+    }
+    _ = child.kill();
+    _ = child.wait();
+}
+
+/// Waits for a script that has closed its output to exit, killing it if it runs past `deadline`.
+fn wait_until(child: &mut Child, deadline: Option<Instant>) {
+    let Some(deadline) = deadline else {
+        _ = child.wait();
+        return
+    };
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            _ => return,
+        }
+    }
+    stop(child);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_parser::HttpVersion;
+
+    #[test]
+    fn parses_output() {
+        let output = b"Content-Type: text/plain\nStatus: 404 Not Found\nContent-Length: 2\n\nno";
+        let (status_code, http_header, body) = parse_output(output).expect("output should be valid");
+        assert_eq!(status_code, HttpStatusCode::NotFound404);
+        assert_eq!(http_header.get_value(&HttpFieldName::ContentType).map(String::as_str), Some("text/plain"));
+        assert!(!http_header.contains(&HttpFieldName::ContentLength));
+        assert_eq!(body, b"no");
+    }
+
+    #[test]
+    fn redirects_with_only_location() {
+        let (status_code, _, _) = parse_output(b"Location: /other\r\n\r\n").expect("output should be valid");
+        assert_eq!(status_code, HttpStatusCode::Found302);
+    }
+
+    #[test]
+    fn rejects_output_without_header() {
+        assert!(parse_output(b"hello").is_none());
+        assert!(parse_output(b"not a field\n\nhello").is_none());
+        assert!(parse_output(b"Status: 999\n\n").is_none());
+    }
+
+    #[test]
+    fn keeps_repeated_set_cookie_fields_separate() {
+        let output = b"Set-Cookie: a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT\nSet-Cookie: b=2\nVary: Accept\nVary: Cookie\n\n";
+        let (status_code, http_header, body) = parse_output(output).expect("output should be valid");
+        assert_eq!(http_header.get_value(&HttpFieldName::Vary).map(String::as_str), Some("Accept, Cookie"));
+        let http_response = HttpResponse::new(&HttpVersion::Http1Dot1, &status_code, &Some(&http_header), &Some(body));
+        let response_text = String::from_utf8(http_response.as_bytes()).expect("response should be UTF-8");
+        assert!(response_text.contains("Set-Cookie: a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT\r\n"));
+        assert!(response_text.contains("Set-Cookie: b=2\r\n"));
+    }
+
+    #[test]
+    fn matches_locations_on_segment_boundaries() {
+        assert!(is_under("/cgi-bin/script.sh", "/cgi-bin/"));
+        assert!(is_under("/cgi-bin/script.sh", "/cgi-bin"));
+        assert!(is_under("/cgi-bin", "/cgi-bin/"));
+        assert!(is_under("/script.sh", "/"));
+        assert!(!is_under("/cgi-bin-old/script.sh", "/cgi-bin"));
+        assert!(!is_under("/cgi/script.sh", "/cgi-bin/"));
+    }
+}
@@ -0,0 +1,248 @@
+use std::{cmp::Ordering, fs, io, path::Path, str::FromStr, time::SystemTime};
+
+use crate::{helper::{escape, percent}, http_parser::{HttpDate, HttpTargetParameters}};
+
+/// What a [`DirectoryListing`] is sorted by, given by the `sort` query parameter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DirectoryListingSort {
+    #[default]
+    Name,
+    Size,
+    Modified,
+}
+
+/// One file or directory in a [`DirectoryListing`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub is_directory: bool,
+    /// The size in bytes, which is [`None`] for directories.
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+}
+
+/// The contents of a directory, for when it is requested and has no index file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryListing {
+    /// The decoded path the directory was requested with, ending with `/`.
+    pub path: String,
+    pub entries: Vec<DirectoryEntry>,
+    pub sort: DirectoryListingSort,
+    pub descending: bool,
+}
+
+impl DirectoryListingSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectoryListingSort::Name => "name",
+            DirectoryListingSort::Size => "size",
+            DirectoryListingSort::Modified => "modified",
+        }
+    }
+}
+
+impl FromStr for DirectoryListingSort {
+    type Err = ();
+
+    fn from_str(sort: &str) -> Result<Self, Self::Err> {
+        match sort {
+            "name" => Ok(DirectoryListingSort::Name),
+            "size" => Ok(DirectoryListingSort::Size),
+            "modified" => Ok(DirectoryListingSort::Modified),
+            _ => Err(()),
+        }
+    }
+}
+
+impl DirectoryListing {
+    /// Reads the entries of `directory`, which was requested with the decoded `path`.
+    ///
+    /// Entries whose name starts with `.` are hidden, as are entries whose metadata can't be read.
+    /// A hidden directory, or one inside a hidden directory, gets an [`io::ErrorKind::NotFound`] error.
+    pub fn read(directory: &Path, path: &str) -> io::Result<Self> {
+        let directory_delimiter = '/';
+        let hidden_prefix = '.';
+        if path.split(directory_delimiter).any(|segment| segment.starts_with(hidden_prefix)) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "hidden directories aren't listed"))
+        }
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            let name = match entry.file_name().into_string() {
+                Err(_) => continue,
+                Ok(name) if name.starts_with(hidden_prefix) => continue,
+                Ok(name) => name,
+            };
+            // Follows symbolic links, like serving the entry would
+            let metadata = match fs::metadata(entry.path()) {
+                Err(_) => continue,
+                Ok(metadata) => metadata,
+            };
+            entries.push(DirectoryEntry {
+                name,
+                is_directory: metadata.is_dir(),
+                size: (!metadata.is_dir()).then_some(metadata.len()),
+                modified: metadata.modified().ok(),
+            });
+        }
+        let mut directory_listing = DirectoryListing {
+            path: path.to_owned(),
+            entries,
+            sort: DirectoryListingSort::default(),
+            descending: false,
+        };
+        directory_listing.sort_by(DirectoryListingSort::default(), false);
+        Ok(directory_listing)
+    }
+
+    /// Sorts the entries by the `sort` and `order` query parameters, where `order` is `asc` or `desc`.
+    /// Unrecognised values are ignored.
+    pub fn sort_by_parameters(&mut self, parameters: Option<&HttpTargetParameters>) {
+        let sort = parameters.and_then(|parameters| parameters.get("sort")).and_then(|sort| sort.parse().ok()).unwrap_or_default();
+        let descending = parameters.and_then(|parameters| parameters.get("order")) == Some("desc");
+        self.sort_by(sort, descending);
+    }
+
+    /// Sorts the entries, keeping directories before files.
+    pub fn sort_by(&mut self, sort: DirectoryListingSort, descending: bool) {
+        self.sort = sort;
+        self.descending = descending;
+        self.entries.sort_by(|a, b| {
+            let ordering = match sort {
+                DirectoryListingSort::Name => Ordering::Equal,
+                DirectoryListingSort::Size => a.size.cmp(&b.size),
+                DirectoryListingSort::Modified => a.modified.cmp(&b.modified),
+            }.then_with(|| a.name.cmp(&b.name));
+            let ordering = match descending {
+                false => ordering,
+                true => ordering.reverse(),
+            };
+            b.is_directory.cmp(&a.is_directory).then(ordering)
+        });
+    }
+
+    /// Renders the listing as a HTML page, with links to sort it by each column.
+    pub fn to_html(&self) -> String {
+        let title = escape::html(&format!("Index of {}", self.path));
+        let mut html = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n<table>\n<tr>");
+        for (sort, heading) in [(DirectoryListingSort::Name, "Name"), (DirectoryListingSort::Size, "Size"), (DirectoryListingSort::Modified, "Last modified")] {
+            let order = match self.sort == sort && !self.descending {
+                false => "asc",
+                true => "desc",
+            };
+            html.push_str(&format!("<th><a href=\"?sort={}&amp;order={order}\">{heading}</a></th>", sort.as_str()));
+        }
+        html.push_str("</tr>\n");
+        if self.path != "/" {
+            html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+        }
+        for entry in &self.entries {
+            let mut href = percent::encode(&entry.name, false, percent::is_path_segment_character);
+            let mut name = entry.name.clone();
+            if entry.is_directory {
+                href.push('/');
+                name.push('/');
+            }
+            // A name such as `a:b` would otherwise be read as a scheme
+            if href.contains(':') {
+                href.insert_str(0, "./");
+            }
+            let size = entry.size.map_or("-".to_owned(), |size| size.to_string());
+            let modified = entry.modified.map_or(String::new(), |modified| HttpDate(modified).to_string());
+            html.push_str(&format!("<tr><td><a href=\"{}\">{}</a></td><td>{size}</td><td>{modified}</td></tr>\n", escape::html(&href), escape::html(&name)));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Renders the listing as a JSON object, with each entry's modification time in seconds since the Unix epoch.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self.entries.iter()
+            .map(|entry| {
+                let entry_type = match entry.is_directory {
+                    false => "file",
+                    true => "directory",
+                };
+                let size = entry.size.map_or("null".to_owned(), |size| size.to_string());
+                let modified = entry.modified.map_or("null".to_owned(), |modified| HttpDate(modified).seconds_since_epoch().to_string());
+                format!("{{\"name\":{},\"type\":\"{entry_type}\",\"size\":{size},\"modified\":{modified}}}", escape::json_string(&entry.name))
+            })
+            .collect();
+        format!("{{\"path\":{},\"entries\":[{}]}}", escape::json_string(&self.path), entries.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::file::TestDirectory;
+
+    fn names(directory_listing: &DirectoryListing) -> Vec<&str> {
+        directory_listing.entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    fn file(name: &str) -> DirectoryEntry {
+        DirectoryEntry { name: name.to_owned(), is_directory: false, size: Some(1), modified: None }
+    }
+
+    #[test]
+    fn hides_dotfiles() {
+        let directory = TestDirectory::new();
+        directory.write(".hidden", "hidden");
+        directory.write(".git/config", "hidden");
+        directory.write("visible.txt", "visible");
+
+        let directory_listing = DirectoryListing::read(&directory.0, "/").expect("directory should be listed");
+        assert_eq!(names(&directory_listing), vec!("visible.txt"));
+        let error = DirectoryListing::read(&directory.0.join(".git"), "/.git/").expect_err("hidden directory shouldn't be listed");
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn sorts_directories_first() {
+        let directory = TestDirectory::new();
+        directory.write("b/file", "");
+        directory.write("a/file", "");
+        directory.write("c.txt", "c");
+        directory.write("a.txt", "aaa");
+
+        let mut directory_listing = DirectoryListing::read(&directory.0, "/").expect("directory should be listed");
+        assert_eq!(names(&directory_listing), vec!("a", "b", "a.txt", "c.txt"));
+        directory_listing.sort_by(DirectoryListingSort::Size, false);
+        assert_eq!(names(&directory_listing), vec!("a", "b", "c.txt", "a.txt"));
+        let parameters: HttpTargetParameters = "sort=name&order=desc".parse().expect("parameters should be valid");
+        directory_listing.sort_by_parameters(Some(&parameters));
+        assert_eq!(names(&directory_listing), vec!("b", "a", "c.txt", "a.txt"));
+    }
+
+    #[test]
+    fn escapes_names_in_html() {
+        let directory_listing = DirectoryListing {
+            path: "/<b>/".to_owned(),
+            entries: vec!(file("<script>alert(1)</script>"), file("a\"b"), file("a:b")),
+            sort: DirectoryListingSort::Name,
+            descending: false,
+        };
+        let html = directory_listing.to_html();
+        assert!(!html.contains("<script>") && !html.contains("<b>"));
+        assert!(html.contains("<title>Index of /&lt;b&gt;/</title>"));
+        assert!(html.contains("<a href=\"%3Cscript%3Ealert(1)%3C%2Fscript%3E\">&lt;script&gt;alert(1)&lt;/script&gt;</a>"));
+        assert!(html.contains("<a href=\"a%22b\">a&quot;b</a>"));
+        // The name isn't read as a scheme
+        assert!(html.contains("<a href=\"./a:b\">a:b</a>"));
+    }
+
+    #[test]
+    fn escapes_names_in_json() {
+        let directory_listing = DirectoryListing {
+            path: "/".to_owned(),
+            entries: vec!(file("<script>"), file("a\"b")),
+            sort: DirectoryListingSort::Name,
+            descending: false,
+        };
+        assert_eq!(
+            directory_listing.to_json(),
+            r#"{"path":"/","entries":[{"name":"\u003cscript\u003e","type":"file","size":1,"modified":null},{"name":"a\"b","type":"file","size":1,"modified":null}]}"#,
+        );
+    }
+}
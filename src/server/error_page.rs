@@ -0,0 +1,106 @@
+use crate::{helper::escape, http_parser::HttpStatusCode};
+
+/// The template used when a virtual host has no error page for a status code, or it can't be read.
+pub const DEFAULT_ERROR_PAGE_TEMPLATE: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{{status}} {{reason}}</title>
+</head>
+<body>
+<h1>{{status}} {{reason}}</h1>
+<p>The request for <code>{{path}}</code> couldn't be completed.</p>
+<p>Request ID: <code>{{request_id}}</code></p>
+</body>
+</html>
+";
+
+/// The values an error page template can include.
+///
+/// A template refers to them as `{{status}}`, `{{reason}}`, `{{request_id}}` and `{{path}}`,
+/// with optional spaces inside the braces. They are HTML-escaped, and any other `{{...}}` is kept as it is.
+#[derive(Clone, Debug)]
+pub struct ErrorPage<'a> {
+    pub status_code: HttpStatusCode,
+    pub request_id: &'a str,
+    /// The decoded path of the request, or `""` if it couldn't be parsed.
+    pub path: &'a str,
+}
+
+impl ErrorPage<'_> {
+    /// Renders `template` with the values filled in.
+    pub fn render(&self, template: &str) -> String {
+        let opening_delimiter = "{{";
+        let closing_delimiter = "}}";
+        let mut output = String::with_capacity(template.len());
+        let mut remaining = template;
+        while let Some(start) = remaining.find(opening_delimiter) {
+            output.push_str(&remaining[..start]);
+            let after_opening = &remaining[start + opening_delimiter.len()..];
+            let (name, after_closing) = match after_opening.split_once(closing_delimiter) {
+                None => {
+                    remaining = &remaining[start..];
+                    break
+                },
+                Some(parts) => parts,
+            };
+            match self.value(name.trim()) {
+                None => output.push_str(&remaining[start..remaining.len() - after_closing.len()]),
+                Some(value) => output.push_str(&escape::html(&value)),
+            }
+            remaining = after_closing;
+        }
+        output.push_str(remaining);
+        output
+    }
+
+    /// Renders the [`DEFAULT_ERROR_PAGE_TEMPLATE`].
+    pub fn render_default(&self) -> String {
+        self.render(DEFAULT_ERROR_PAGE_TEMPLATE)
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        match name {
+            "status" => Some(self.status_code.as_u16().to_string()),
+            "reason" => Some(self.status_code.reason_phrase().to_owned()),
+            "request_id" => Some(self.request_id.to_owned()),
+            "path" => Some(self.path.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_found<'a>(request_id: &'a str, path: &'a str) -> ErrorPage<'a> {
+        ErrorPage { status_code: HttpStatusCode::NotFound404, request_id, path }
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        let error_page = not_found("abc-123", "/missing");
+        assert_eq!(error_page.render("{{status}} {{ reason }} {{request_id}} {{  path  }}"), "404 Not Found abc-123 /missing");
+        assert!(error_page.render_default().contains("<title>404 Not Found</title>"));
+    }
+
+    #[test]
+    fn escapes_values() {
+        let error_page = not_found("\"><script>", "/<img src=x onerror=alert(1)>");
+        assert_eq!(
+            error_page.render("<a title=\"{{request_id}}\">{{path}}</a>"),
+            "<a title=\"&quot;&gt;&lt;script&gt;\">/&lt;img src=x onerror=alert(1)&gt;</a>",
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_and_unterminated_placeholders() {
+        let error_page = not_found("abc-123", "/missing");
+        assert_eq!(error_page.render("{{unknown}} {{status}}"), "{{unknown}} 404");
+        assert_eq!(error_page.render("{{status}} {{path"), "404 {{path");
+        assert_eq!(error_page.render("{{status}} }}{{"), "404 }}{{");
+        // A value containing a placeholder isn't rendered again
+        assert_eq!(not_found("{{status}}", "/").render("{{request_id}}"), "{{status}}");
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{helper::regex::Regex, http_parser::{HttpFieldName, HttpHost, HttpMethod, HttpRequest, HttpStatusCode, HttpTargetParameters, HttpUri}};
+
+/// The most rules that can change a request's target, so a set of rules that never settles is stopped.
+const MAXIMUM_REWRITES: usize = 16;
+
+/// What a [`RewriteRule`] does when it matches.
+///
+/// The substitution can refer to the groups captured by the rule's `path` as `$0` to `$9`, and
+/// `$$` is a literal `$`. If it has no `?`, the request's query is kept.
+#[derive(Clone, Debug)]
+pub enum RewriteAction {
+    /// Changes the target to the substituted path and query, without telling the client.
+    Rewrite(String),
+    /// Redirects the client to the substituted location, which should have a redirection status
+    /// code such as 301, 302, 303, 307 or 308.
+    Redirect { status_code: HttpStatusCode, location: String },
+}
+
+/// What happens after a [`RewriteRule`] matches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RewriteFlow {
+    /// No more rules are checked.
+    #[default]
+    Stop,
+    /// The following rules are checked against the rewritten target.
+    Continue,
+    /// Every rule is checked again from the start against the rewritten target.
+    Restart,
+}
+
+/// A rule that rewrites or redirects requests, checked before the target is mapped to a file.
+///
+/// Every condition that is given must match for the rule to match.
+#[derive(Clone, Debug)]
+pub struct RewriteRule {
+    /// Matches the normalized, still percent-encoded path. Use [`Regex::from_glob`] for a glob.
+    pub path: Option<Regex>,
+    /// Matches the host, as described by [`HttpHost::strip_domain`].
+    pub host: Option<String>,
+    /// The methods the rule matches. If it is empty, every method matches.
+    pub methods: Vec<HttpMethod>,
+    /// Matches the query, without the leading `?`. A request without a query has an empty query.
+    pub query: Option<Regex>,
+    /// Matches the value of each field. A missing field doesn't match.
+    pub fields: Vec<(HttpFieldName, Regex)>,
+    pub action: RewriteAction,
+    pub flow: RewriteFlow,
+}
+
+/// What to do with a request after the [`RewriteRule`]s are applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RewriteResult {
+    /// Serve the request's target, which may have been rewritten.
+    Serve,
+    Redirect { status_code: HttpStatusCode, location: String },
+}
+
+impl RewriteRule {
+    /// Creates a rule that matches every request.
+    pub fn new(action: RewriteAction) -> Self {
+        RewriteRule {
+            path: None,
+            host: None,
+            methods: Vec::new(),
+            query: None,
+            fields: Vec::new(),
+            action,
+            flow: RewriteFlow::default(),
+        }
+    }
+
+    /// Applies `rules` to `http_request` in order, rewriting its target.
+    ///
+    /// Returns [`HttpStatusCode::InternalServerError500`] if the rules loop, or produce a target or
+    /// location that isn't valid.
+    pub fn apply_all(rules: &[RewriteRule], http_request: &mut HttpRequest) -> Result<RewriteResult, HttpStatusCode> {
+        let mut seen_targets = vec!(Self::path_and_query(http_request));
+        let mut rewrite_count = 0;
+        let mut index = 0;
+        while let Some(rule) = rules.get(index) {
+            index += 1;
+            let captures = match rule.captures(http_request) {
+                None => continue,
+                Some(captures) => captures,
+            };
+            let original_query = http_request.target.as_ref()
+                .and_then(|target| target.parameters.as_ref())
+                .map(|parameters| parameters.to_query_string());
+            match &rule.action {
+                RewriteAction::Redirect { status_code, location } => {
+                    let location = Self::keep_query(Self::substitute(location, &captures), original_query);
+                    if !status_code.is_redirection() || location.parse::<HttpUri>().is_err() {
+                        println!("Rewrite rule produced an invalid redirect `{status_code}` to `{location}`");
+                        return Err(HttpStatusCode::InternalServerError500)
+                    }
+                    if location == Self::path_and_query(http_request) {
+                        println!("Rewrite rule redirects `{location}` to itself");
+                        return Err(HttpStatusCode::InternalServerError500)
+                    }
+                    return Ok(RewriteResult::Redirect { status_code: *status_code, location })
+                },
+                RewriteAction::Rewrite(substitution) => {
+                    let path_and_query = Self::keep_query(Self::substitute(substitution, &captures), original_query);
+                    Self::set_target(http_request, &path_and_query)?;
+                    rewrite_count += 1;
+                    let path_and_query = Self::path_and_query(http_request);
+                    if rewrite_count > MAXIMUM_REWRITES || (rule.flow == RewriteFlow::Restart && seen_targets.contains(&path_and_query)) {
+                        println!("Rewrite rules loop on `{path_and_query}`");
+                        return Err(HttpStatusCode::InternalServerError500)
+                    }
+                    seen_targets.push(path_and_query);
+                },
+            }
+            match rule.flow {
+                RewriteFlow::Stop => break,
+                RewriteFlow::Continue => (),
+                RewriteFlow::Restart => index = 0,
+            }
+        }
+        Ok(RewriteResult::Serve)
+    }
+
+    /// Gets the groups captured by the `path` if every condition matches `http_request`.
+    fn captures(&self, http_request: &HttpRequest) -> Option<Vec<String>> {
+        if !self.methods.is_empty() && !http_request.method.as_ref().is_some_and(|method| self.methods.contains(method)) {
+            return None
+        }
+        if let Some(host_pattern) = &self.host {
+            let host = http_request.host()?.parse::<HttpHost>().ok()?;
+            if !host.matches(host_pattern) {
+                return None
+            }
+        }
+        let target = http_request.target.as_ref()?;
+        if let Some(query) = &self.query {
+            let query_string = target.parameters.as_ref().map(|parameters| parameters.to_query_string()).unwrap_or_default();
+            if !query.is_match(&query_string) {
+                return None
+            }
+        }
+        for (field_name, value) in &self.fields {
+            if !value.is_match(http_request.header.as_ref()?.get_value(field_name)?) {
+                return None
+            }
+        }
+        match &self.path {
+            None => Some(Vec::new()),
+            Some(path) => {
+                let captures = path.captures(target.path.as_deref()?)?;
+                Some(captures.into_iter().map(|capture| capture.unwrap_or_default().to_owned()).collect())
+            },
+        }
+    }
+
+    /// Replaces `$0` to `$9` in `substitution` with the captured groups, and `$$` with `$`.
+    fn substitute(substitution: &str, captures: &[String]) -> String {
+        let mut output = String::with_capacity(substitution.len());
+        let mut characters = substitution.chars().peekable();
+        while let Some(character) = characters.next() {
+            if character != '$' {
+                output.push(character);
+                continue;
+            }
+            match characters.peek().copied() {
+                Some('$') => {
+                    characters.next();
+                    output.push('$');
+                },
+                Some(digit) if digit.is_ascii_digit() => {
+                    characters.next();
+                    let index = digit.to_digit(10).expect("`digit` should be a digit") as usize;
+                    output.push_str(captures.get(index).map_or("", |capture| capture.as_str()));
+                },
+                _ => output.push('$'),
+            }
+        }
+        output
+    }
+
+    /// Adds `query` to `location` if it doesn't have a query of its own.
+    fn keep_query(location: String, query: Option<String>) -> String {
+        match (location.contains('?'), query) {
+            (false, Some(query)) if !query.is_empty() => format!("{location}?{query}"),
+            _ => location,
+        }
+    }
+
+    fn set_target(http_request: &mut HttpRequest, path_and_query: &str) -> Result<(), HttpStatusCode> {
+        let uri = match HttpUri::from_path_and_query(path_and_query) {
+            None => {
+                println!("Rewrite rule produced an invalid target `{path_and_query}`");
+                return Err(HttpStatusCode::InternalServerError500)
+            },
+            Some(uri) => uri.normalize(),
+        };
+        let parameters = match uri.query.as_deref() {
+            None | Some("") => None,
+            Some(query) => Some(query.parse::<HttpTargetParameters>().map_err(|_| HttpStatusCode::InternalServerError500)?),
+        };
+        let target = http_request.target.get_or_insert_with(Default::default);
+        target.path = Some(uri.path);
+        target.parameters = parameters;
+        Ok(())
+    }
+
+    fn path_and_query(http_request: &HttpRequest) -> String {
+        http_request.target.as_ref().map(|target| target.to_uri().path_and_query()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::parse;
+
+    fn rewrite(path: &str, substitution: &str, flow: RewriteFlow) -> RewriteRule {
+        RewriteRule {
+            path: Some(path.parse().expect("`path` should be a valid regex")),
+            flow,
+            ..RewriteRule::new(RewriteAction::Rewrite(substitution.to_owned()))
+        }
+    }
+
+    fn redirect(path: &str, status_code: HttpStatusCode, location: &str) -> RewriteRule {
+        RewriteRule {
+            path: Some(path.parse().expect("`path` should be a valid regex")),
+            ..RewriteRule::new(RewriteAction::Redirect { status_code, location: location.to_owned() })
+        }
+    }
+
+    /// Applies `rules` to a GET of `target`, giving the result and the target afterwards.
+    fn apply(rules: &[RewriteRule], target: &str) -> (Result<RewriteResult, HttpStatusCode>, String) {
+        let request_bytes = format!("GET {target} HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        let mut http_request = parse(request_bytes.as_bytes());
+        let result = RewriteRule::apply_all(rules, &mut http_request);
+        (result, RewriteRule::path_and_query(&http_request))
+    }
+
+    #[test]
+    fn substitutes_captures() {
+        let rules = [rewrite("^/blog/([0-9]+)/(.*)$", "/posts/$2-$1/$$$3", RewriteFlow::Stop)];
+        assert_eq!(apply(&rules, "/blog/2024/hello"), (Ok(RewriteResult::Serve), "/posts/hello-2024/$".to_owned()));
+        assert_eq!(apply(&rules, "/about"), (Ok(RewriteResult::Serve), "/about".to_owned()));
+    }
+
+    #[test]
+    fn keeps_query_unless_replaced() {
+        let rules = [rewrite("^/old$", "/new", RewriteFlow::Stop)];
+        assert_eq!(apply(&rules, "/old?a=1&b=2").1, "/new?a=1&b=2");
+        let rules = [rewrite("^/old$", "/new?c=3", RewriteFlow::Stop)];
+        assert_eq!(apply(&rules, "/old?a=1").1, "/new?c=3");
+        let rules = [redirect("^/old$", HttpStatusCode::MovedPermanently301, "/new")];
+        assert_eq!(apply(&rules, "/old?a=1").0, Ok(RewriteResult::Redirect {
+            status_code: HttpStatusCode::MovedPermanently301,
+            location: "/new?a=1".to_owned(),
+        }));
+    }
+
+    #[test]
+    fn follows_flow() {
+        let rules = |flow| [rewrite("^/a$", "/b", flow), rewrite("^/b$", "/c", RewriteFlow::Stop), rewrite("^/c$", "/a", RewriteFlow::Stop)];
+        assert_eq!(apply(&rules(RewriteFlow::Stop), "/a").1, "/b");
+        assert_eq!(apply(&rules(RewriteFlow::Continue), "/a").1, "/c");
+        let rules = [rewrite("^/c$", "/d", RewriteFlow::Stop), rewrite("^/a$", "/c", RewriteFlow::Restart)];
+        assert_eq!(apply(&rules, "/a").1, "/d");
+        let rules = [rewrite("^/c$", "/d", RewriteFlow::Stop), rewrite("^/a$", "/c", RewriteFlow::Continue)];
+        assert_eq!(apply(&rules, "/a").1, "/c");
+    }
+
+    #[test]
+    fn stops_loops() {
+        let rules = [rewrite("^/a$", "/b", RewriteFlow::Restart), rewrite("^/b$", "/a", RewriteFlow::Restart)];
+        assert_eq!(apply(&rules, "/a").0, Err(HttpStatusCode::InternalServerError500));
+        let rules = [rewrite("^/(x*)$", "/$1x", RewriteFlow::Restart)];
+        assert_eq!(apply(&rules, "/").0, Err(HttpStatusCode::InternalServerError500));
+        let rules = vec!(rewrite("^/(x*)$", "/$1x", RewriteFlow::Continue); MAXIMUM_REWRITES);
+        assert_eq!(apply(&rules, "/").1, format!("/{}", "x".repeat(MAXIMUM_REWRITES)));
+        let rules = vec!(rewrite("^/(x*)$", "/$1x", RewriteFlow::Continue); MAXIMUM_REWRITES + 1);
+        assert_eq!(apply(&rules, "/").0, Err(HttpStatusCode::InternalServerError500));
+    }
+
+    #[test]
+    fn rejects_invalid_redirects() {
+        let rules = [redirect("^/a$", HttpStatusCode::Found302, "/a")];
+        assert_eq!(apply(&rules, "/a").0, Err(HttpStatusCode::InternalServerError500));
+        let rules = [redirect("^/a$", HttpStatusCode::Found302, "/b")];
+        assert_eq!(apply(&rules, "/a?q=1").0, Ok(RewriteResult::Redirect {
+            status_code: HttpStatusCode::Found302,
+            location: "/b?q=1".to_owned(),
+        }));
+        let rules = [redirect("^/a$", HttpStatusCode::OK200, "/b")];
+        assert_eq!(apply(&rules, "/a").0, Err(HttpStatusCode::InternalServerError500));
+        let rules = [redirect("^/a$", HttpStatusCode::NotFound404, "/b")];
+        assert_eq!(apply(&rules, "/a").0, Err(HttpStatusCode::InternalServerError500));
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+/// How the subdomains of a [`super::VirtualHost`] are mapped to the directories they are served from.
+///
+/// Directories are relative to the [`super::Config`]'s `top_directory`.
+#[derive(Clone, Debug, Default)]
+pub enum SubdomainMapping {
+    /// Subdomains are served from the virtual host's document root.
+    #[default]
+    DocumentRoot,
+    /// Each label is a deeper directory in `directory`, read right-to-left, so `uk.shop` is served from `directory/shop/uk`.
+    ReversedNesting { directory: String },
+    /// Each subdomain has its own directory in `directory`, so `uk.shop` is served from `directory/uk.shop`.
+    Flat { directory: String },
+    /// Each subdomain is served from the directory it is mapped to. Other subdomains aren't served.
+    Explicit { directories: HashMap<String, String> },
+    /// The directory is chosen by a function, which returns [`None`] if the subdomain isn't served.
+    Custom(fn(&str) -> Option<String>),
+}
+
+impl SubdomainMapping {
+    /// Gets the directory `subdomain` is served from, or [`None`] if it is served from the
+    /// document root or isn't served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use webserver::server::SubdomainMapping;
+    ///
+    /// let mapping = SubdomainMapping::ReversedNesting { directory: "subdomains".to_owned() };
+    /// assert_eq!(Some("subdomains/shop/uk".to_owned()), mapping.directory_for("uk.shop"));
+    /// ```
+    pub fn directory_for(&self, subdomain: &str) -> Option<String> {
+        let directory_delimiter = '/';
+        match self {
+            Self::DocumentRoot => None,
+            Self::ReversedNesting { directory } => Some(format!("{directory}{directory_delimiter}{}", Self::subdomain_as_path(subdomain))),
+            Self::Flat { directory } => Some(format!("{directory}{directory_delimiter}{subdomain}")),
+            Self::Explicit { directories } => directories.get(subdomain).cloned(),
+            Self::Custom(directory_for) => directory_for(subdomain),
+        }
+    }
+
+    /// Gets the subdomain as a directory path, where each subdomain is a deeper folder.
+    ///
+    /// The subdomains are read right-to-left, so `uk.shop` becomes `shop/uk`.
+    fn subdomain_as_path(subdomain: &str) -> String {
+        let subdomain_delimiter = '.';
+        let path_separator = '/';
+        let subdomains = subdomain.rsplit(subdomain_delimiter);
+        let mut path = String::with_capacity(subdomain.len());
+        for subdomain in subdomains {
+            path.push_str(subdomain);
+            path.push(path_separator);
+        }
+        path.pop();
+        path
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::http_parser::{HttpRequest, HttpUriBuilder};
+
+use super::{TryFiles, VirtualHostMatch};
+
+/// What a request's target refers to on the filesystem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolvedTarget {
+    /// A file, which may be an index file of the requested directory.
+    File(PathBuf),
+    /// A directory without any of the virtual host's index files.
+    Directory(PathBuf),
+    /// A directory requested without a trailing `/`, which the client should be redirected to
+    /// the location of, so relative links in it work.
+    DirectoryRedirect(String),
+}
+
+impl ResolvedTarget {
+    /// Resolves the target of `http_request` in the virtual host's document root by checking the filesystem.
+    ///
+    /// A directory is resolved to the first of the virtual host's index files that exists in it.
+    /// A target with a trailing `/` that refers to a file gets an [`io::ErrorKind::NotADirectory`] error,
+    /// and a target whose path can't be safely decoded gets an [`io::ErrorKind::NotFound`] error.
+    /// If the virtual host has [`TryFiles`] for the path, its candidates are tried instead.
+    pub fn resolve(site: &VirtualHostMatch, http_request: &HttpRequest) -> io::Result<Self> {
+        let directory_delimiter = '/';
+        let not_found = || io::Error::new(io::ErrorKind::NotFound, "target can't be decoded");
+        let target = http_request.target.as_ref().ok_or_else(not_found)?;
+        let decoded_path = target.decoded_path().ok_or_else(not_found)?;
+        let directory_redirect = || {
+            let mut uri = target.to_uri();
+            uri.path.push(directory_delimiter);
+            ResolvedTarget::DirectoryRedirect(HttpUriBuilder::from_uri(&uri).build().to_string())
+        };
+
+        let try_files = match TryFiles::select(&site.virtual_host.try_files, &decoded_path) {
+            None => return match Self::resolve_path(site, &decoded_path) {
+                Err(error) if error.kind() == io::ErrorKind::IsADirectory => Ok(directory_redirect()),
+                result => result,
+            },
+            Some(try_files) => try_files,
+        };
+
+        for candidate in try_files.candidates_for(&decoded_path) {
+            let resolved_target = match Self::resolve_path(site, &candidate) {
+                Err(_) => continue,
+                Ok(resolved_target) => resolved_target,
+            };
+            // Like a directory without a trailing `/`, so relative links in it work
+            if !decoded_path.ends_with(directory_delimiter) && candidate.strip_suffix(directory_delimiter) == Some(decoded_path.as_str()) {
+                return Ok(directory_redirect())
+            }
+            return Ok(resolved_target)
+        }
+        match &try_files.fallback {
+            Some(fallback) if !TryFiles::is_asset(&decoded_path) => Self::resolve_path(site, fallback),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, "no candidate file exists")),
+        }
+    }
+
+    /// Resolves the decoded `path` in the virtual host's document root.
+    ///
+    /// A directory without a trailing `/` gets an [`io::ErrorKind::IsADirectory`] error.
+    fn resolve_path(site: &VirtualHostMatch, path: &str) -> io::Result<Self> {
+        let directory_delimiter = '/';
+        let full_path = PathBuf::from(format!("{}{path}", site.document_root.trim_end_matches(directory_delimiter)));
+        let metadata = fs::metadata(&full_path)?;
+        let has_trailing_delimiter = path.ends_with(directory_delimiter);
+
+        match (metadata.is_dir(), has_trailing_delimiter) {
+            (false, true) => Err(io::Error::new(io::ErrorKind::NotADirectory, "target is a file but has a trailing `/`")),
+            (false, false) => Ok(ResolvedTarget::File(full_path)),
+            (true, false) => Err(io::Error::new(io::ErrorKind::IsADirectory, "target is a directory but has no trailing `/`")),
+            (true, true) => match Self::find_index_file(site, &full_path) {
+                None => Ok(ResolvedTarget::Directory(full_path)),
+                Some(index_path) => Ok(ResolvedTarget::File(index_path)),
+            },
+        }
+    }
+
+    /// Finds the first of the virtual host's index files that is a file in `directory`.
+    fn find_index_file(site: &VirtualHostMatch, directory: &Path) -> Option<PathBuf> {
+        site.virtual_host.index_filenames.iter()
+            .map(|index_filename| directory.join(index_filename))
+            .find(|index_path| index_path.is_file())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helper::file::TestDirectory, server::{tests::parse, VirtualHost}};
+
+    fn resolve(directory: &TestDirectory, target: &str) -> io::Result<ResolvedTarget> {
+        let virtual_host = VirtualHost { index_filenames: vec!("index.html".to_owned()), ..Default::default() };
+        let site = VirtualHostMatch { virtual_host: &virtual_host, subdomain: String::new(), document_root: directory.0.to_string_lossy().into_owned() };
+        let request_bytes = format!("GET {target} HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        ResolvedTarget::resolve(&site, &parse(request_bytes.as_bytes()))
+    }
+
+    #[test]
+    fn resolves_files() {
+        let directory = TestDirectory::new();
+        directory.write("LICENSE", "");
+        directory.write("v1.2/notes.txt", "");
+        assert_eq!(resolve(&directory, "/LICENSE").ok(), Some(ResolvedTarget::File(directory.0.join("LICENSE"))));
+        assert_eq!(resolve(&directory, "/v1.2/notes.txt").ok(), Some(ResolvedTarget::File(directory.0.join("v1.2/notes.txt"))));
+        assert_eq!(resolve(&directory, "/missing").map_err(|error| error.kind()), Err(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn resolves_directories() {
+        let directory = TestDirectory::new();
+        directory.write("v1.2/index.html", "");
+        directory.write("empty/.hidden", "");
+        assert_eq!(resolve(&directory, "/v1.2/").ok(), Some(ResolvedTarget::File(directory.0.join("v1.2/index.html"))));
+        assert_eq!(resolve(&directory, "/empty/").ok(), Some(ResolvedTarget::Directory(directory.0.join("empty/"))));
+    }
+
+    #[test]
+    fn redirects_directory_without_trailing_slash() {
+        let directory = TestDirectory::new();
+        directory.write("v1.2/index.html", "");
+        assert_eq!(resolve(&directory, "/v1.2").ok(), Some(ResolvedTarget::DirectoryRedirect("/v1.2/".to_owned())));
+        assert_eq!(resolve(&directory, "/v1.2?page=2").ok(), Some(ResolvedTarget::DirectoryRedirect("/v1.2/?page=2".to_owned())));
+    }
+
+    #[test]
+    fn rejects_file_with_trailing_slash() {
+        let directory = TestDirectory::new();
+        directory.write("LICENSE", "");
+        assert_eq!(resolve(&directory, "/LICENSE/").map_err(|error| error.kind()), Err(io::ErrorKind::NotADirectory));
+    }
+
+    #[test]
+    fn rejects_paths_that_cant_be_decoded() {
+        let directory = TestDirectory::new();
+        directory.write("secret/file", "");
+        for target in ["/secret%2Ffile", "/secret%5Cfile", "/secret/file%00", "/secret/%FF"] {
+            assert_eq!(resolve(&directory, target).map_err(|error| error.kind()), Err(io::ErrorKind::NotFound), "{target}");
+        }
+        // Dot segments are removed when the target is parsed, so they can't lead outside the document root
+        assert_eq!(resolve(&directory, "/%2e%2e/secret/file").ok(), Some(ResolvedTarget::File(directory.0.join("secret/file"))));
+    }
+}
@@ -0,0 +1,123 @@
+/// The files to try, in order, for requests with a path under a location, like nginx's `try_files`.
+///
+/// Each candidate is a path relative to the document root, with `$uri` replaced by the request's
+/// decoded path. A candidate ending with `/` only matches a directory, which is served by its
+/// index file, and any other candidate only matches a file.
+#[derive(Clone, Debug, Default)]
+pub struct TryFiles {
+    /// The path prefix the candidates are tried for, such as `/` or `/app/`.
+    pub location: String,
+    pub candidates: Vec<String>,
+    /// The file served when no candidate matches and the path isn't for an asset, such as a
+    /// single-page app's `/index.html`. Missing assets are still not found.
+    pub fallback: Option<String>,
+}
+
+impl TryFiles {
+    /// Creates a list that serves `/about` from `about.html`, as well as files and directories.
+    pub fn clean_urls(location: &str) -> Self {
+        TryFiles {
+            location: location.to_owned(),
+            candidates: vec!("$uri".to_owned(), "$uri.html".to_owned(), "$uri/".to_owned()),
+            fallback: None,
+        }
+    }
+
+    /// Creates a list that serves files and directories, and `app_shell` for any other path that isn't for an asset.
+    pub fn single_page_app(location: &str, app_shell: &str) -> Self {
+        TryFiles {
+            location: location.to_owned(),
+            candidates: vec!("$uri".to_owned(), "$uri/".to_owned()),
+            fallback: Some(app_shell.to_owned()),
+        }
+    }
+
+    /// Selects the list with the longest `location` that `path` starts with.
+    pub fn select<'a>(try_files: &'a [TryFiles], path: &str) -> Option<&'a TryFiles> {
+        try_files.iter()
+            .filter(|try_files| path.starts_with(try_files.location.as_str()))
+            .max_by_key(|try_files| try_files.location.len())
+    }
+
+    /// Gets the candidate paths for `path`, in order.
+    pub fn candidates_for(&self, path: &str) -> Vec<String> {
+        self.candidates.iter().map(|candidate| candidate.replace("$uri", path)).collect()
+    }
+
+    /// Returns `true` if `path` is for an asset, which is when its last segment has an extension.
+    pub fn is_asset(path: &str) -> bool {
+        let directory_delimiter = '/';
+        let file_extension_delimiter = '.';
+        match path.rsplit_once(directory_delimiter) {
+            None => path.contains(file_extension_delimiter),
+            Some((_, filename)) => filename.contains(file_extension_delimiter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{helper::file::TestDirectory, server::{tests::parse, ResolvedTarget, VirtualHost, VirtualHostMatch}};
+
+    fn resolve(directory: &TestDirectory, try_files: TryFiles, target: &str) -> io::Result<ResolvedTarget> {
+        let virtual_host = VirtualHost { index_filenames: vec!("index.html".to_owned()), try_files: vec!(try_files), ..Default::default() };
+        let site = VirtualHostMatch { virtual_host: &virtual_host, subdomain: String::new(), document_root: directory.0.to_string_lossy().into_owned() };
+        let request_bytes = format!("GET {target} HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        ResolvedTarget::resolve(&site, &parse(request_bytes.as_bytes()))
+    }
+
+    #[test]
+    fn selects_longest_location() {
+        let try_files = [TryFiles::clean_urls("/"), TryFiles::clean_urls("/app/"), TryFiles::clean_urls("/application/")];
+        assert_eq!(TryFiles::select(&try_files, "/app/page").map(|try_files| try_files.location.as_str()), Some("/app/"));
+        assert_eq!(TryFiles::select(&try_files, "/about").map(|try_files| try_files.location.as_str()), Some("/"));
+        assert!(TryFiles::select(&try_files[1..], "/about").is_none());
+    }
+
+    #[test]
+    fn detects_assets_by_last_segment() {
+        assert!(TryFiles::is_asset("/app.js"));
+        assert!(TryFiles::is_asset("/v1.2/app.min.css"));
+        assert!(!TryFiles::is_asset("/v1.2/settings"));
+        assert!(!TryFiles::is_asset("/"));
+    }
+
+    #[test]
+    fn tries_candidates_in_order() {
+        let directory = TestDirectory::new();
+        directory.write("page", "");
+        directory.write("page.html", "");
+        directory.write("other.html", "");
+        let try_files = TryFiles { location: "/".to_owned(), candidates: vec!("$uri.html".to_owned(), "$uri".to_owned()), fallback: None };
+        assert_eq!(try_files.candidates_for("/page"), vec!("/page.html", "/page"));
+        assert_eq!(resolve(&directory, try_files.clone(), "/page").ok(), Some(ResolvedTarget::File(directory.0.join("page.html"))));
+        assert_eq!(resolve(&directory, try_files, "/other").ok(), Some(ResolvedTarget::File(directory.0.join("other.html"))));
+    }
+
+    #[test]
+    fn serves_clean_urls() {
+        let directory = TestDirectory::new();
+        directory.write("about.html", "");
+        directory.write("docs/index.html", "");
+        let try_files = TryFiles::clean_urls("/");
+        assert_eq!(resolve(&directory, try_files.clone(), "/about").ok(), Some(ResolvedTarget::File(directory.0.join("about.html"))));
+        assert_eq!(resolve(&directory, try_files.clone(), "/about.html").ok(), Some(ResolvedTarget::File(directory.0.join("about.html"))));
+        assert_eq!(resolve(&directory, try_files.clone(), "/docs/").ok(), Some(ResolvedTarget::File(directory.0.join("docs/index.html"))));
+        assert_eq!(resolve(&directory, try_files.clone(), "/docs").ok(), Some(ResolvedTarget::DirectoryRedirect("/docs/".to_owned())));
+        assert_eq!(resolve(&directory, try_files, "/missing").map_err(|error| error.kind()), Err(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn falls_back_to_app_shell_except_for_assets() {
+        let directory = TestDirectory::new();
+        directory.write("index.html", "");
+        directory.write("app.js", "");
+        let try_files = TryFiles::single_page_app("/", "/index.html");
+        assert_eq!(resolve(&directory, try_files.clone(), "/app.js").ok(), Some(ResolvedTarget::File(directory.0.join("app.js"))));
+        assert_eq!(resolve(&directory, try_files.clone(), "/settings/profile").ok(), Some(ResolvedTarget::File(directory.0.join("index.html"))));
+        assert_eq!(resolve(&directory, try_files, "/missing.js").map_err(|error| error.kind()), Err(io::ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,531 @@
+use std::{collections::HashMap, error::Error, fs::{self, OpenOptions}, io::{self, Write}, path::{Path, PathBuf}, sync::Mutex, time::{Duration, SystemTime}};
+
+use crate::{helper::{base64, file, random}, http_parser::{HttpDate, HttpFieldName, HttpHeader, HttpMethod, HttpRequest, HttpResponse, HttpStatusCode}};
+
+use super::basic_authentication;
+
+/// The version of the tus protocol that is supported.
+const TUS_VERSION: &str = "1.0.0";
+
+/// The tus extensions that are supported.
+const TUS_EXTENSIONS: &str = "creation,creation-with-upload,termination,expiration";
+
+/// The methods that are only allowed under a [`Tus`] location.
+pub const TUS_METHODS: [HttpMethod; 3] = [HttpMethod::Post, HttpMethod::Patch, HttpMethod::Delete];
+
+/// Held while an upload is changed, so two requests can't append to it at the same offset.
+static UPLOADS: Mutex<()> = Mutex::new(());
+
+/// When each staging directory was last checked for expired uploads.
+static EXPIRY_CHECKS: Mutex<Vec<(String, SystemTime)>> = Mutex::new(Vec::new());
+
+/// How often a staging directory is checked for expired uploads.
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A [tus](https://tus.io/protocols/resumable-upload) 1.0 endpoint for resumable uploads under a
+/// location of a [`super::VirtualHost`].
+///
+/// An upload is created with a `POST` request to the location, which responds with the URL of the
+/// upload. Its data is then appended with `PATCH` requests, which can resume from the offset given
+/// by a `HEAD` request after a connection fails. Completed uploads are left in the `staging_directory`
+/// as a file named after the upload's ID.
+///
+/// Request bodies aren't streamed to the staging directory yet. A `PATCH` request is received into
+/// memory in full, like any other request, before its data is appended, so each chunk is limited by
+/// the [`super::Config`]'s `request_maximum_buffer_size_kilobytes` and the virtual host's
+/// `maximum_body_size_kilobytes`. Clients have to split uploads into chunks no larger than those
+/// limits, as a larger chunk gets [`HttpStatusCode::ContentTooLarge413`].
+///
+/// If there are no `users`, requests aren't authenticated, so the endpoint should be behind
+/// another form of authentication, such as a reverse proxy.
+#[derive(Clone, Debug)]
+pub struct Tus {
+    /// The path prefix the endpoint serves, ending with `/`, such as `/files/`.
+    pub location: String,
+    /// The full path of the directory uploads are written to, which shouldn't be served.
+    pub staging_directory: String,
+    /// The largest upload that can be created. `0` means there is no limit.
+    pub maximum_size_bytes: u64,
+    /// How long an incomplete upload is kept after it was last changed. `0` means it is kept forever.
+    pub expiration_seconds: u64,
+    /// The passwords of the users that are allowed, by user ID. If it is empty, every request is allowed.
+    pub users: HashMap<String, String>,
+    /// The realm sent in the `WWW-Authenticate` field.
+    pub realm: String,
+}
+
+/// An upload in the staging directory.
+struct TusUpload {
+    data_path: PathBuf,
+    information_path: PathBuf,
+    /// The size of the whole upload.
+    length: u64,
+    /// The `Upload-Metadata` it was created with.
+    metadata: String,
+    /// How much of it has been received.
+    offset: u64,
+    /// When data was last appended to it.
+    modified: SystemTime,
+}
+
+impl Default for Tus {
+    fn default() -> Self {
+        Tus {
+            location: "/files/".to_owned(),
+            staging_directory: std::env::temp_dir().join("tus").to_string_lossy().into_owned(),
+            maximum_size_bytes: 1024 * 1024 * 1024,
+            expiration_seconds: 24 * 60 * 60,
+            users: HashMap::new(),
+            realm: "tus".to_owned(),
+        }
+    }
+}
+
+impl Tus {
+    /// Returns `true` if the decoded `path` is under the location.
+    pub fn serves(&self, path: &str) -> bool {
+        let directory_delimiter = '/';
+        path.starts_with(self.location.as_str()) || self.location.strip_suffix(directory_delimiter) == Some(path)
+    }
+
+    /// Gets the methods handled for the decoded `path`, which is under the location. `POST` creates
+    /// an upload at the location itself, and the other methods are for an upload.
+    pub fn methods_for(&self, path: &str) -> &'static [HttpMethod] {
+        match self.upload_id(path).is_empty() {
+            false => &[HttpMethod::Options, HttpMethod::Head, HttpMethod::Patch, HttpMethod::Delete],
+            true => &[HttpMethod::Options, HttpMethod::Post],
+        }
+    }
+
+    /// Responds to a request for a path under the location, where `allowed_methods` are the methods
+    /// allowed for it.
+    pub fn respond(&self, http_request: &HttpRequest, allowed_methods: &[HttpMethod]) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let mut result = self.respond_to(http_request, allowed_methods);
+        let http_response = match &mut result {
+            Err((http_response, _)) => http_response,
+            Ok(http_response) => http_response,
+        };
+        let http_header = http_response.header.get_or_insert_with(HttpHeader::new);
+        http_header.insert(tus_field("Tus-Resumable"), TUS_VERSION);
+        if http_response.status_code == HttpStatusCode::Unauthorized401 {
+            http_header.insert(HttpFieldName::WwwAuthenticate, &basic_authentication::challenge(&self.realm));
+        }
+        result
+    }
+
+    fn respond_to(&self, http_request: &HttpRequest, allowed_methods: &[HttpMethod]) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let header = http_request.header.clone().unwrap_or_default();
+        let method = http_request.method.as_ref().expect("`http_request.method` should be `Some`");
+        self.remove_expired();
+
+        if *method == HttpMethod::Options {
+            let allowed_methods: Vec<&str> = allowed_methods.iter().map(|method| method.as_str()).collect();
+            let mut http_header = HttpHeader::new();
+            http_header.insert(HttpFieldName::Allow, &allowed_methods.join(", "));
+            http_header.insert(tus_field("Tus-Version"), TUS_VERSION);
+            http_header.insert(tus_field("Tus-Extension"), TUS_EXTENSIONS);
+            if self.maximum_size_bytes > 0 {
+                http_header.insert(tus_field("Tus-Max-Size"), &self.maximum_size_bytes.to_string());
+            }
+            return Ok(response(http_request, HttpStatusCode::NoContent204, http_header))
+        }
+        // `OPTIONS` is allowed without credentials, as browsers don't send them in a CORS preflight request
+        if !self.users.is_empty() && !basic_authentication::is_authenticated(&self.users, http_request) {
+            return Err(failure(http_request, HttpStatusCode::Unauthorized401, "tus request isn't authenticated"))
+        }
+        if header.get_value(&tus_field("Tus-Resumable")).map(|version| version.trim()) != Some(TUS_VERSION) {
+            let mut http_response = failure(http_request, HttpStatusCode::PreconditionFailed412, "`Tus-Resumable` isn't a supported version");
+            http_response.0.header.get_or_insert_with(HttpHeader::new).insert(tus_field("Tus-Version"), TUS_VERSION);
+            return Err(http_response)
+        }
+
+        let path = http_request.target.as_ref().and_then(|target| target.decoded_path()).unwrap_or_default();
+        let id = self.upload_id(&path);
+        match (method, id.is_empty()) {
+            (HttpMethod::Post, true) => self.create(http_request, &header),
+            (HttpMethod::Head, false) => self.progress(http_request, id),
+            (HttpMethod::Patch, false) => self.append(http_request, &header, id),
+            (HttpMethod::Delete, false) => self.terminate(http_request, id),
+            (method, _) => {
+                let http_version = http_request.version.as_ref().expect("`http_request.version` should be `Some`");
+                let allowed_methods: Vec<HttpMethod> = allowed_methods.iter().filter(|allowed_method| *allowed_method != method).cloned().collect();
+                let error = io::Error::other(format!("method `{method}` isn't allowed for this tus resource"));
+                Err((super::method_not_allowed_response(http_version, &allowed_methods), Box::new(error)))
+            },
+        }
+    }
+
+    /// Gets the ID of the upload the decoded `path` is for, or `""` for the location itself.
+    fn upload_id<'a>(&self, path: &'a str) -> &'a str {
+        let directory_delimiter = '/';
+        path.strip_prefix(self.location.as_str()).unwrap_or_default().trim_end_matches(directory_delimiter)
+    }
+
+    /// Creates an upload, appending the body to it if there is one.
+    fn create(&self, http_request: &HttpRequest, header: &HttpHeader) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let length = header.get_value(&tus_field("Upload-Length"))
+            .and_then(|length| length.trim().parse::<u64>().ok())
+            .ok_or_else(|| failure(http_request, HttpStatusCode::BadRequest400, "`Upload-Length` is missing or isn't valid"))?;
+        if self.maximum_size_bytes > 0 && length > self.maximum_size_bytes {
+            return Err(failure(http_request, HttpStatusCode::ContentTooLarge413, "`Upload-Length` is larger than the maximum size"))
+        }
+        let metadata = header.get_value(&tus_field("Upload-Metadata")).map(|metadata| metadata.trim().to_owned()).unwrap_or_default();
+        if !is_valid_metadata(&metadata) {
+            return Err(failure(http_request, HttpStatusCode::BadRequest400, "`Upload-Metadata` isn't valid"))
+        }
+        let body = http_request.body.unwrap_or_default();
+        if !body.is_empty() && !has_offset_content_type(header) {
+            return Err(failure(http_request, HttpStatusCode::UnsupportedMediaType415, "upload data must be `application/offset+octet-stream`"))
+        }
+        if body.len() as u64 > length {
+            return Err(failure(http_request, HttpStatusCode::ContentTooLarge413, "body is larger than `Upload-Length`"))
+        }
+
+        let id = random::uuid();
+        let upload = TusUpload {
+            data_path: self.data_path(&id),
+            information_path: self.information_path(&id),
+            length,
+            metadata,
+            offset: 0,
+            modified: SystemTime::now(),
+        };
+        upload.save().map_err(|error| io_failure(http_request, error))?;
+        let upload = match body.is_empty() {
+            true => upload,
+            false => {
+                let _uploads = UPLOADS.lock().unwrap_or_else(|error| error.into_inner());
+                upload.append(body).map_err(|error| io_failure(http_request, error))?
+            },
+        };
+
+        let mut http_header = HttpHeader::new();
+        http_header.insert(HttpFieldName::Location, &format!("{}{id}", self.location));
+        if !body.is_empty() {
+            http_header.insert(tus_field("Upload-Offset"), &upload.offset.to_string());
+        }
+        self.insert_expires(&mut http_header, &upload);
+        Ok(response(http_request, HttpStatusCode::Created201, http_header))
+    }
+
+    /// Responds with how much of an upload has been received.
+    fn progress(&self, http_request: &HttpRequest, id: &str) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let upload = self.upload(http_request, id)?;
+        let mut http_header = HttpHeader::new();
+        http_header.insert(tus_field("Upload-Offset"), &upload.offset.to_string());
+        http_header.insert(tus_field("Upload-Length"), &upload.length.to_string());
+        if !upload.metadata.is_empty() {
+            http_header.insert(tus_field("Upload-Metadata"), &upload.metadata);
+        }
+        http_header.insert(HttpFieldName::CacheControl, "no-store");
+        self.insert_expires(&mut http_header, &upload);
+        Ok(response(http_request, HttpStatusCode::OK200, http_header))
+    }
+
+    /// Appends the body to an upload at the offset given by `Upload-Offset`.
+    fn append(&self, http_request: &HttpRequest, header: &HttpHeader, id: &str) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        if !has_offset_content_type(header) {
+            return Err(failure(http_request, HttpStatusCode::UnsupportedMediaType415, "upload data must be `application/offset+octet-stream`"))
+        }
+        let offset = header.get_value(&tus_field("Upload-Offset"))
+            .and_then(|offset| offset.trim().parse::<u64>().ok())
+            .ok_or_else(|| failure(http_request, HttpStatusCode::BadRequest400, "`Upload-Offset` is missing or isn't valid"))?;
+        let body = http_request.body.unwrap_or_default();
+
+        let _uploads = UPLOADS.lock().unwrap_or_else(|error| error.into_inner());
+        let upload = self.upload(http_request, id)?;
+        if offset != upload.offset {
+            return Err(failure(http_request, HttpStatusCode::Conflict409, "`Upload-Offset` doesn't match the upload's offset"))
+        }
+        if upload.offset + body.len() as u64 > upload.length {
+            return Err(failure(http_request, HttpStatusCode::ContentTooLarge413, "body is larger than the rest of the upload"))
+        }
+        let upload = upload.append(body).map_err(|error| io_failure(http_request, error))?;
+
+        let mut http_header = HttpHeader::new();
+        http_header.insert(tus_field("Upload-Offset"), &upload.offset.to_string());
+        self.insert_expires(&mut http_header, &upload);
+        Ok(response(http_request, HttpStatusCode::NoContent204, http_header))
+    }
+
+    /// Deletes an upload, whether or not it is complete.
+    fn terminate(&self, http_request: &HttpRequest, id: &str) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let _uploads = UPLOADS.lock().unwrap_or_else(|error| error.into_inner());
+        let upload = self.upload(http_request, id)?;
+        upload.remove().map_err(|error| io_failure(http_request, error))?;
+        Ok(response(http_request, HttpStatusCode::NoContent204, HttpHeader::new()))
+    }
+
+    /// Reads the upload with `id`, responding with [`HttpStatusCode::NotFound404`] if there isn't one.
+    fn upload(&self, http_request: &HttpRequest, id: &str) -> Result<TusUpload, (HttpResponse, Box<dyn Error>)> {
+        let is_valid_id = id.chars().all(|character| character.is_ascii_hexdigit() || character == '-');
+        let upload = match is_valid_id {
+            false => None,
+            true => TusUpload::read(self.data_path(id), self.information_path(id)),
+        };
+        upload.ok_or_else(|| failure(http_request, HttpStatusCode::NotFound404, "upload doesn't exist"))
+    }
+
+    /// Deletes the incomplete uploads that haven't been changed within the `expiration_seconds`,
+    /// if the staging directory hasn't been checked within the [`EXPIRY_CHECK_INTERVAL`].
+    fn remove_expired(&self) {
+        if self.expiration_seconds == 0 {
+            return
+        }
+        let now = SystemTime::now();
+        {
+            let mut expiry_checks = EXPIRY_CHECKS.lock().unwrap_or_else(|error| error.into_inner());
+            match expiry_checks.iter_mut().find(|(staging_directory, _)| *staging_directory == self.staging_directory) {
+                None => expiry_checks.push((self.staging_directory.clone(), now)),
+                Some((_, checked)) if now.duration_since(*checked).is_ok_and(|elapsed| elapsed < EXPIRY_CHECK_INTERVAL) => return,
+                Some((_, checked)) => *checked = now,
+            }
+        }
+        let Ok(entries) = fs::read_dir(&self.staging_directory) else {
+            return
+        };
+        let _uploads = UPLOADS.lock().unwrap_or_else(|error| error.into_inner());
+        for entry in entries.flatten() {
+            let filename = entry.file_name().to_string_lossy().into_owned();
+            let Some(id) = filename.strip_suffix(".info") else {
+                continue
+            };
+            let upload = TusUpload::read(self.data_path(id), entry.path());
+            if let Some(upload) = upload.filter(|upload| self.expires(upload).is_some_and(|expires| expires <= now)) {
+                _ = upload.remove();
+            }
+        }
+    }
+
+    /// Gets when an upload expires, or [`None`] if it is complete or uploads don't expire.
+    fn expires(&self, upload: &TusUpload) -> Option<SystemTime> {
+        (self.expiration_seconds > 0 && upload.offset < upload.length)
+            .then(|| upload.modified + Duration::from_secs(self.expiration_seconds))
+    }
+
+    fn insert_expires(&self, http_header: &mut HttpHeader, upload: &TusUpload) {
+        if let Some(expires) = self.expires(upload) {
+            http_header.insert_typed(tus_field("Upload-Expires"), &HttpDate(expires));
+        }
+    }
+
+    fn data_path(&self, id: &str) -> PathBuf {
+        Path::new(&self.staging_directory).join(id)
+    }
+
+    fn information_path(&self, id: &str) -> PathBuf {
+        Path::new(&self.staging_directory).join(format!("{id}.info"))
+    }
+}
+
+impl TusUpload {
+    /// Reads an upload from its data file and the information file written by [`TusUpload::save`].
+    fn read(data_path: PathBuf, information_path: PathBuf) -> Option<Self> {
+        let information = fs::read_to_string(&information_path).ok()?;
+        let data_metadata = fs::metadata(&data_path).ok()?;
+        let mut length = None;
+        let mut metadata = String::new();
+        for line in information.lines() {
+            match line.split_once(": ") {
+                Some(("length", value)) => length = value.parse::<u64>().ok(),
+                Some(("metadata", value)) => metadata = value.to_owned(),
+                _ => (),
+            }
+        }
+        Some(TusUpload {
+            data_path,
+            information_path,
+            length: length?,
+            metadata,
+            offset: data_metadata.len(),
+            modified: data_metadata.modified().ok()?,
+        })
+    }
+
+    /// Creates the upload's empty data file and writes its information file.
+    fn save(&self) -> io::Result<()> {
+        if let Some(staging_directory) = self.data_path.parent() {
+            fs::create_dir_all(staging_directory)?;
+        }
+        fs::File::create_new(&self.data_path)?;
+        let information = format!("length: {}\nmetadata: {}\n", self.length, self.metadata);
+        let result = file::write_atomically(&self.information_path, information.as_bytes());
+        if result.is_err() {
+            _ = fs::remove_file(&self.data_path);
+        }
+        result
+    }
+
+    /// Appends `bytes` to the data file, returning the upload with its new offset.
+    fn append(self, bytes: &[u8]) -> io::Result<Self> {
+        let mut data_file = OpenOptions::new().append(true).open(&self.data_path)?;
+        let result = data_file.write_all(bytes).and_then(|_| data_file.sync_data());
+        if result.is_err() {
+            // Drops a partly written chunk, so the offset still matches what the client sent
+            _ = data_file.set_len(self.offset);
+        }
+        result?;
+        Ok(TusUpload {
+            offset: self.offset + bytes.len() as u64,
+            modified: SystemTime::now(),
+            ..self
+        })
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        fs::remove_file(&self.information_path)?;
+        fs::remove_file(&self.data_path)
+    }
+}
+
+/// Returns `true` if `metadata` is a comma-separated list of keys, each followed by an optional
+/// space and base64-encoded value.
+fn is_valid_metadata(metadata: &str) -> bool {
+    if metadata.is_empty() {
+        return true
+    }
+    metadata.split(',').all(|pair| {
+        let pair = pair.trim();
+        let (key, value) = pair.split_once(' ').unwrap_or((pair, ""));
+        !key.is_empty() && !value.contains(' ') && base64::decode(value).is_some()
+    })
+}
+
+fn has_offset_content_type(header: &HttpHeader) -> bool {
+    header.content_type().is_some_and(|content_type| content_type.is("application", "offset+octet-stream"))
+}
+
+/// Gets the name of a tus field, none of which are registered.
+fn tus_field(field_name: &str) -> HttpFieldName {
+    HttpFieldName::Other(field_name.to_owned())
+}
+
+fn response(http_request: &HttpRequest, status_code: HttpStatusCode, http_header: HttpHeader) -> HttpResponse {
+    let http_version = http_request.version.as_ref().expect("`http_request.version` should be `Some`");
+    HttpResponse::new(http_version, &status_code, &Some(&http_header), &None)
+}
+
+fn failure(http_request: &HttpRequest, status_code: HttpStatusCode, message: &str) -> (HttpResponse, Box<dyn Error>) {
+    (response(http_request, status_code, HttpHeader::new()), Box::new(io::Error::other(message.to_owned())))
+}
+
+fn io_failure(http_request: &HttpRequest, error: io::Error) -> (HttpResponse, Box<dyn Error>) {
+    (response(http_request, HttpStatusCode::from_io_error(&error), HttpHeader::new()), Box::new(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::parse;
+
+    /// A [`Tus`] endpoint at `/files/` with its own staging directory, which is removed when it is dropped.
+    struct TestEndpoint(Tus);
+
+    impl TestEndpoint {
+        fn new() -> Self {
+            let staging_directory = std::env::temp_dir().join(format!("tus-test-{}", random::uuid()));
+            TestEndpoint(Tus { staging_directory: staging_directory.to_string_lossy().into_owned(), maximum_size_bytes: 100, ..Default::default() })
+        }
+
+        /// Sends a tus request with the `fields`, each ending with CRLF, and `body`.
+        fn respond(&self, request_line: &str, fields: &str, body: &[u8]) -> HttpResponse {
+            let mut request_bytes = format!("{request_line} HTTP/1.1\r\nHost: example.com\r\nTus-Resumable: 1.0.0\r\n{fields}Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+            request_bytes.extend_from_slice(body);
+            let allowed_methods = [HttpMethod::Head, HttpMethod::Options, HttpMethod::Post, HttpMethod::Patch, HttpMethod::Delete];
+            self.0.respond(&parse(&request_bytes), &allowed_methods).unwrap_or_else(|(http_response, _)| http_response)
+        }
+
+        /// Creates an upload of `length` bytes, returning its path.
+        fn create(&self, length: u64) -> String {
+            let http_response = self.respond("POST /files/", &format!("Upload-Length: {length}\r\n"), b"");
+            assert_eq!(http_response.status_code, HttpStatusCode::Created201);
+            http_response.header.as_ref().and_then(|header| header.get_value(&HttpFieldName::Location)).cloned().expect("`Location` should be sent")
+        }
+
+        fn patch(&self, path: &str, offset: u64, body: &[u8]) -> HttpResponse {
+            let fields = format!("Upload-Offset: {offset}\r\nContent-Type: application/offset+octet-stream\r\n");
+            self.respond(&format!("PATCH {path}"), &fields, body)
+        }
+    }
+
+    impl Drop for TestEndpoint {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.0.staging_directory);
+        }
+    }
+
+    fn field(http_response: &HttpResponse, field_name: &str) -> Option<String> {
+        http_response.header.as_ref().and_then(|header| header.get_value(&tus_field(field_name))).cloned()
+    }
+
+    #[test]
+    fn appends_chunks_at_offset() {
+        let endpoint = TestEndpoint::new();
+        let path = endpoint.create(10);
+        let http_response = endpoint.patch(&path, 0, b"hello");
+        assert_eq!(http_response.status_code, HttpStatusCode::NoContent204);
+        assert_eq!(field(&http_response, "Upload-Offset").as_deref(), Some("5"));
+
+        let http_response = endpoint.respond(&format!("HEAD {path}"), "", b"");
+        assert_eq!(http_response.status_code, HttpStatusCode::OK200);
+        assert_eq!(field(&http_response, "Upload-Offset").as_deref(), Some("5"));
+        assert_eq!(field(&http_response, "Upload-Length").as_deref(), Some("10"));
+
+        assert_eq!(endpoint.patch(&path, 5, b"world").status_code, HttpStatusCode::NoContent204);
+        let id = path.strip_prefix("/files/").expect("upload should be under the location");
+        assert_eq!(fs::read(endpoint.0.data_path(id)).ok().as_deref(), Some(&b"helloworld"[..]));
+    }
+
+    #[test]
+    fn rejects_chunk_at_wrong_offset() {
+        let endpoint = TestEndpoint::new();
+        let path = endpoint.create(10);
+        assert_eq!(endpoint.patch(&path, 3, b"hello").status_code, HttpStatusCode::Conflict409);
+        assert_eq!(endpoint.patch(&path, 0, b"hello").status_code, HttpStatusCode::NoContent204);
+        assert_eq!(endpoint.patch(&path, 0, b"hello").status_code, HttpStatusCode::Conflict409);
+    }
+
+    #[test]
+    fn rejects_data_past_upload_length() {
+        let endpoint = TestEndpoint::new();
+        let path = endpoint.create(4);
+        assert_eq!(endpoint.patch(&path, 0, b"hello").status_code, HttpStatusCode::ContentTooLarge413);
+        let http_response = endpoint.respond(&format!("HEAD {path}"), "", b"");
+        assert_eq!(field(&http_response, "Upload-Offset").as_deref(), Some("0"));
+
+        let http_response = endpoint.respond("POST /files/", "Upload-Length: 101\r\n", b"");
+        assert_eq!(http_response.status_code, HttpStatusCode::ContentTooLarge413);
+        let fields = "Upload-Length: 4\r\nContent-Type: application/offset+octet-stream\r\n";
+        assert_eq!(endpoint.respond("POST /files/", fields, b"hello").status_code, HttpStatusCode::ContentTooLarge413);
+    }
+
+    #[test]
+    fn terminates_upload() {
+        let endpoint = TestEndpoint::new();
+        let path = endpoint.create(10);
+        assert_eq!(endpoint.respond(&format!("DELETE {path}"), "", b"").status_code, HttpStatusCode::NoContent204);
+        assert_eq!(endpoint.respond(&format!("HEAD {path}"), "", b"").status_code, HttpStatusCode::NotFound404);
+        assert_eq!(endpoint.respond(&format!("DELETE {path}"), "", b"").status_code, HttpStatusCode::NotFound404);
+        assert_eq!(endpoint.respond("DELETE /files/not-an-upload", "", b"").status_code, HttpStatusCode::NotFound404);
+    }
+
+    #[test]
+    fn removes_expired_uploads() {
+        let endpoint = TestEndpoint::new();
+        let (stale_path, fresh_path, complete_path) = (endpoint.create(10), endpoint.create(10), endpoint.create(2));
+        assert_eq!(endpoint.patch(&complete_path, 0, b"ok").status_code, HttpStatusCode::NoContent204);
+        let stale = SystemTime::now() - Duration::from_secs(2 * endpoint.0.expiration_seconds);
+        for path in [&stale_path, &complete_path] {
+            let id = path.strip_prefix("/files/").expect("upload should be under the location");
+            let data_file = OpenOptions::new().append(true).open(endpoint.0.data_path(id)).expect("data file should exist");
+            data_file.set_modified(stale).expect("modification time should be set");
+        }
+
+        // The staging directory was checked by the first request, so the check is forgotten to run it again
+        EXPIRY_CHECKS.lock().unwrap_or_else(|error| error.into_inner()).retain(|(staging_directory, _)| *staging_directory != endpoint.0.staging_directory);
+        endpoint.0.remove_expired();
+        assert_eq!(endpoint.respond(&format!("HEAD {stale_path}"), "", b"").status_code, HttpStatusCode::NotFound404);
+        assert_eq!(endpoint.respond(&format!("HEAD {fresh_path}"), "", b"").status_code, HttpStatusCode::OK200);
+        assert_eq!(endpoint.respond(&format!("HEAD {complete_path}"), "", b"").status_code, HttpStatusCode::OK200);
+    }
+}
@@ -0,0 +1,148 @@
+use std::{error::Error, fs, io, path::{Path, PathBuf}};
+
+use crate::{helper::escape, http_parser::{HttpContentType, HttpFieldName, HttpForm, HttpFormLimits, HttpHeader, HttpRequest, HttpResponse, HttpStatusCode}};
+
+/// A handler that saves the files in a `multipart/form-data` form posted to a path of a
+/// [`super::VirtualHost`] to a directory.
+///
+/// Only the last segment of each filename is used, and a leading `.` is removed, so files can't
+/// be saved outside the directory or hidden. The response lists the files that were saved as JSON.
+#[derive(Clone, Debug)]
+pub struct UploadDirectory {
+    /// The path the form is posted to, such as `/upload`.
+    pub location: String,
+    /// The full path of the directory files are saved to.
+    pub directory: String,
+    pub limits: HttpFormLimits,
+    /// Replaces a file with the same name. Otherwise, a number is added to the name of the new
+    /// file, such as `photo-1.jpg`.
+    pub overwrite: bool,
+}
+
+impl UploadDirectory {
+    /// Returns `true` if the decoded `path` is the location, with or without a trailing `/`.
+    pub fn serves(&self, path: &str) -> bool {
+        let directory_delimiter = '/';
+        path.trim_end_matches(directory_delimiter) == self.location.trim_end_matches(directory_delimiter)
+    }
+
+    /// Saves the files posted in `http_request`.
+    pub fn respond(&self, http_request: &HttpRequest) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        let http_version = http_request.version.as_ref().expect("`http_request.version` should be `Some`");
+        let failure = |status_code: HttpStatusCode, message: &str| {
+            let error: Box<dyn Error> = Box::new(io::Error::other(message.to_owned()));
+            (HttpResponse::new(http_version, &status_code, &None, &None), error)
+        };
+        let form = HttpForm::from_request(http_request, &self.limits).map_err(|status_code| failure(status_code, "form couldn't be parsed"))?;
+        // Browsers send an empty file without a filename for a file input that nothing was chosen for
+        let files: Vec<_> = form.files.into_iter().filter(|file| !(file.filename.is_empty() && file.size == 0)).collect();
+        if files.is_empty() {
+            return Err(failure(HttpStatusCode::BadRequest400, "form has no files"))
+        }
+        fs::create_dir_all(&self.directory).map_err(|error| failure(HttpStatusCode::from_io_error(&error), &error.to_string()))?;
+
+        let mut saved_files = Vec::new();
+        for file in files {
+            let filename = safe_filename(&file.filename).ok_or_else(|| failure(HttpStatusCode::BadRequest400, "filename isn't valid"))?;
+            let path = self.available_path(&filename);
+            let filename = path.file_name().map(|filename| filename.to_string_lossy().into_owned()).unwrap_or(filename);
+            let field_name = file.field_name.clone();
+            let size = file.size;
+            file.persist(&path).map_err(|error| failure(HttpStatusCode::from_io_error(&error), &error.to_string()))?;
+            saved_files.push(format!(
+                "{{\"field\":{},\"filename\":{},\"size\":{size}}}",
+                escape::json_string(&field_name),
+                escape::json_string(&filename),
+            ));
+        }
+
+        let body = format!("{{\"files\":[{}]}}\n", saved_files.join(","));
+        let mut http_header = HttpHeader::new();
+        http_header.insert_typed(HttpFieldName::ContentType, &HttpContentType::new("application", "json"));
+        Ok(HttpResponse::new(http_version, &HttpStatusCode::Created201, &Some(&http_header), &Some(body.as_bytes())))
+    }
+
+    /// Gets the path to save `filename` to, adding a number to it if it exists and files aren't overwritten.
+    fn available_path(&self, filename: &str) -> PathBuf {
+        let directory = Path::new(&self.directory);
+        let path = directory.join(filename);
+        if self.overwrite || !path.exists() {
+            return path
+        }
+        let extension_delimiter = '.';
+        let (stem, extension) = match filename.rsplit_once(extension_delimiter) {
+            None => (filename, String::new()),
+            Some((stem, extension)) => (stem, format!("{extension_delimiter}{extension}")),
+        };
+        (1..)
+            .map(|number| directory.join(format!("{stem}-{number}{extension}")))
+            .find(|path| !path.exists())
+            .expect("a numbered filename should be available")
+    }
+}
+
+/// Gets the last segment of a filename given by a client, without leading `.`s, or [`None`] if nothing is left.
+fn safe_filename(filename: &str) -> Option<String> {
+    let filename = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let filename = filename.trim().trim_start_matches('.');
+    match filename.is_empty() || filename.chars().any(|character| character.is_control()) {
+        false => Some(filename.to_owned()),
+        true => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::file::TestDirectory;
+
+    fn upload_directory(directory: &TestDirectory, overwrite: bool) -> UploadDirectory {
+        UploadDirectory {
+            location: "/upload".to_owned(),
+            directory: directory.0.to_string_lossy().into_owned(),
+            limits: HttpFormLimits::default(),
+            overwrite,
+        }
+    }
+
+    #[test]
+    fn keeps_last_segment_of_filename() {
+        assert_eq!(safe_filename("photo.jpg").as_deref(), Some("photo.jpg"));
+        assert_eq!(safe_filename("../../etc/passwd").as_deref(), Some("passwd"));
+        assert_eq!(safe_filename("C:\\x\\y").as_deref(), Some("y"));
+        assert_eq!(safe_filename("/a/b\\c/ d.txt ").as_deref(), Some("d.txt"));
+        assert_eq!(safe_filename("../").as_deref(), None);
+    }
+
+    #[test]
+    fn rejects_hidden_and_control_characters() {
+        assert_eq!(safe_filename(".htaccess").as_deref(), Some("htaccess"));
+        assert_eq!(safe_filename("...").as_deref(), None);
+        assert_eq!(safe_filename("").as_deref(), None);
+        assert_eq!(safe_filename("a\nb.txt").as_deref(), None);
+        assert_eq!(safe_filename("a\0b.txt").as_deref(), None);
+        assert_eq!(safe_filename("a\u{7f}b.txt").as_deref(), None);
+    }
+
+    #[test]
+    fn numbers_existing_files() {
+        let directory = TestDirectory::new();
+        let upload_directory = upload_directory(&directory, false);
+        assert_eq!(upload_directory.available_path("photo.jpg"), directory.0.join("photo.jpg"));
+        directory.write("photo.jpg", "");
+        assert_eq!(upload_directory.available_path("photo.jpg"), directory.0.join("photo-1.jpg"));
+        directory.write("photo-1.jpg", "");
+        assert_eq!(upload_directory.available_path("photo.jpg"), directory.0.join("photo-2.jpg"));
+        directory.write("notes", "");
+        assert_eq!(upload_directory.available_path("notes"), directory.0.join("notes-1"));
+        directory.write("archive.tar.gz", "");
+        assert_eq!(upload_directory.available_path("archive.tar.gz"), directory.0.join("archive.tar-1.gz"));
+    }
+
+    #[test]
+    fn overwrites_existing_files() {
+        let directory = TestDirectory::new();
+        directory.write("photo.jpg", "");
+        assert_eq!(upload_directory(&directory, true).available_path("photo.jpg"), directory.0.join("photo.jpg"));
+    }
+}
@@ -0,0 +1,246 @@
+use std::{collections::HashMap, net::IpAddr, path::Path};
+
+use crate::http_parser::{HttpHeader, HttpHost, HttpMethod, HttpRequest, HttpStatusCode};
+
+use super::{CanonicalRedirect, Cgi, Config, RewriteRule, SubdomainMapping, TryFiles, Tus, UnknownHostPolicy, UploadDirectory, WebDav};
+
+/// The settings for one site, which is selected by the host a request is for.
+///
+/// Paths are relative to the [`Config`]'s `top_directory`. Subdomains of the `host_patterns`
+/// use the same block, but are served from the directory given by the `subdomain_mapping`.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualHost {
+    /// The name used to refer to the block, such as in [`UnknownHostPolicy::DefaultServer`].
+    pub name: String,
+    /// The domain name patterns the block serves, as described by [`HttpHost::strip_domain`].
+    pub host_patterns: Vec<String>,
+    pub document_root: String,
+    pub subdomain_mapping: SubdomainMapping,
+    /// The directory for subdomains that the `subdomain_mapping` doesn't give an existing directory for.
+    /// If it is [`None`], those subdomains are served from the directory they are mapped to anyway,
+    /// or aren't served by the block if they aren't mapped.
+    pub subdomain_catch_all_directory: Option<String>,
+    /// Subdomains that share another subdomain's directory, such as `www` sharing `""`, the domain itself.
+    pub subdomain_aliases: HashMap<String, String>,
+    /// The files to look for, in order, when a directory is requested.
+    pub index_filenames: Vec<String>,
+    /// The files to try for paths under each location, instead of only the path itself.
+    pub try_files: Vec<TryFiles>,
+    /// Lists the contents of a requested directory that has none of the `index_filenames`,
+    /// instead of responding with [`HttpStatusCode::NotFound404`].
+    pub directory_listing: bool,
+    /// Serves a location with WebDAV, so its content can be managed remotely.
+    pub webdav: Option<WebDav>,
+    /// Accepts resumable uploads under a location with the tus protocol.
+    pub tus: Option<Tus>,
+    /// Saves files posted in a form to a directory.
+    pub upload_directory: Option<UploadDirectory>,
+    /// Runs scripts in the document root with CGI.
+    pub cgi: Option<Cgi>,
+    /// The error page template for each error status code, relative to the document root.
+    /// The templates are described by [`super::ErrorPage`].
+    pub error_pages: HashMap<HttpStatusCode, String>,
+    /// The error page template for each class of status codes without its own error page,
+    /// keyed by the first digit, so `4` is every 4xx status code.
+    pub class_error_pages: HashMap<u16, String>,
+    /// Fields added to every response.
+    pub response_header: HttpHeader,
+    /// The methods that are allowed. If it is empty, every method is allowed.
+    pub allowed_methods: Vec<HttpMethod>,
+    /// The methods allowed for paths under each location, instead of the `allowed_methods`.
+    /// The longest location that matches is used.
+    pub location_allowed_methods: HashMap<String, Vec<HttpMethod>>,
+    /// Echoes `TRACE` requests back to every client. This is disabled by default, as the echo
+    /// can reveal fields added by proxies.
+    pub trace_enabled: bool,
+    /// The client addresses that `TRACE` requests are echoed back to, even if `trace_enabled` is `false`.
+    pub trace_allowed_addresses: Vec<IpAddr>,
+    pub canonical_redirect: CanonicalRedirect,
+    /// The rules applied, in order, after the canonical redirects.
+    pub rewrite_rules: Vec<RewriteRule>,
+    /// The maximum size of a request's body in kilobytes. `0` means there is no limit beyond
+    /// the `request_maximum_buffer_size_kilobytes`.
+    pub maximum_body_size_kilobytes: usize,
+}
+
+/// The [`VirtualHost`] selected for a request, and the directory the request is served from.
+#[derive(Clone, Debug)]
+pub struct VirtualHostMatch<'a> {
+    pub virtual_host: &'a VirtualHost,
+    /// The labels before the matched domain name, or `""` for the domain itself.
+    pub subdomain: String,
+    /// The full path of the directory the request is served from.
+    pub document_root: String,
+}
+
+impl VirtualHost {
+    /// Gets the methods in `implemented` that are allowed for `path`, in the same order, or for the
+    /// whole block if `path` is [`None`].
+    ///
+    /// `HEAD` is allowed wherever `GET` is.
+    pub fn allowed_methods_for(&self, path: Option<&str>, implemented: &[HttpMethod]) -> Vec<HttpMethod> {
+        let allowed_methods = path
+            .and_then(|path| self.location_allowed_methods.iter()
+                .filter(|(location, _)| path.starts_with(location.as_str()))
+                .max_by_key(|(location, _)| location.len()))
+            .map_or(&self.allowed_methods, |(_, allowed_methods)| allowed_methods);
+        implemented.iter()
+            .filter(|method| {
+                allowed_methods.is_empty()
+                    || allowed_methods.contains(method)
+                    || (**method == HttpMethod::Head && allowed_methods.contains(&HttpMethod::Get))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns `true` if `TRACE` requests from `client_address` are echoed back.
+    pub fn allows_trace(&self, client_address: Option<IpAddr>) -> bool {
+        self.trace_enabled || client_address.is_some_and(|client_address| self.trace_allowed_addresses.contains(&client_address))
+    }
+
+    /// Gets the error page for `status_code`, or for its class if it has none of its own.
+    pub fn error_page(&self, status_code: &HttpStatusCode) -> Option<&String> {
+        self.error_pages.get(status_code).or_else(|| self.class_error_pages.get(&(status_code.as_u16() / 100)))
+    }
+
+    /// Returns `true` if a body of `size` bytes exceeds the `maximum_body_size_kilobytes`.
+    pub fn body_too_large(&self, size: usize) -> bool {
+        self.maximum_body_size_kilobytes > 0 && size > 1024 * self.maximum_body_size_kilobytes
+    }
+
+    /// Gets the full path of the directory a subdomain of the block is served from, or [`None`]
+    /// if the subdomain isn't served by the block.
+    fn document_root_for(&self, config: &Config, subdomain: &str) -> Option<String> {
+        let directory_delimiter = '/';
+        let full_path = |directory: &str| format!("{}{directory_delimiter}{directory}", config.top_directory);
+        let subdomain = self.subdomain_aliases.get(subdomain).map_or(subdomain, |alias| alias.as_str());
+        if subdomain.is_empty() || matches!(self.subdomain_mapping, SubdomainMapping::DocumentRoot) {
+            return Some(full_path(&self.document_root))
+        }
+        let directory = self.subdomain_mapping.directory_for(subdomain);
+        match (directory, &self.subdomain_catch_all_directory) {
+            (Some(directory), _) if Path::new(&full_path(&directory)).is_dir() => Some(full_path(&directory)),
+            (_, Some(catch_all_directory)) => Some(full_path(catch_all_directory)),
+            (directory, None) => directory.map(|directory| full_path(&directory)),
+        }
+    }
+}
+
+impl<'a> VirtualHostMatch<'a> {
+    /// Selects the virtual host for `http_request`.
+    ///
+    /// The most specific match wins: the host itself, then a subdomain of a pattern, then `*`, with
+    /// shorter subdomains before longer ones. So a block for `www.example.com` is used instead of a
+    /// block for `example.com`, and a `*` block is only used for hosts no other block matches. Equally
+    /// specific matches are won by the block listed first. A request without a host uses the default
+    /// server. A host that isn't valid gets [`HttpStatusCode::BadRequest400`], and a host that isn't
+    /// matched is handled by the `unknown_host_policy`.
+    pub fn select(config: &'a Config, http_request: &HttpRequest) -> Result<Self, HttpStatusCode> {
+        let host = match http_request.host() {
+            None => return Self::default_server(config).ok_or(HttpStatusCode::BadRequest400),
+            Some(host) => host.parse::<HttpHost>().map_err(|_| HttpStatusCode::BadRequest400)?,
+        };
+        let best_match = config.virtual_hosts.iter()
+            .filter_map(|virtual_host| {
+                let (rank, subdomain) = virtual_host.host_patterns.iter()
+                    .filter_map(|pattern| host.strip_domain(pattern).map(|subdomain| (match_rank(pattern, subdomain), subdomain)))
+                    .min_by_key(|(rank, _)| *rank)?;
+                let document_root = virtual_host.document_root_for(config, subdomain)?;
+                Some((rank, VirtualHostMatch { virtual_host, subdomain: subdomain.to_owned(), document_root }))
+            })
+            .min_by_key(|(rank, _)| *rank);
+        match best_match {
+            Some((_, virtual_host_match)) => Ok(virtual_host_match),
+            None => match &config.unknown_host_policy {
+                UnknownHostPolicy::DefaultServer(_) => Self::default_server(config).ok_or(HttpStatusCode::MisdirectedRequest421),
+                UnknownHostPolicy::MisdirectedRequest => Err(HttpStatusCode::MisdirectedRequest421),
+                UnknownHostPolicy::BadRequest => Err(HttpStatusCode::BadRequest400),
+            },
+        }
+    }
+
+    /// Gets the block named by [`UnknownHostPolicy::DefaultServer`], or the first block if there isn't one.
+    fn default_server(config: &'a Config) -> Option<Self> {
+        let virtual_host = match &config.unknown_host_policy {
+            UnknownHostPolicy::DefaultServer(name) => config.virtual_hosts.iter().find(|virtual_host| virtual_host.name == *name),
+            _ => config.virtual_hosts.first(),
+        }?;
+        Some(VirtualHostMatch {
+            document_root: virtual_host.document_root_for(config, "")?,
+            virtual_host,
+            subdomain: String::new(),
+        })
+    }
+}
+
+/// Ranks a match of a host pattern that gave `subdomain`, as described by [`VirtualHostMatch::select`],
+/// where lower ranks are more specific.
+fn match_rank(pattern: &str, subdomain: &str) -> (u8, usize) {
+    let wildcard = "*";
+    let kind = match (pattern == wildcard, subdomain.is_empty()) {
+        (true, _) => 2,
+        (false, true) => 0,
+        (false, false) => 1,
+    };
+    (kind, subdomain.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::parse;
+
+    fn config(host_patterns: &[&[&str]]) -> Config {
+        crate::server::tests::config(host_patterns.iter().enumerate().map(|(index, host_patterns)| VirtualHost {
+            name: format!("block-{index}"),
+            host_patterns: host_patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            document_root: format!("root-{index}"),
+            ..Default::default()
+        }).collect())
+    }
+
+    /// Gets the name of the block selected for `host`, and the subdomain it was matched with.
+    fn select(config: &Config, host: &str) -> Result<(String, String), HttpStatusCode> {
+        let request_bytes = format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n");
+        VirtualHostMatch::select(config, &parse(request_bytes.as_bytes()))
+            .map(|virtual_host_match| (virtual_host_match.virtual_host.name.clone(), virtual_host_match.subdomain))
+    }
+
+    #[test]
+    fn prefers_exact_host_to_catch_all() {
+        let config = config(&[&["*"], &["*.example.com"], &["example.com"]]);
+        assert_eq!(select(&config, "example.com"), Ok(("block-2".to_owned(), String::new())));
+        assert_eq!(select(&config, "shop.example.com"), Ok(("block-1".to_owned(), "shop".to_owned())));
+        assert_eq!(select(&config, "example.org"), Ok(("block-0".to_owned(), String::new())));
+    }
+
+    #[test]
+    fn prefers_shortest_subdomain() {
+        let config = config(&[&["example.com"], &["shop.example.com"], &["uk.shop.example.com"]]);
+        assert_eq!(select(&config, "uk.shop.example.com"), Ok(("block-2".to_owned(), String::new())));
+        assert_eq!(select(&config, "de.shop.example.com"), Ok(("block-1".to_owned(), "de".to_owned())));
+        assert_eq!(select(&config, "blog.example.com"), Ok(("block-0".to_owned(), "blog".to_owned())));
+    }
+
+    #[test]
+    fn ranks_patterns_within_a_block() {
+        let config = config(&[&["*", "example.com"]]);
+        assert_eq!(select(&config, "shop.example.com"), Ok(("block-0".to_owned(), "shop".to_owned())));
+    }
+
+    #[test]
+    fn prefers_first_of_equal_matches() {
+        let config = config(&[&["*.example.com"], &["example.com"]]);
+        assert_eq!(select(&config, "shop.example.com"), Ok(("block-0".to_owned(), "shop".to_owned())));
+        assert_eq!(select(&config, "example.com"), Ok(("block-1".to_owned(), String::new())));
+    }
+
+    #[test]
+    fn applies_unknown_host_policy() {
+        let mut config = config(&[&["example.com"]]);
+        assert_eq!(select(&config, "example.org"), Err(HttpStatusCode::MisdirectedRequest421));
+        config.unknown_host_policy = UnknownHostPolicy::DefaultServer("block-0".to_owned());
+        assert_eq!(select(&config, "example.org"), Ok(("block-0".to_owned(), String::new())));
+    }
+}
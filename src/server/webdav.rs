@@ -0,0 +1,840 @@
+use std::{collections::HashMap, error::Error, fs, io, path::{Path, PathBuf}, sync::Mutex, time::Duration};
+
+use crate::{helper::{escape, file, percent, xml::XmlElement}, http_parser::{HttpContentType, HttpDate, HttpFieldName, HttpHeader, HttpHost, HttpMethod, HttpRequest, HttpResponse, HttpStatusCode, HttpTarget, HttpUri}};
+
+use super::{basic_authentication, DirectoryListing, VirtualHostMatch, WebDavLock};
+
+const DAV_NAMESPACE: &str = "DAV:";
+
+/// The methods that are only allowed under a [`WebDav`] location.
+pub const WEBDAV_METHODS: [HttpMethod; 9] = [
+    HttpMethod::Put,
+    HttpMethod::Delete,
+    HttpMethod::Mkcol,
+    HttpMethod::Copy,
+    HttpMethod::Move,
+    HttpMethod::Propfind,
+    HttpMethod::Proppatch,
+    HttpMethod::Lock,
+    HttpMethod::Unlock,
+];
+
+/// The properties in the `DAV:` namespace that are computed, so they can't be changed with `PROPPATCH`.
+const LIVE_PROPERTIES: [&str; 6] = ["resourcetype", "getcontentlength", "getlastmodified", "getetag", "supportedlock", "lockdiscovery"];
+
+/// The properties set with `PROPPATCH`, which are kept in memory, so they are lost when the server stops.
+static DEAD_PROPERTIES: Mutex<Vec<DeadProperty>> = Mutex::new(Vec::new());
+
+/// The most dead properties that are kept for each virtual host.
+const MAXIMUM_DEAD_PROPERTIES: usize = 10_000;
+
+/// A WebDAV (class 1 and 2) handler for the paths under a location of a [`super::VirtualHost`],
+/// so its content can be managed by DAV clients.
+///
+/// The location is mapped to the document root. Every request must have `Basic` credentials for
+/// one of the `users`, so the handler should only be used over HTTPS. Files are written to a
+/// temporary file which is then renamed, and paths that lead outside the document root, such as
+/// through a symbolic link, are refused.
+#[derive(Clone, Debug)]
+pub struct WebDav {
+    /// The path prefix the handler serves, ending with `/`, such as `/dav/`.
+    pub location: String,
+    /// The passwords of the users that are allowed, by user ID. If it is empty, every request is refused.
+    pub users: HashMap<String, String>,
+    /// The realm sent in the `WWW-Authenticate` field.
+    pub realm: String,
+    /// The longest time a lock is held for without being refreshed. `0` means locks can be held forever.
+    pub maximum_lock_timeout_seconds: u64,
+}
+
+/// A property set on a resource with `PROPPATCH`.
+#[derive(Clone, Debug)]
+struct DeadProperty {
+    /// The name of the [`super::VirtualHost`] the resource is served by.
+    virtual_host: String,
+    /// The full path of the resource.
+    path: PathBuf,
+    property: XmlElement,
+}
+
+/// Which properties a `PROPFIND` request asks for.
+enum PropertyRequest {
+    All,
+    Names,
+    Properties(Vec<(String, String)>),
+}
+
+/// A resource under the [`WebDav`] location.
+struct Resource {
+    /// The full path of the file or directory.
+    path: PathBuf,
+    /// The percent-encoded path the resource is requested with.
+    href: String,
+    /// Whether the resource is the location itself.
+    is_root: bool,
+    /// The name of the [`super::VirtualHost`] the resource is served by.
+    virtual_host: String,
+}
+
+impl Default for WebDav {
+    fn default() -> Self {
+        WebDav {
+            location: "/".to_owned(),
+            users: HashMap::new(),
+            realm: "WebDAV".to_owned(),
+            maximum_lock_timeout_seconds: 3600,
+        }
+    }
+}
+
+impl WebDav {
+    /// Returns `true` if the decoded `path` is under the location.
+    pub fn serves(&self, path: &str) -> bool {
+        let directory_delimiter = '/';
+        path.starts_with(self.location.as_str()) || self.location.strip_suffix(directory_delimiter) == Some(path)
+    }
+
+    /// Returns `true` if `method` is handled by WebDAV. Other methods, such as `TRACE`, are handled
+    /// in the same way as outside the location.
+    pub fn handles(method: &HttpMethod) -> bool {
+        matches!(method, HttpMethod::Options | HttpMethod::Get | HttpMethod::Head) || WEBDAV_METHODS.contains(method)
+    }
+
+    /// Responds to a request for a path under the location, where `allowed_methods` are the methods
+    /// allowed for it.
+    pub fn respond(&self, site: &VirtualHostMatch, http_request: &HttpRequest, allowed_methods: &[HttpMethod]) -> Result<HttpResponse, (HttpResponse, Box<dyn Error>)> {
+        self.respond_to(site, http_request, allowed_methods).map_err(|(status_code, error)| {
+            let http_version = http_request.version.as_ref().expect("`http_request.version` should be `Some`");
+            let mut http_response = match status_code {
+                // The method is refused for this resource, such as `MKCOL` for one that exists
+                HttpStatusCode::MethodNotAllowed405 => {
+                    let allowed_methods: Vec<HttpMethod> = allowed_methods.iter().filter(|method| http_request.method.as_ref() != Some(*method)).cloned().collect();
+                    super::method_not_allowed_response(http_version, &allowed_methods)
+                },
+                status_code => HttpResponse::new(http_version, &status_code, &None, &None),
+            };
+            if status_code == HttpStatusCode::Unauthorized401 {
+                http_response.header.get_or_insert_with(HttpHeader::new).insert(HttpFieldName::WwwAuthenticate, &basic_authentication::challenge(&self.realm));
+            }
+            (http_response, error)
+        })
+    }
+
+    fn respond_to(&self, site: &VirtualHostMatch, http_request: &HttpRequest, allowed_methods: &[HttpMethod]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        if !basic_authentication::is_authenticated(&self.users, http_request) {
+            return Err(failure(HttpStatusCode::Unauthorized401, "WebDAV request isn't authenticated"))
+        }
+        let target = http_request.target.as_ref().expect("`http_request.target` should be `Some`");
+        let resource = self.resource(site, target)?;
+        let tokens = submitted_tokens(http_request);
+        let header = http_request.header.clone().unwrap_or_default();
+        let body = http_request.body.unwrap_or_default();
+        let method = http_request.method.as_ref().expect("`http_request.method` should be `Some`");
+
+        match method {
+            HttpMethod::Options => {
+                let allowed_methods: Vec<&str> = allowed_methods.iter().map(|method| method.as_str()).collect();
+                let mut http_header = HttpHeader::new();
+                http_header.insert(HttpFieldName::Allow, &allowed_methods.join(", "));
+                http_header.insert(HttpFieldName::Dav, "1, 2");
+                http_header.insert(HttpFieldName::Other("MS-Author-Via".to_owned()), "DAV");
+                Ok(response(http_request, HttpStatusCode::OK200, http_header, None))
+            },
+            HttpMethod::Get | HttpMethod::Head => Self::get(http_request, &resource),
+            HttpMethod::Put => Self::put(http_request, &resource, &tokens, body),
+            HttpMethod::Delete => Self::delete(http_request, &resource, &tokens),
+            HttpMethod::Mkcol => Self::make_collection(http_request, &resource, &tokens, body),
+            HttpMethod::Copy | HttpMethod::Move => self.copy_or_move(site, http_request, &resource, &tokens, &header),
+            HttpMethod::Propfind => Self::find_properties(http_request, &resource, &header, body),
+            HttpMethod::Proppatch => Self::patch_properties(http_request, &resource, &tokens, body),
+            HttpMethod::Lock => self.lock(http_request, &resource, &tokens, &header, body),
+            HttpMethod::Unlock => Self::unlock(http_request, &resource, &header),
+            method => Err(failure(HttpStatusCode::MethodNotAllowed405, &format!("method `{method}` isn't handled by WebDAV"))),
+        }
+    }
+
+    /// Maps `target` to its resource in the document root, refusing paths that lead outside it.
+    fn resource(&self, site: &VirtualHostMatch, target: &HttpTarget) -> Result<Resource, (HttpStatusCode, Box<dyn Error>)> {
+        let directory_delimiter = '/';
+        let decoded_path = target.decoded_path().filter(|path| self.serves(path))
+            .ok_or_else(|| failure(HttpStatusCode::NotFound404, "WebDAV path can't be decoded"))?;
+        let relative_path = decoded_path.strip_prefix(self.location.as_str()).unwrap_or_default();
+        let root = Path::new(&site.document_root);
+        let path = root.join(relative_path.trim_end_matches(directory_delimiter));
+        if !file::is_confined(root, &path) {
+            return Err(failure(HttpStatusCode::Forbidden403, "WebDAV path leads outside the document root"))
+        }
+        Ok(Resource {
+            path,
+            href: target.path.clone().unwrap_or_default(),
+            is_root: relative_path.trim_end_matches(directory_delimiter).is_empty(),
+            virtual_host: site.virtual_host.name.clone(),
+        })
+    }
+
+    fn get(http_request: &HttpRequest, resource: &Resource) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        let metadata = fs::metadata(&resource.path).map_err(io_failure)?;
+        let mut http_header = HttpHeader::new();
+        let body = match metadata.is_dir() {
+            false => {
+                if let Ok(modified) = metadata.modified() {
+                    http_header.insert_typed(HttpFieldName::LastModified, &HttpDate(modified));
+                }
+                http_header.insert(HttpFieldName::ETag, &entity_tag(&metadata));
+                fs::read(&resource.path).map_err(io_failure)?
+            },
+            true => {
+                let path = percent::decode(&resource.href, false).unwrap_or_default();
+                let mut content_type = HttpContentType::new("text", "html");
+                content_type.set_parameter("charset", "utf-8");
+                http_header.insert_typed(HttpFieldName::ContentType, &content_type);
+                DirectoryListing::read(&resource.path, &path).map_err(io_failure)?.to_html().into_bytes()
+            },
+        };
+        Ok(response(http_request, HttpStatusCode::OK200, http_header, Some(body)))
+    }
+
+    fn put(http_request: &HttpRequest, resource: &Resource, tokens: &[String], body: &[u8]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        if resource.path.is_dir() {
+            return Err(failure(HttpStatusCode::MethodNotAllowed405, "a collection can't be replaced with `PUT`"))
+        }
+        let parent = parent_of(&resource.path)?;
+        let existed = resource.path.exists();
+        // Adding a file changes its parent's members, but replacing one doesn't
+        let mut changed_paths = vec!((resource.path.as_path(), false));
+        if !existed {
+            changed_paths.push((parent, false));
+        }
+        if !WebDavLock::may_change(&changed_paths, tokens) {
+            return Err(failure(HttpStatusCode::Locked423, "resource is locked"))
+        }
+        file::write_atomically(&resource.path, body).map_err(io_failure)?;
+        Ok(response(http_request, created_or_replaced(existed), HttpHeader::new(), None))
+    }
+
+    fn delete(http_request: &HttpRequest, resource: &Resource, tokens: &[String]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        if resource.is_root {
+            return Err(failure(HttpStatusCode::Forbidden403, "the WebDAV location can't be deleted"))
+        }
+        let metadata = fs::metadata(&resource.path).map_err(io_failure)?;
+        let parent = parent_of(&resource.path)?;
+        if !WebDavLock::may_change(&[(&resource.path, true), (parent, false)], tokens) {
+            return Err(failure(HttpStatusCode::Locked423, "resource is locked"))
+        }
+        remove(&resource.path, metadata.is_dir()).map_err(io_failure)?;
+        WebDavLock::release_all(&resource.path);
+        remove_dead_properties(&resource.virtual_host, &resource.path);
+        Ok(response(http_request, HttpStatusCode::NoContent204, HttpHeader::new(), None))
+    }
+
+    fn make_collection(http_request: &HttpRequest, resource: &Resource, tokens: &[String], body: &[u8]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        if !body.is_empty() {
+            return Err(failure(HttpStatusCode::UnsupportedMediaType415, "`MKCOL` bodies aren't supported"))
+        }
+        if resource.path.exists() {
+            return Err(failure(HttpStatusCode::MethodNotAllowed405, "resource already exists"))
+        }
+        let parent = parent_of(&resource.path)?;
+        if !WebDavLock::may_change(&[(parent, false)], tokens) {
+            return Err(failure(HttpStatusCode::Locked423, "parent collection is locked"))
+        }
+        fs::create_dir(&resource.path).map_err(io_failure)?;
+        Ok(response(http_request, HttpStatusCode::Created201, HttpHeader::new(), None))
+    }
+
+    fn copy_or_move(&self, site: &VirtualHostMatch, http_request: &HttpRequest, resource: &Resource, tokens: &[String], header: &HttpHeader) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        let is_move = http_request.method == Some(HttpMethod::Move);
+        let destination = self.destination(site, http_request, header)?;
+        let metadata = fs::metadata(&resource.path).map_err(io_failure)?;
+        let overwrite = !header.get_value(&HttpFieldName::Overwrite).is_some_and(|overwrite| overwrite.trim().eq_ignore_ascii_case("F"));
+        let depth_infinity = header.get_value(&HttpFieldName::Depth).is_none_or(|depth| depth.trim().eq_ignore_ascii_case("infinity"));
+
+        if destination.path.starts_with(&resource.path) || resource.path.starts_with(&destination.path) || destination.is_root || (is_move && resource.is_root) {
+            return Err(failure(HttpStatusCode::Forbidden403, "source and destination overlap"))
+        }
+        if is_move && metadata.is_dir() && !depth_infinity {
+            return Err(failure(HttpStatusCode::BadRequest400, "a collection can only be moved with infinite depth"))
+        }
+        let destination_parent = parent_of(&destination.path)?;
+        let destination_existed = destination.path.exists();
+        if destination_existed && !overwrite {
+            return Err(failure(HttpStatusCode::PreconditionFailed412, "destination exists"))
+        }
+        let mut changed_paths = vec!((destination.path.as_path(), true), (destination_parent, false));
+        let source_parent = parent_of(&resource.path)?;
+        if is_move {
+            changed_paths.extend([(resource.path.as_path(), true), (source_parent, false)]);
+        }
+        if !WebDavLock::may_change(&changed_paths, tokens) {
+            return Err(failure(HttpStatusCode::Locked423, "resource is locked"))
+        }
+
+        // A file can replace a file atomically, but anything else has to be removed first
+        if destination_existed && (metadata.is_dir() || destination.path.is_dir()) {
+            remove(&destination.path, destination.path.is_dir()).map_err(io_failure)?;
+            WebDavLock::release_all(&destination.path);
+        }
+        // The destination is replaced, so it doesn't keep its own properties
+        if destination_existed {
+            remove_dead_properties(&destination.virtual_host, &destination.path);
+        }
+        match (is_move, metadata.is_dir()) {
+            (true, _) => fs::rename(&resource.path, &destination.path).map_err(io_failure)?,
+            (false, false) => file::copy_atomically(&resource.path, &destination.path).map_err(io_failure)?,
+            (false, true) => copy_directory(&resource.path, &destination.path, depth_infinity).map_err(io_failure)?,
+        }
+        copy_dead_properties(&resource.virtual_host, &resource.path, &destination.path, is_move);
+        if is_move {
+            WebDavLock::release_all(&resource.path);
+        }
+        Ok(response(http_request, created_or_replaced(destination_existed), HttpHeader::new(), None))
+    }
+
+    /// Gets the resource named by the `Destination` field, which must be under the location on the same host.
+    fn destination(&self, site: &VirtualHostMatch, http_request: &HttpRequest, header: &HttpHeader) -> Result<Resource, (HttpStatusCode, Box<dyn Error>)> {
+        let bad_destination = || failure(HttpStatusCode::BadRequest400, "`Destination` isn't valid");
+        let uri = header.get_value(&HttpFieldName::Destination)
+            .and_then(|destination| destination.trim().parse::<HttpUri>().ok())
+            .ok_or_else(bad_destination)?;
+        if let Some(destination_host) = &uri.host {
+            let request_host = http_request.host().and_then(|host| host.parse::<HttpHost>().ok());
+            let destination_host = destination_host.parse::<HttpHost>().ok();
+            if request_host.is_none() || request_host.map(|host| host.name) != destination_host.map(|host| host.name) {
+                return Err(failure(HttpStatusCode::BadGateway502, "`Destination` is on another server"))
+            }
+        }
+        let target = uri.path.parse::<HttpTarget>().map_err(|_| bad_destination())?;
+        if !target.decoded_path().is_some_and(|path| self.serves(&path)) {
+            return Err(failure(HttpStatusCode::BadGateway502, "`Destination` isn't under the WebDAV location"))
+        }
+        self.resource(site, &target)
+    }
+
+    fn find_properties(http_request: &HttpRequest, resource: &Resource, header: &HttpHeader, body: &[u8]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        let directory_delimiter = '/';
+        let depth = header.get_value(&HttpFieldName::Depth).map(|depth| depth.trim());
+        let include_members = match depth {
+            Some("0") => false,
+            Some("1") => true,
+            _ => {
+                let body = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:error xmlns:D=\"DAV:\"><D:propfind-finite-depth/></D:error>";
+                return Ok(xml_response(http_request, HttpStatusCode::Forbidden403, body.to_owned()))
+            },
+        };
+        let property_request = match body.is_empty() {
+            true => PropertyRequest::All,
+            false => {
+                let propfind = parse_body(body).filter(|propfind| propfind.is(DAV_NAMESPACE, "propfind"))
+                    .ok_or_else(|| failure(HttpStatusCode::BadRequest400, "`PROPFIND` body isn't valid"))?;
+                let property_request = match propfind.elements().next() {
+                    Some(element) if element.is(DAV_NAMESPACE, "propname") => PropertyRequest::Names,
+                    Some(element) if element.is(DAV_NAMESPACE, "prop") => {
+                        PropertyRequest::Properties(element.elements().map(|property| (property.namespace.clone(), property.name.clone())).collect())
+                    },
+                    _ => PropertyRequest::All,
+                };
+                property_request
+            },
+        };
+
+        let metadata = fs::metadata(&resource.path).map_err(io_failure)?;
+        let mut resources = vec!((resource.path.clone(), resource.href.clone(), metadata.clone()));
+        if include_members && metadata.is_dir() {
+            let mut href = resource.href.clone();
+            if !href.ends_with(directory_delimiter) {
+                href.push(directory_delimiter);
+            }
+            let mut members = Vec::new();
+            for entry in fs::read_dir(&resource.path).map_err(io_failure)? {
+                let entry = entry.map_err(io_failure)?;
+                let name = match entry.file_name().into_string() {
+                    Ok(name) if !name.starts_with('.') => name,
+                    _ => continue,
+                };
+                let Ok(member_metadata) = fs::metadata(entry.path()) else {
+                    continue
+                };
+                let mut member_href = format!("{href}{}", percent::encode(&name, false, percent::is_path_segment_character));
+                if member_metadata.is_dir() {
+                    member_href.push(directory_delimiter);
+                }
+                members.push((entry.path(), member_href, member_metadata));
+            }
+            members.sort_by(|a, b| a.1.cmp(&b.1));
+            resources.extend(members);
+        }
+
+        let mut body = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">");
+        for (path, href, metadata) in resources {
+            let properties = properties(&resource.virtual_host, &path, &metadata);
+            let (found, missing): (Vec<String>, Vec<String>) = match &property_request {
+                PropertyRequest::All => (properties.into_iter().map(|(_, _, xml)| xml).collect(), Vec::new()),
+                PropertyRequest::Names => (properties.into_iter().map(|(namespace, name, _)| XmlElement::new(&namespace, &name).to_xml()).collect(), Vec::new()),
+                PropertyRequest::Properties(requested) => {
+                    let mut found = Vec::new();
+                    let mut missing = Vec::new();
+                    for (namespace, name) in requested {
+                        match properties.iter().find(|(property_namespace, property_name, _)| property_namespace == namespace && property_name == name) {
+                            None => missing.push(XmlElement::new(namespace, name).to_xml()),
+                            Some((_, _, xml)) => found.push(xml.clone()),
+                        }
+                    }
+                    (found, missing)
+                },
+            };
+            body.push_str(&format!("<D:response><D:href>{}</D:href>", escape::html(&href)));
+            body.push_str(&property_status(&found, HttpStatusCode::OK200));
+            body.push_str(&property_status(&missing, HttpStatusCode::NotFound404));
+            body.push_str("</D:response>");
+        }
+        body.push_str("</D:multistatus>");
+        Ok(xml_response(http_request, HttpStatusCode::MultiStatus207, body))
+    }
+
+    fn patch_properties(http_request: &HttpRequest, resource: &Resource, tokens: &[String], body: &[u8]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        if !resource.path.exists() {
+            return Err(failure(HttpStatusCode::NotFound404, "resource doesn't exist"))
+        }
+        if !WebDavLock::may_change(&[(&resource.path, false)], tokens) {
+            return Err(failure(HttpStatusCode::Locked423, "resource is locked"))
+        }
+        let bad_body = || failure(HttpStatusCode::BadRequest400, "`PROPPATCH` body isn't valid");
+        let property_update = parse_body(body).filter(|element| element.is(DAV_NAMESPACE, "propertyupdate")).ok_or_else(bad_body)?;
+        let mut updates = Vec::new();
+        for instruction in property_update.elements() {
+            let is_set = match (instruction.namespace.as_str(), instruction.name.as_str()) {
+                (DAV_NAMESPACE, "set") => true,
+                (DAV_NAMESPACE, "remove") => false,
+                _ => return Err(bad_body()),
+            };
+            let prop = instruction.child(DAV_NAMESPACE, "prop").ok_or_else(bad_body)?;
+            updates.extend(prop.elements().map(|property| (is_set, property.clone())));
+        }
+
+        let is_live = |property: &XmlElement| property.namespace == DAV_NAMESPACE && LIVE_PROPERTIES.contains(&property.name.as_str());
+        let any_live = updates.iter().any(|(_, property)| is_live(property));
+        // The updates are applied together, so none are applied if any would fail
+        let is_stored = !any_live && update_dead_properties(&resource.virtual_host, &resource.path, &updates);
+
+        let mut statuses: Vec<(HttpStatusCode, Vec<String>)> = Vec::new();
+        for (_, property) in &updates {
+            let status_code = match (any_live, is_live(property), is_stored) {
+                (false, _, true) => HttpStatusCode::OK200,
+                (false, _, false) => HttpStatusCode::InsufficientStorage507,
+                (true, true, _) => HttpStatusCode::Forbidden403,
+                (true, false, _) => HttpStatusCode::FailedDependency424,
+            };
+            let xml = XmlElement::new(&property.namespace, &property.name).to_xml();
+            match statuses.iter_mut().find(|(existing_status_code, _)| *existing_status_code == status_code) {
+                None => statuses.push((status_code, vec!(xml))),
+                Some((_, properties)) => properties.push(xml),
+            }
+        }
+        let mut body = format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\"><D:response><D:href>{}</D:href>", escape::html(&resource.href));
+        for (status_code, properties) in statuses {
+            body.push_str(&property_status(&properties, status_code));
+        }
+        body.push_str("</D:response></D:multistatus>");
+        Ok(xml_response(http_request, HttpStatusCode::MultiStatus207, body))
+    }
+
+    fn lock(&self, http_request: &HttpRequest, resource: &Resource, tokens: &[String], header: &HttpHeader, body: &[u8]) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        let timeout = self.lock_timeout(header);
+        let lock_response = |status_code: HttpStatusCode, lock: &WebDavLock, http_header: HttpHeader| {
+            let body = format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:prop xmlns:D=\"DAV:\"><D:lockdiscovery>{}</D:lockdiscovery></D:prop>", lock.to_xml());
+            let mut http_response = xml_response(http_request, status_code, body);
+            for (field_name, value) in http_header.0 {
+                http_response.header.get_or_insert_with(HttpHeader::new).insert(field_name, &value);
+            }
+            http_response
+        };
+
+        // A lock is refreshed with an empty body and its token in the `If` field
+        if body.is_empty() {
+            let lock = WebDavLock::refresh(&resource.path, tokens, timeout)
+                .ok_or_else(|| failure(HttpStatusCode::PreconditionFailed412, "no lock to refresh"))?;
+            return Ok(lock_response(HttpStatusCode::OK200, &lock, HttpHeader::new()))
+        }
+
+        let bad_body = || failure(HttpStatusCode::BadRequest400, "`LOCK` body isn't valid");
+        let lock_info = parse_body(body).filter(|element| element.is(DAV_NAMESPACE, "lockinfo")).ok_or_else(bad_body)?;
+        let shared = match lock_info.child(DAV_NAMESPACE, "lockscope").and_then(|scope| scope.elements().next()) {
+            Some(scope) if scope.is(DAV_NAMESPACE, "exclusive") => false,
+            Some(scope) if scope.is(DAV_NAMESPACE, "shared") => true,
+            _ => return Err(bad_body()),
+        };
+        let lock_type = lock_info.child(DAV_NAMESPACE, "locktype").and_then(|lock_type| lock_type.elements().next());
+        if !lock_type.is_some_and(|lock_type| lock_type.is(DAV_NAMESPACE, "write")) {
+            return Err(bad_body())
+        }
+        let owner = lock_info.child(DAV_NAMESPACE, "owner").map(|owner| owner.to_xml());
+        let depth_infinity = header.get_value(&HttpFieldName::Depth).is_none_or(|depth| depth.trim() != "0");
+
+        // Locking a path that doesn't exist creates an empty file
+        let created = !resource.path.exists();
+        if created {
+            parent_of(&resource.path)?;
+            file::write_atomically(&resource.path, &[]).map_err(io_failure)?;
+        }
+        let lock = match WebDavLock::new(&resource.path, &resource.href, depth_infinity, shared, owner, timeout).acquire() {
+            None => {
+                if created {
+                    _ = fs::remove_file(&resource.path);
+                }
+                return Err(failure(HttpStatusCode::Locked423, "resource is already locked"))
+            },
+            Some(lock) => lock,
+        };
+        let mut http_header = HttpHeader::new();
+        http_header.insert(HttpFieldName::LockToken, &format!("<{}>", lock.token));
+        let status_code = match created {
+            false => HttpStatusCode::OK200,
+            true => HttpStatusCode::Created201,
+        };
+        Ok(lock_response(status_code, &lock, http_header))
+    }
+
+    fn unlock(http_request: &HttpRequest, resource: &Resource, header: &HttpHeader) -> Result<HttpResponse, (HttpStatusCode, Box<dyn Error>)> {
+        let token = header.get_value(&HttpFieldName::LockToken)
+            .map(|token| token.trim().trim_start_matches('<').trim_end_matches('>').to_owned())
+            .ok_or_else(|| failure(HttpStatusCode::BadRequest400, "`Lock-Token` is missing"))?;
+        match WebDavLock::release(&resource.path, &token) {
+            false => Err(failure(HttpStatusCode::Conflict409, "lock token doesn't match a lock on the resource")),
+            true => Ok(response(http_request, HttpStatusCode::NoContent204, HttpHeader::new(), None)),
+        }
+    }
+
+    /// Gets how long a lock is held for from the first timeout in the `Timeout` field, limited
+    /// to the `maximum_lock_timeout_seconds`. [`None`] means forever.
+    fn lock_timeout(&self, header: &HttpHeader) -> Option<Duration> {
+        let maximum = (self.maximum_lock_timeout_seconds > 0).then_some(self.maximum_lock_timeout_seconds);
+        let requested = header.get_value(&HttpFieldName::Timeout)
+            .and_then(|timeout| timeout.split(',').next())
+            .and_then(|timeout| timeout.trim().strip_prefix("Second-"))
+            .and_then(|seconds| seconds.parse::<u64>().ok());
+        let seconds = match (requested, maximum) {
+            (Some(requested), Some(maximum)) => Some(requested.min(maximum)),
+            (requested, None) => requested,
+            (None, maximum) => maximum,
+        };
+        seconds.map(Duration::from_secs)
+    }
+}
+
+/// Gets the live and dead properties of a resource, as their namespace, name and XML.
+fn properties(virtual_host: &str, path: &Path, metadata: &fs::Metadata) -> Vec<(String, String, String)> {
+    let mut live_properties = Vec::new();
+    live_properties.push(("resourcetype", match metadata.is_dir() {
+        false => String::new(),
+        true => "<D:collection/>".to_owned(),
+    }));
+    if !metadata.is_dir() {
+        live_properties.push(("getcontentlength", metadata.len().to_string()));
+        live_properties.push(("getetag", escape::html(&entity_tag(metadata))));
+    }
+    if let Ok(modified) = metadata.modified() {
+        live_properties.push(("getlastmodified", HttpDate(modified).to_string()));
+    }
+    let lock_entry = |scope: &str| format!("<D:lockentry><D:lockscope><D:{scope}/></D:lockscope><D:locktype><D:write/></D:locktype></D:lockentry>");
+    live_properties.push(("supportedlock", format!("{}{}", lock_entry("exclusive"), lock_entry("shared"))));
+    let locks: Vec<String> = WebDavLock::locks_on(path, false).iter().map(|lock| lock.to_xml()).collect();
+    live_properties.push(("lockdiscovery", locks.concat()));
+
+    let mut properties: Vec<(String, String, String)> = live_properties.into_iter()
+        .map(|(name, value)| (DAV_NAMESPACE.to_owned(), name.to_owned(), format!("<D:{name}>{value}</D:{name}>")))
+        .collect();
+    let dead_properties = DEAD_PROPERTIES.lock().unwrap_or_else(|error| error.into_inner());
+    properties.extend(dead_properties.iter()
+        .filter(|dead_property| dead_property.virtual_host == virtual_host && dead_property.path == path)
+        .map(|dead_property| (dead_property.property.namespace.clone(), dead_property.property.name.clone(), dead_property.property.to_xml())));
+    properties
+}
+
+/// Gets a `DAV:propstat` element for `properties`, or nothing if there are none.
+fn property_status(properties: &[String], status_code: HttpStatusCode) -> String {
+    if properties.is_empty() {
+        return String::new()
+    }
+    format!("<D:propstat><D:prop>{}</D:prop><D:status>HTTP/1.1 {status_code}</D:status></D:propstat>", properties.concat())
+}
+
+/// Sets or removes each of the dead properties in `updates` on the resource at `path`.
+///
+/// Returns `false`, without changing any properties, if the virtual host would have more than
+/// [`MAXIMUM_DEAD_PROPERTIES`], even after the properties of resources that no longer exist are removed.
+fn update_dead_properties(virtual_host: &str, path: &Path, updates: &[(bool, XmlElement)]) -> bool {
+    let mut dead_properties = DEAD_PROPERTIES.lock().unwrap_or_else(|error| error.into_inner());
+    let mut updated_properties = dead_properties.clone();
+    for (is_set, property) in updates {
+        updated_properties.retain(|existing| !(existing.virtual_host == virtual_host && existing.path == path && existing.property.is(&property.namespace, &property.name)));
+        if *is_set {
+            updated_properties.push(DeadProperty { virtual_host: virtual_host.to_owned(), path: path.to_path_buf(), property: property.clone() });
+        }
+    }
+    let count = |properties: &[DeadProperty]| properties.iter().filter(|dead_property| dead_property.virtual_host == virtual_host).count();
+    if count(&updated_properties) > MAXIMUM_DEAD_PROPERTIES {
+        // Resources can be deleted without WebDAV, which leaves their properties behind
+        updated_properties.retain(|dead_property| dead_property.virtual_host != virtual_host || dead_property.path == path || dead_property.path.exists());
+        if count(&updated_properties) > MAXIMUM_DEAD_PROPERTIES {
+            return false
+        }
+    }
+    *dead_properties = updated_properties;
+    true
+}
+
+/// Removes the dead properties of the resource at `path` and its descendants.
+fn remove_dead_properties(virtual_host: &str, path: &Path) {
+    DEAD_PROPERTIES.lock().unwrap_or_else(|error| error.into_inner())
+        .retain(|dead_property| !(dead_property.virtual_host == virtual_host && dead_property.path.starts_with(path)));
+}
+
+/// Copies the dead properties of `from` and its descendants to `to`, removing them from `from` if `is_move` is `true`.
+///
+/// Properties that would take the virtual host over [`MAXIMUM_DEAD_PROPERTIES`] aren't copied.
+fn copy_dead_properties(virtual_host: &str, from: &Path, to: &Path, is_move: bool) {
+    let mut dead_properties = DEAD_PROPERTIES.lock().unwrap_or_else(|error| error.into_inner());
+    let is_moved = |dead_property: &DeadProperty| dead_property.virtual_host == virtual_host && dead_property.path.starts_with(from);
+    let copied: Vec<DeadProperty> = dead_properties.iter()
+        .filter(|dead_property| is_moved(dead_property))
+        .filter_map(|dead_property| Some(DeadProperty { path: to.join(dead_property.path.strip_prefix(from).ok()?), ..dead_property.clone() }))
+        .collect();
+    if is_move {
+        dead_properties.retain(|dead_property| !is_moved(dead_property));
+    }
+    let count = dead_properties.iter().filter(|dead_property| dead_property.virtual_host == virtual_host).count();
+    dead_properties.extend(copied.into_iter().take(MAXIMUM_DEAD_PROPERTIES.saturating_sub(count)));
+}
+
+/// Copies the directory at `from` to `to`, and its contents if `depth_infinity` is `true`.
+///
+/// Symbolic links are skipped, as they could lead outside the document root.
+fn copy_directory(from: &Path, to: &Path, depth_infinity: bool) -> io::Result<()> {
+    fs::create_dir(to)?;
+    if !depth_infinity {
+        return Ok(())
+    }
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        // Unlike `fs::metadata`, the entry's file type doesn't follow symbolic links
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue
+        }
+        match file_type.is_dir() {
+            false => file::copy_atomically(&entry.path(), &destination)?,
+            true => copy_directory(&entry.path(), &destination, depth_infinity)?,
+        }
+    }
+    Ok(())
+}
+
+fn remove(path: &Path, is_directory: bool) -> io::Result<()> {
+    match is_directory {
+        false => fs::remove_file(path),
+        true => fs::remove_dir_all(path),
+    }
+}
+
+/// Gets the parent of `path`, which must be an existing directory.
+fn parent_of(path: &Path) -> Result<&Path, (HttpStatusCode, Box<dyn Error>)> {
+    path.parent()
+        .filter(|parent| parent.is_dir())
+        .ok_or_else(|| failure(HttpStatusCode::Conflict409, "parent collection doesn't exist"))
+}
+
+/// Gets the lock tokens given in the `If` field.
+fn submitted_tokens(http_request: &HttpRequest) -> Vec<String> {
+    let Some(condition) = http_request.header.as_ref().and_then(|header| header.get_value(&HttpFieldName::If)) else {
+        return Vec::new()
+    };
+    condition.split('<')
+        .filter_map(|part| part.split_once('>'))
+        .map(|(token, _)| token.trim().to_owned())
+        .filter(|token| token.starts_with("opaquelocktoken:"))
+        .collect()
+}
+
+fn parse_body(body: &[u8]) -> Option<XmlElement> {
+    std::str::from_utf8(body).ok()?.parse().ok()
+}
+
+/// Gets a strong entity tag from a file's size and modification time.
+fn entity_tag(metadata: &fs::Metadata) -> String {
+    let modified = metadata.modified().map_or(0, |modified| HttpDate(modified).seconds_since_epoch());
+    format!("\"{:x}-{modified:x}\"", metadata.len())
+}
+
+fn created_or_replaced(existed: bool) -> HttpStatusCode {
+    match existed {
+        false => HttpStatusCode::Created201,
+        true => HttpStatusCode::NoContent204,
+    }
+}
+
+fn response(http_request: &HttpRequest, status_code: HttpStatusCode, http_header: HttpHeader, body: Option<Vec<u8>>) -> HttpResponse {
+    HttpResponse {
+        version: http_request.version.clone().expect("`http_request.version` should be `Some`"),
+        status_code,
+        header: Some(http_header),
+        body,
+    }
+}
+
+fn xml_response(http_request: &HttpRequest, status_code: HttpStatusCode, body: String) -> HttpResponse {
+    let mut content_type = HttpContentType::new("application", "xml");
+    content_type.set_parameter("charset", "utf-8");
+    let mut http_header = HttpHeader::new();
+    http_header.insert_typed(HttpFieldName::ContentType, &content_type);
+    response(http_request, status_code, http_header, Some(body.into_bytes()))
+}
+
+fn failure(status_code: HttpStatusCode, message: &str) -> (HttpStatusCode, Box<dyn Error>) {
+    (status_code, Box::new(io::Error::other(message.to_owned())))
+}
+
+fn io_failure(error: io::Error) -> (HttpStatusCode, Box<dyn Error>) {
+    (HttpStatusCode::from_io_error(&error), Box::new(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helper::{base64, enums::Processing, file::TestDirectory, random}, http_parser::{HttpParserConfig, PartialHttpRequest}, server::VirtualHost};
+
+    /// Sends a request with the credentials of a test user to a [`WebDav`] location at `/dav/`.
+    fn respond(document_root: &Path, request_head: &str) -> HttpResponse {
+        let webdav = WebDav {
+            location: "/dav/".to_owned(),
+            users: HashMap::from([("user".to_owned(), "password".to_owned())]),
+            ..Default::default()
+        };
+        let virtual_host = VirtualHost { name: random::uuid(), ..Default::default() };
+        let site = VirtualHostMatch { virtual_host: &virtual_host, subdomain: String::new(), document_root: document_root.to_string_lossy().into_owned() };
+        let request_bytes = format!("{request_head}\r\nHost: example.com\r\nAuthorization: Basic {}\r\n\r\n", base64::encode(b"user:password"));
+        let http_request = match HttpRequest::try_parse(&HttpParserConfig::default(), &PartialHttpRequest::new(), request_bytes.as_bytes()) {
+            Processing::Finished(Ok(http_request)) => http_request,
+            _ => panic!("request should be parsed"),
+        };
+        let allowed_methods = [HttpMethod::Options, HttpMethod::Get, HttpMethod::Head];
+        webdav.respond(&site, &http_request, &allowed_methods).unwrap_or_else(|(http_response, _)| http_response)
+    }
+
+    #[test]
+    fn refuses_to_move_into_ancestor_or_descendant() {
+        let directory = TestDirectory::new();
+        fs::create_dir_all(directory.0.join("a").join("b")).expect("directory should be created");
+        fs::write(directory.0.join("a").join("b").join("file.txt"), "file").expect("file should be written");
+
+        for (source, destination) in [("/dav/a/b", "/dav/a"), ("/dav/a/b", "/dav/a/"), ("/dav/a", "/dav/a/b/c"), ("/dav/a", "/dav/a")] {
+            for method in ["MOVE", "COPY"] {
+                let http_response = respond(&directory.0, &format!("{method} {source} HTTP/1.1\r\nDestination: {destination}"));
+                assert_eq!(http_response.status_code, HttpStatusCode::Forbidden403, "{method} {source} to {destination}");
+            }
+        }
+        assert_eq!(fs::read_to_string(directory.0.join("a").join("b").join("file.txt")).ok().as_deref(), Some("file"));
+
+        let http_response = respond(&directory.0, "MOVE /dav/a/b HTTP/1.1\r\nDestination: /dav/b");
+        assert_eq!(http_response.status_code, HttpStatusCode::Created201);
+        assert!(directory.0.join("b").join("file.txt").is_file());
+    }
+
+    #[test]
+    fn copies_directory() {
+        let directory = TestDirectory::new();
+        let from = directory.0.join("from");
+        fs::create_dir_all(from.join("nested")).expect("directory should be created");
+        fs::write(from.join("a.txt"), "a").expect("file should be written");
+        fs::write(from.join("nested").join("b.txt"), "b").expect("file should be written");
+
+        copy_directory(&from, &directory.0.join("shallow"), false).expect("directory should be copied");
+        assert_eq!(fs::read_dir(directory.0.join("shallow")).expect("copy should exist").count(), 0);
+        copy_directory(&from, &directory.0.join("deep"), true).expect("directory should be copied");
+        assert_eq!(fs::read_to_string(directory.0.join("deep").join("nested").join("b.txt")).ok().as_deref(), Some("b"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_symbolic_links_when_copying_directory() {
+        let directory = TestDirectory::new();
+        let from = directory.0.join("from");
+        let outside = directory.0.join("outside");
+        fs::create_dir_all(&from).expect("directory should be created");
+        fs::create_dir_all(&outside).expect("directory should be created");
+        fs::write(outside.join("secret.txt"), "secret").expect("file should be written");
+        std::os::unix::fs::symlink(outside.join("secret.txt"), from.join("file-link")).expect("link should be created");
+        std::os::unix::fs::symlink(&outside, from.join("directory-link")).expect("link should be created");
+
+        let to = directory.0.join("to");
+        copy_directory(&from, &to, true).expect("directory should be copied");
+        assert!(!to.join("file-link").exists());
+        assert!(!to.join("directory-link").exists());
+    }
+
+    fn dead_property_names(virtual_host: &str, path: &Path) -> Vec<String> {
+        let metadata = fs::metadata(std::env::temp_dir()).expect("temporary directory should exist");
+        properties(virtual_host, path, &metadata).into_iter()
+            .filter(|(namespace, _, _)| namespace != DAV_NAMESPACE)
+            .map(|(_, name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_dead_properties_for_each_virtual_host() {
+        let (first_host, second_host) = (random::uuid(), random::uuid());
+        let path = Path::new("/dead-properties/resource");
+        let property = XmlElement::new("urn:test", "colour");
+        assert!(update_dead_properties(&first_host, path, &[(true, property.clone())]));
+        assert_eq!(dead_property_names(&first_host, path), vec!("colour"));
+        assert!(dead_property_names(&second_host, path).is_empty());
+
+        assert!(update_dead_properties(&first_host, path, &[(false, property)]));
+        assert!(dead_property_names(&first_host, path).is_empty());
+    }
+
+    #[test]
+    fn removes_dead_properties_with_resource() {
+        let virtual_host = random::uuid();
+        let directory = Path::new("/dead-properties/directory");
+        let member = directory.join("member");
+        assert!(update_dead_properties(&virtual_host, &member, &[(true, XmlElement::new("urn:test", "a"))]));
+        remove_dead_properties(&virtual_host, directory);
+        assert!(dead_property_names(&virtual_host, &member).is_empty());
+    }
+
+    #[test]
+    fn copies_and_moves_dead_properties() {
+        let virtual_host = random::uuid();
+        let (from, copied, moved) = (Path::new("/dead-properties/from"), Path::new("/dead-properties/copied"), Path::new("/dead-properties/moved"));
+        assert!(update_dead_properties(&virtual_host, &from.join("member"), &[(true, XmlElement::new("urn:test", "a"))]));
+
+        copy_dead_properties(&virtual_host, from, copied, false);
+        assert_eq!(dead_property_names(&virtual_host, &from.join("member")), vec!("a"));
+        assert_eq!(dead_property_names(&virtual_host, &copied.join("member")), vec!("a"));
+
+        copy_dead_properties(&virtual_host, from, moved, true);
+        assert!(dead_property_names(&virtual_host, &from.join("member")).is_empty());
+        assert_eq!(dead_property_names(&virtual_host, &moved.join("member")), vec!("a"));
+    }
+
+    #[test]
+    fn limits_dead_properties_for_each_virtual_host() {
+        let directory = TestDirectory::new();
+        let virtual_host = random::uuid();
+        DEAD_PROPERTIES.lock().unwrap_or_else(|error| error.into_inner()).extend((0..MAXIMUM_DEAD_PROPERTIES).map(|index| DeadProperty {
+            virtual_host: virtual_host.clone(),
+            path: directory.0.clone(),
+            property: XmlElement::new("urn:test", &format!("p{index}")),
+        }));
+        let extra = [(true, XmlElement::new("urn:test", "extra"))];
+        assert!(!update_dead_properties(&virtual_host, &directory.0, &extra));
+        assert!(update_dead_properties(&virtual_host, &directory.0, &[(true, XmlElement::new("urn:test", "p0"))]));
+        assert!(update_dead_properties(&random::uuid(), &directory.0, &extra));
+
+        // The properties of a resource that no longer exists make room for new ones
+        drop(directory);
+        let directory = TestDirectory::new();
+        assert!(update_dead_properties(&virtual_host, &directory.0, &extra));
+    }
+}
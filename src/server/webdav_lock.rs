@@ -0,0 +1,157 @@
+use std::{path::{Path, PathBuf}, sync::Mutex, time::{Duration, Instant}};
+
+use crate::helper::{escape, random};
+
+/// The locks that are held, which are kept in memory, so they are released when the server stops.
+static LOCKS: Mutex<Vec<WebDavLock>> = Mutex::new(Vec::new());
+
+/// A WebDAV write lock on a resource, and on its descendants if it has infinite depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebDavLock {
+    /// The `opaquelocktoken:` URI that identifies the lock.
+    pub token: String,
+    /// The full path of the locked resource.
+    pub path: PathBuf,
+    /// The path the resource was locked with, which is the lock's root.
+    pub href: String,
+    pub depth_infinity: bool,
+    pub shared: bool,
+    /// The `owner` element given by the client, as XML.
+    pub owner: Option<String>,
+    pub timeout: Option<Duration>,
+    expires: Option<Instant>,
+}
+
+impl WebDavLock {
+    /// Creates a lock, which is held forever if it has no `timeout` or the timeout is too long to be measured.
+    pub fn new(path: &Path, href: &str, depth_infinity: bool, shared: bool, owner: Option<String>, timeout: Option<Duration>) -> Self {
+        let (timeout, expires) = expiry(timeout);
+        WebDavLock {
+            token: format!("opaquelocktoken:{}", random::uuid()),
+            path: path.to_owned(),
+            href: href.to_owned(),
+            depth_infinity,
+            shared,
+            owner,
+            timeout,
+            expires,
+        }
+    }
+
+    /// Returns `true` if the lock applies to the resource at `path`.
+    pub fn covers(&self, path: &Path) -> bool {
+        self.path == path || (self.depth_infinity && path.starts_with(&self.path))
+    }
+
+    /// Gets the locks that apply to `path`, and to its descendants if `include_descendants` is `true`.
+    pub fn locks_on(path: &Path, include_descendants: bool) -> Vec<WebDavLock> {
+        Self::locks().iter()
+            .filter(|lock| lock.covers(path) || (include_descendants && lock.path.starts_with(path)))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns `true` if every lock on the `paths` has one of the `tokens`, so the client may change them.
+    pub fn may_change(paths: &[(&Path, bool)], tokens: &[String]) -> bool {
+        paths.iter().all(|(path, include_descendants)| {
+            Self::locks_on(path, *include_descendants).iter().all(|lock| tokens.contains(&lock.token))
+        })
+    }
+
+    /// Takes the lock, returning [`None`] if it conflicts with a lock that is held.
+    ///
+    /// An exclusive lock conflicts with any other lock on the same resources, and a shared lock
+    /// conflicts with exclusive locks.
+    pub fn acquire(self) -> Option<WebDavLock> {
+        let mut locks = Self::locks();
+        let conflicts = locks.iter().any(|lock| {
+            let overlaps = lock.covers(&self.path) || (self.depth_infinity && lock.path.starts_with(&self.path));
+            overlaps && !(lock.shared && self.shared)
+        });
+        if conflicts {
+            return None
+        }
+        locks.push(self.clone());
+        Some(self)
+    }
+
+    /// Restarts the timeout of the lock with one of the `tokens` that applies to `path`.
+    pub fn refresh(path: &Path, tokens: &[String], timeout: Option<Duration>) -> Option<WebDavLock> {
+        let mut locks = Self::locks();
+        let lock = locks.iter_mut().find(|lock| lock.covers(path) && tokens.contains(&lock.token))?;
+        (lock.timeout, lock.expires) = expiry(timeout);
+        Some(lock.clone())
+    }
+
+    /// Releases the lock with `token` that applies to `path`, returning `false` if there isn't one.
+    pub fn release(path: &Path, token: &str) -> bool {
+        let mut locks = Self::locks();
+        let length = locks.len();
+        locks.retain(|lock| !(lock.token == token && lock.covers(path)));
+        locks.len() < length
+    }
+
+    /// Releases every lock on `path` and its descendants, such as after it is deleted.
+    pub fn release_all(path: &Path) {
+        Self::locks().retain(|lock| !lock.path.starts_with(path));
+    }
+
+    /// Writes the lock as a `DAV:activelock` element, using the `D` prefix.
+    pub fn to_xml(&self) -> String {
+        let scope = match self.shared {
+            false => "exclusive",
+            true => "shared",
+        };
+        let depth = match self.depth_infinity {
+            false => "0",
+            true => "infinity",
+        };
+        let timeout = match self.timeout {
+            None => "Infinite".to_owned(),
+            Some(timeout) => format!("Second-{}", timeout.as_secs()),
+        };
+        format!(
+            "<D:activelock><D:locktype><D:write/></D:locktype><D:lockscope><D:{scope}/></D:lockscope><D:depth>{depth}</D:depth>{}<D:timeout>{timeout}</D:timeout><D:locktoken><D:href>{}</D:href></D:locktoken><D:lockroot><D:href>{}</D:href></D:lockroot></D:activelock>",
+            self.owner.as_deref().unwrap_or_default(),
+            escape::html(&self.token),
+            escape::html(&self.href),
+        )
+    }
+
+    /// Gets the locks that are held, after removing those that have expired.
+    fn locks() -> std::sync::MutexGuard<'static, Vec<WebDavLock>> {
+        let mut locks = LOCKS.lock().unwrap_or_else(|error| error.into_inner());
+        let now = Instant::now();
+        locks.retain(|lock| lock.expires.is_none_or(|expires| expires > now));
+        locks
+    }
+}
+
+/// Gets the timeout of a lock and when it expires, treating a timeout that overflows as infinite.
+fn expiry(timeout: Option<Duration>) -> (Option<Duration>, Option<Instant>) {
+    match timeout.and_then(|timeout| Instant::now().checked_add(timeout)) {
+        None => (None, None),
+        Some(expires) => (timeout, Some(expires)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn treats_overflowing_timeout_as_infinite() {
+        let path = Path::new("/webdav-lock").join(random::uuid());
+        let lock = WebDavLock::new(&path, "/dav/file", false, false, None, Some(Duration::from_secs(u64::MAX)));
+        assert_eq!(lock.timeout, None);
+        assert!(lock.to_xml().contains("<D:timeout>Infinite</D:timeout>"));
+
+        let lock = lock.acquire().expect("lock should be acquired");
+        let tokens = [lock.token.clone()];
+        let refreshed = WebDavLock::refresh(&path, &tokens, Some(Duration::from_secs(60))).expect("lock should be refreshed");
+        assert_eq!(refreshed.timeout, Some(Duration::from_secs(60)));
+        let refreshed = WebDavLock::refresh(&path, &tokens, Some(Duration::MAX)).expect("lock should be refreshed");
+        assert_eq!(refreshed.timeout, None);
+        assert!(WebDavLock::release(&path, &lock.token));
+    }
+}